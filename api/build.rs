@@ -2,16 +2,31 @@ use std::{error::Error, path::PathBuf};
 
 use bomboni_prost::config::{ApiConfig, CompileConfig};
 
+/// Domain of type URLs, e.g. `type.sky.dev/sky.v1.User`, overridable at build time.
+const TYPE_URL_DOMAIN_VAR: &str = "SKY_TYPE_URL_DOMAIN";
+const DEFAULT_TYPE_URL_DOMAIN: &str = "type.sky.dev";
+/// Domain of `ErrorInfo` details, the type URL domain without its `type.` label.
+const ERROR_DOMAIN_VAR: &str = "SKY_ERROR_DOMAIN";
+
 fn main() -> Result<(), Box<dyn Error>> {
     let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
     let fd_path = out_dir.join("sky_fd.bin");
 
+    let type_url_domain =
+        std::env::var(TYPE_URL_DOMAIN_VAR).unwrap_or_else(|_| DEFAULT_TYPE_URL_DOMAIN.into());
+    println!("cargo:rerun-if-env-changed={}", TYPE_URL_DOMAIN_VAR);
+    let error_domain = type_url_domain
+        .strip_prefix("type.")
+        .unwrap_or(&type_url_domain);
+    println!("cargo:rustc-env={}={}", ERROR_DOMAIN_VAR, error_domain);
+
     let root_path = PathBuf::from("./proto");
     let proto_paths: Vec<_> = [
-        "sky/resources.proto",
-        "sky/user_service.proto",
-        "sky/post_service.proto",
-        "sky/error/error.proto",
+        "sky/v1/resources.proto",
+        "sky/v1/user_service.proto",
+        "sky/v1/post_service.proto",
+        "sky/v1/api_key_service.proto",
+        "sky/v1/error/error.proto",
     ]
     .into_iter()
     .map(|proto_path| root_path.join(proto_path))
@@ -20,17 +35,23 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut prost_config = prost_build::Config::default();
     prost_config
         .file_descriptor_set_path(&fd_path)
-        .type_name_domain(&["."], "example.com")
+        .type_name_domain(&["."], &type_url_domain)
         .enable_type_names()
         .protoc_arg("--experimental_allow_proto3_optional");
 
     // Regression? https://github.com/tokio-rs/prost/issues/932
-    for type_name in ["Timestamp"] {
+    for type_name in ["Timestamp", "FieldMask"] {
         prost_config.extern_path(
             format!(".google.protobuf.{type_name}"),
             format!("::bomboni_proto::google::protobuf::{type_name}"),
         );
     }
+    for type_name in ["Status"] {
+        prost_config.extern_path(
+            format!(".google.rpc.{type_name}"),
+            format!("::bomboni_proto::google::rpc::{type_name}"),
+        );
+    }
 
     tonic_build::configure()
         // .file_descriptor_set_path(&fd_path)
@@ -41,7 +62,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     bomboni_prost::compile(CompileConfig {
         api: ApiConfig {
-            domain: Some("example.com".into()),
+            domain: Some(type_url_domain),
             helpers_mod: Some("helpers".into()),
             ..Default::default()
         },
@@ -1,27 +1,73 @@
 use bomboni_common::{btree_map_into, date_time::UtcDateTime, id::Id};
 use bomboni_request::{
     derive::Parse,
-    error::RequestResult,
-    parse::helpers::id_convert,
+    error::{
+        CommonError, GenericErrorBox, PathError, PathErrorStep, RequestError, RequestResult,
+    },
+    parse::{helpers::id_convert, RequestParse},
     query::list::ListQuery,
     schema::{FieldMemberSchema, Schema, ValueType},
 };
 use prost::Name;
+use regex::Regex;
+use std::sync::LazyLock;
 
 use crate::error::UserError;
 use crate::proto::{
-    user_error::UserErrorReason, ListPostsRequest, Post, PostRequest, SignUpRequest, User,
+    user_error::UserErrorReason, BatchGetPostsRequest, BatchGetUsersRequest, ChangePasswordRequest,
+    CreateApiKeyRequest, ExchangeOidcTokenRequest, GetUserByNameRequest, GetUserRequest,
+    ListPostsRequest, ListUsersRequest, Post, PostRequest, RevokeApiKeyRequest, SignInRequest,
+    SignUpRequest, UnlockAccountRequest, UpdateProfileRequest, User,
 };
+use crate::user_name::{
+    canonicalize_user_name, is_single_script_user_name, MAX_CANONICAL_USER_NAME_LENGTH,
+};
+
+pub const POST_NAME_PREFIX: &str = "posts/";
+pub const USER_NAME_PREFIX: &str = "users/";
+pub const API_KEY_NAME_PREFIX: &str = "apiKeys/";
+pub const MAX_BATCH_GET_SIZE: usize = 100;
+
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 50;
+pub const MAX_BIO_LENGTH: usize = 160;
+pub const MAX_LOCATION_LENGTH: usize = 30;
+pub const MAX_URL_LENGTH: usize = 255;
+pub const MAX_API_KEY_SCOPES: usize = 16;
+
+static USER_NAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\p{L}[\p{L}\p{N}_]{2,15}$"#).unwrap());
+static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^https?://\S+$"#).unwrap());
 
 #[derive(Debug, Clone, Parse)]
 #[parse(source = SignUpRequest, request, write)]
 pub struct SignUpRequestDto {
-    #[parse(regex = r#"^[a-zA-Z][a-zA-Z0-9_]{2,15}$"#)]
+    #[parse(convert = user_name_convert)]
     pub name: String,
-    #[parse(convert = parse_password)]
+    /// Checked against the password policy by the service.
     pub password: String,
 }
 
+#[derive(Debug, Clone, Parse)]
+#[parse(source = SignInRequest, request, write)]
+pub struct SignInRequestDto {
+    pub name: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = ExchangeOidcTokenRequest, request, write)]
+pub struct ExchangeOidcTokenRequestDto {
+    /// Verified against the identity provider by the service.
+    pub id_token: String,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = UnlockAccountRequest, request, write)]
+pub struct UnlockAccountRequestDto {
+    #[parse(convert = user_name_convert)]
+    pub name: String,
+}
+
 #[derive(Debug, Clone, Parse)]
 #[parse(source = PostRequest, request, write)]
 pub struct PostRequestDto {
@@ -35,12 +81,92 @@ pub struct ListPostsRequestDto {
     pub query: ListQuery,
 }
 
+#[derive(Debug, Clone, Parse)]
+#[parse(source = GetUserRequest, request, write)]
+pub struct GetUserRequestDto {
+    #[parse(convert = parse_user_resource_name)]
+    pub name: Id,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = GetUserByNameRequest, request, write)]
+pub struct GetUserByNameRequestDto {
+    #[parse(convert = user_name_convert)]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = ListUsersRequest, request, write)]
+pub struct ListUsersRequestDto {
+    #[parse(list_query)]
+    pub query: ListQuery,
+    pub name_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = BatchGetPostsRequest, request, write)]
+pub struct BatchGetPostsRequestDto {
+    #[parse(convert = parse_post_names)]
+    pub names: Vec<Id>,
+    pub allow_missing: bool,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = BatchGetUsersRequest, request, write)]
+pub struct BatchGetUsersRequestDto {
+    #[parse(convert = parse_user_names)]
+    pub names: Vec<Id>,
+    pub allow_missing: bool,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = ChangePasswordRequest, request, write)]
+pub struct ChangePasswordRequestDto {
+    pub current_password: Option<String>,
+    /// Checked against the password policy by the service.
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = CreateApiKeyRequest, request, write)]
+pub struct CreateApiKeyRequestDto {
+    #[parse(convert = api_key_display_name_convert)]
+    pub display_name: String,
+    /// Checked against the permissions of the user by the service.
+    #[parse(convert = api_key_scopes_convert)]
+    pub scopes: Vec<String>,
+    #[parse(timestamp)]
+    pub expire_time: Option<UtcDateTime>,
+}
+
+#[derive(Debug, Clone, Parse)]
+#[parse(source = RevokeApiKeyRequest, request, write)]
+pub struct RevokeApiKeyRequestDto {
+    #[parse(convert = parse_api_key_resource_name)]
+    pub name: Id,
+}
+
+/// Profile fields set to `Some` are updated, and empty values clear the field.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateProfileRequestDto {
+    pub user_id: Option<Id>,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+}
+
 #[derive(Debug, Clone, Parse)]
 #[parse(source = User, write)]
 pub struct UserDto {
     #[parse(convert = id_convert)]
     pub id: Id,
     pub name: String,
+    #[parse(timestamp)]
+    pub create_time: Option<UtcDateTime>,
+    #[parse(timestamp)]
+    pub update_time: Option<UtcDateTime>,
 }
 
 #[derive(Debug, Clone, Parse)]
@@ -53,21 +179,333 @@ pub struct PostDto {
     pub content: String,
 }
 
-mod parse_password {
+/// Validates the format of a user name, rejecting names that mix scripts
+/// or whose canonical form is too long to store.
+mod user_name_convert {
+    use super::*;
+
+    pub fn parse(name: String) -> RequestResult<String> {
+        if name.is_empty() {
+            return Err(CommonError::RequiredFieldMissing.into());
+        }
+        if !USER_NAME_REGEX.is_match(&name) {
+            return Err(CommonError::InvalidStringFormat {
+                expected_format: USER_NAME_REGEX.to_string(),
+            }
+            .into());
+        }
+        if !is_single_script_user_name(&name) {
+            return Err(UserError::new(UserErrorReason::InvalidName)
+                .with_user_name(&name)
+                .with_canonical_name(&canonicalize_user_name(&name))
+                .with_message("user name mixes scripts")
+                .into());
+        }
+        let canonical_name = canonicalize_user_name(&name);
+        if canonical_name.chars().count() > MAX_CANONICAL_USER_NAME_LENGTH {
+            return Err(UserError::new(UserErrorReason::InvalidName)
+                .with_user_name(&name)
+                .with_canonical_name(&canonical_name)
+                .with_message("canonical user name is too long")
+                .into());
+        }
+        Ok(name)
+    }
+
+    pub fn write(name: String) -> String {
+        name
+    }
+}
+
+mod api_key_display_name_convert {
+    use super::*;
+
+    pub fn parse(display_name: String) -> RequestResult<String> {
+        let display_name = display_name.trim();
+        if display_name.is_empty() {
+            return Err(CommonError::RequiredFieldMissing.into());
+        }
+        if display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH
+            || display_name.chars().any(char::is_control)
+        {
+            return Err(CommonError::InvalidDisplayName.into());
+        }
+        Ok(display_name.into())
+    }
+
+    pub fn write(display_name: String) -> String {
+        display_name
+    }
+}
+
+mod api_key_scopes_convert {
+    use super::*;
+
+    pub fn parse(mut scopes: Vec<String>) -> RequestResult<Vec<String>> {
+        if scopes.is_empty() {
+            return Err(CommonError::RequiredFieldMissing.into());
+        }
+        scopes.sort();
+        scopes.dedup();
+        if scopes.len() > MAX_API_KEY_SCOPES {
+            return Err(CommonError::NumericOutOfRange.into());
+        }
+        Ok(scopes)
+    }
+
+    pub fn write(scopes: Vec<String>) -> Vec<String> {
+        scopes
+    }
+}
+
+mod parse_post_names {
     use super::*;
 
-    pub fn parse(password: String) -> RequestResult<String> {
-        if password.len() < 6 {
-            return Err(UserError::new(UserErrorReason::InvalidPassword).into());
+    pub fn parse(names: Vec<String>) -> RequestResult<Vec<Id>> {
+        parse_names(names, POST_NAME_PREFIX)
+    }
+
+    pub fn write(ids: Vec<Id>) -> Vec<String> {
+        ids.into_iter().map(make_post_name).collect()
+    }
+}
+
+mod parse_user_names {
+    use super::*;
+
+    pub fn parse(names: Vec<String>) -> RequestResult<Vec<Id>> {
+        parse_names(names, USER_NAME_PREFIX)
+    }
+
+    pub fn write(ids: Vec<Id>) -> Vec<String> {
+        ids.into_iter().map(make_user_name).collect()
+    }
+}
+
+mod parse_user_resource_name {
+    use super::*;
+
+    pub fn parse(name: String) -> RequestResult<Id> {
+        parse_user_name(&name).ok_or_else(|| {
+            CommonError::InvalidName {
+                expected_format: format!("{}{{id}}", USER_NAME_PREFIX),
+                name,
+            }
+            .into()
+        })
+    }
+
+    pub fn write(id: Id) -> String {
+        make_user_name(id)
+    }
+}
+
+mod parse_api_key_resource_name {
+    use super::*;
+
+    pub fn parse(name: String) -> RequestResult<Id> {
+        parse_api_key_name(&name).ok_or_else(|| {
+            CommonError::InvalidName {
+                expected_format: format!("{}{{id}}", API_KEY_NAME_PREFIX),
+                name,
+            }
+            .into()
+        })
+    }
+
+    pub fn write(id: Id) -> String {
+        make_api_key_name(id)
+    }
+}
+
+fn parse_names(names: Vec<String>, prefix: &str) -> RequestResult<Vec<Id>> {
+    if names.is_empty() {
+        return Err(CommonError::RequiredFieldMissing.into());
+    }
+    if names.len() > MAX_BATCH_GET_SIZE {
+        return Err(CommonError::NumericOutOfRange.into());
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            parse_resource_name(&name, prefix).ok_or_else(|| {
+                CommonError::InvalidName {
+                    expected_format: format!("{}{{id}}", prefix),
+                    name,
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+fn parse_resource_name(name: &str, prefix: &str) -> Option<Id> {
+    name.strip_prefix(prefix)?.parse().ok()
+}
+
+/// Checks the same rules as user names of sign-up requests.
+pub fn is_valid_user_name(name: &str) -> bool {
+    USER_NAME_REGEX.is_match(name)
+        && is_single_script_user_name(name)
+        && canonicalize_user_name(name).chars().count() <= MAX_CANONICAL_USER_NAME_LENGTH
+}
+
+pub fn parse_post_name(name: &str) -> Option<Id> {
+    parse_resource_name(name, POST_NAME_PREFIX)
+}
+
+pub fn make_post_name(id: Id) -> String {
+    format!("{}{}", POST_NAME_PREFIX, id)
+}
+
+pub fn parse_user_name(name: &str) -> Option<Id> {
+    parse_resource_name(name, USER_NAME_PREFIX)
+}
+
+pub fn make_user_name(id: Id) -> String {
+    format!("{}{}", USER_NAME_PREFIX, id)
+}
+
+pub fn parse_api_key_name(name: &str) -> Option<Id> {
+    parse_resource_name(name, API_KEY_NAME_PREFIX)
+}
+
+pub fn make_api_key_name(id: Id) -> String {
+    format!("{}{}", API_KEY_NAME_PREFIX, id)
+}
+
+impl RequestParse<UpdateProfileRequest> for UpdateProfileRequestDto {
+    fn parse(request: UpdateProfileRequest) -> RequestResult<Self> {
+        let Some(user) = request.user else {
+            return Err(RequestError::BadRequest {
+                name: UpdateProfileRequest::NAME.into(),
+                violations: vec![PathError {
+                    path: vec![PathErrorStep::Field("user".into())],
+                    error: Box::new(CommonError::RequiredFieldMissing),
+                }],
+            });
+        };
+
+        let mut violations = Vec::new();
+        let mut dto = UpdateProfileRequestDto::default();
+
+        if !user.id.is_empty() {
+            match user.id.parse() {
+                Ok(user_id) => dto.user_id = Some(user_id),
+                Err(_) => violations.push(make_profile_violation(
+                    "id",
+                    Box::new(CommonError::InvalidId),
+                )),
+            }
         }
-        Ok(password)
+
+        let paths: Vec<String> = match request.update_mask {
+            Some(update_mask) if !update_mask.paths.is_empty() => update_mask.paths,
+            _ => [
+                ("display_name", &user.display_name),
+                ("bio", &user.bio),
+                ("avatar_url", &user.avatar_url),
+                ("location", &user.location),
+                ("website", &user.website),
+            ]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(path, _)| path.into())
+            .collect(),
+        };
+
+        for path in paths {
+            match path.as_str() {
+                "display_name" => {
+                    let display_name = user.display_name.trim();
+                    if display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH
+                        || display_name.chars().any(char::is_control)
+                    {
+                        violations.push(make_profile_violation(
+                            "display_name",
+                            Box::new(CommonError::InvalidDisplayName),
+                        ));
+                    }
+                    dto.display_name = Some(display_name.into());
+                }
+                "bio" => {
+                    if user.bio.chars().count() > MAX_BIO_LENGTH {
+                        violations.push(make_profile_violation(
+                            "bio",
+                            Box::new(
+                                UserError::new(UserErrorReason::BioTooLong)
+                                    .with_max_length(MAX_BIO_LENGTH),
+                            ),
+                        ));
+                    }
+                    dto.bio = Some(user.bio.clone());
+                }
+                "avatar_url" => {
+                    if !is_valid_url(&user.avatar_url) {
+                        violations.push(make_profile_violation(
+                            "avatar_url",
+                            Box::new(UserError::new(UserErrorReason::InvalidAvatarUrl)),
+                        ));
+                    }
+                    dto.avatar_url = Some(user.avatar_url.clone());
+                }
+                "location" => {
+                    let location = user.location.trim();
+                    if location.chars().count() > MAX_LOCATION_LENGTH {
+                        violations.push(make_profile_violation(
+                            "location",
+                            Box::new(
+                                UserError::new(UserErrorReason::LocationTooLong)
+                                    .with_max_length(MAX_LOCATION_LENGTH),
+                            ),
+                        ));
+                    }
+                    dto.location = Some(location.into());
+                }
+                "website" => {
+                    if !is_valid_url(&user.website) {
+                        violations.push(make_profile_violation(
+                            "website",
+                            Box::new(UserError::new(UserErrorReason::InvalidWebsite)),
+                        ));
+                    }
+                    dto.website = Some(user.website.clone());
+                }
+                _ => violations.push(PathError {
+                    path: vec![PathErrorStep::Field("update_mask".into())],
+                    error: Box::new(CommonError::InvalidName {
+                        expected_format: "display_name|bio|avatar_url|location|website".into(),
+                        name: path,
+                    }),
+                }),
+            }
+        }
+
+        if !violations.is_empty() {
+            return Err(RequestError::BadRequest {
+                name: UpdateProfileRequest::NAME.into(),
+                violations,
+            });
+        }
+        Ok(dto)
     }
+}
 
-    pub fn write(password: String) -> String {
-        password
+fn make_profile_violation(field: &str, error: GenericErrorBox) -> PathError {
+    PathError {
+        path: vec![
+            PathErrorStep::Field("user".into()),
+            PathErrorStep::Field(field.into()),
+        ],
+        error,
     }
 }
 
+/// Empty URLs are valid and clear the field.
+fn is_valid_url(url: &str) -> bool {
+    url.is_empty() || (url.len() <= MAX_URL_LENGTH && URL_REGEX.is_match(url))
+}
+
 impl PostDto {
     pub fn get_schema() -> Schema {
         Schema {
@@ -80,8 +518,21 @@ impl PostDto {
     }
 }
 
+impl UserDto {
+    pub fn get_schema() -> Schema {
+        Schema {
+            members: btree_map_into!(
+                "id" => FieldMemberSchema::new_ordered(ValueType::String),
+                "name" => FieldMemberSchema::new_ordered(ValueType::String),
+                "createTime" => FieldMemberSchema::new_ordered(ValueType::Timestamp),
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use bomboni_proto::google::protobuf::FieldMask;
     use bomboni_request::{
         error::{CommonError, RequestError},
         parse::RequestParse,
@@ -128,14 +579,130 @@ mod tests {
         assert!(matches!(
             SignUpRequestDto::parse(SignUpRequest {
                 name: "tester".into(),
-                password: "abc".into(),
+                password: "".into(),
             }).unwrap_err(),
             RequestError::BadRequest { name, violations }
             if name == SignUpRequest::NAME
                 && matches!(
-                    violations[0].error.as_any().downcast_ref::<UserError>().unwrap().reason,
-                    UserErrorReason::InvalidPassword,
+                    violations[0].error.as_any().downcast_ref::<CommonError>().unwrap(),
+                    CommonError::RequiredFieldMissing,
                 ) && violations[0].path_to_string() == "password",
         ));
+
+        assert!(matches!(
+            SignUpRequestDto::parse(SignUpRequest {
+                name: "t\u{435}ster".into(),
+                password: "123456".into(),
+            }).unwrap_err(),
+            RequestError::BadRequest { name, violations }
+            if name == SignUpRequest::NAME
+                && matches!(
+                    violations[0].error.as_any().downcast_ref::<UserError>().unwrap().reason,
+                    UserErrorReason::InvalidName,
+                ) && violations[0].path_to_string() == "name",
+        ));
+
+        // Each `ﷺ` is a single letter that canonicalizes into 18 characters
+        let name = "\u{fdfa}".repeat(16);
+        assert!(!is_valid_user_name(&name));
+        assert!(matches!(
+            SignUpRequestDto::parse(SignUpRequest {
+                name,
+                password: "123456".into(),
+            }).unwrap_err(),
+            RequestError::BadRequest { name, violations }
+            if name == SignUpRequest::NAME
+                && matches!(
+                    violations[0].error.as_any().downcast_ref::<UserError>().unwrap().reason,
+                    UserErrorReason::InvalidName,
+                ) && violations[0].path_to_string() == "name",
+        ));
+    }
+
+    #[test]
+    fn parse_batch_get_posts() {
+        let ids = [Id::generate(), Id::generate()];
+        let parsed = BatchGetPostsRequestDto::parse(BatchGetPostsRequest {
+            names: ids.iter().copied().map(make_post_name).collect(),
+            allow_missing: true,
+        })
+        .unwrap();
+        assert_eq!(parsed.names, ids);
+        assert!(parsed.allow_missing);
+
+        assert!(matches!(
+            BatchGetPostsRequestDto::parse(BatchGetPostsRequest {
+                names: vec![make_user_name(ids[0])],
+                allow_missing: false,
+            }).unwrap_err(),
+            RequestError::BadRequest { name, violations }
+            if name == BatchGetPostsRequest::NAME
+                && matches!(
+                    violations[0].error.as_any().downcast_ref::<CommonError>().unwrap(),
+                    CommonError::InvalidName { .. },
+                ) && violations[0].path_to_string() == "names",
+        ));
+    }
+
+    #[test]
+    fn parse_update_profile() {
+        let parsed = UpdateProfileRequestDto::parse(UpdateProfileRequest {
+            user: Some(User {
+                display_name: " Tester ".into(),
+                bio: "Hello".into(),
+                ..Default::default()
+            }),
+            update_mask: None,
+        })
+        .unwrap();
+        assert_eq!(parsed.display_name.as_deref(), Some("Tester"));
+        assert_eq!(parsed.bio.as_deref(), Some("Hello"));
+        assert!(parsed.website.is_none());
+
+        assert!(matches!(
+            UpdateProfileRequestDto::parse(UpdateProfileRequest {
+                user: Some(User {
+                    bio: "a".repeat(MAX_BIO_LENGTH + 1),
+                    ..Default::default()
+                }),
+                update_mask: Some(FieldMask {
+                    paths: vec!["bio".into(), "website".into()],
+                }),
+            }).unwrap_err(),
+            RequestError::BadRequest { name, violations }
+            if name == UpdateProfileRequest::NAME
+                && violations.len() == 1
+                && matches!(
+                    violations[0].error.as_any().downcast_ref::<UserError>().unwrap().reason,
+                    UserErrorReason::BioTooLong,
+                ) && violations[0].path_to_string() == "user.bio",
+        ));
+    }
+
+    #[test]
+    fn parse_create_api_key() {
+        let parsed = CreateApiKeyRequestDto::parse(CreateApiKeyRequest {
+            display_name: " Bot ".into(),
+            scopes: vec!["posts.write".into(), "posts.write".into()],
+            expire_time: None,
+        })
+        .unwrap();
+        assert_eq!(&parsed.display_name, "Bot");
+        assert_eq!(parsed.scopes, vec!["posts.write".to_string()]);
+        assert!(parsed.expire_time.is_none());
+
+        assert!(matches!(
+            CreateApiKeyRequestDto::parse(CreateApiKeyRequest {
+                display_name: "Bot".into(),
+                scopes: Vec::new(),
+                expire_time: None,
+            }).unwrap_err(),
+            RequestError::BadRequest { name, violations }
+            if name == CreateApiKeyRequest::NAME
+                && matches!(
+                    violations[0].error.as_any().downcast_ref::<CommonError>().unwrap(),
+                    CommonError::RequiredFieldMissing,
+                ) && violations[0].path_to_string() == "scopes",
+        ));
     }
 }
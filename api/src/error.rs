@@ -1,21 +1,28 @@
 use bomboni_proto::google::rpc::Status;
-use bomboni_proto::google::{protobuf::Any, rpc::ErrorInfo};
-use bomboni_request::error::{CommonError, GenericError, RequestError};
+use bomboni_proto::google::{
+    protobuf::{Any, Duration as ProtoDuration},
+    rpc::{
+        bad_request::FieldViolation, quota_failure::Violation, BadRequest, ErrorInfo, QuotaFailure,
+        RetryInfo,
+    },
+};
+use bomboni_request::error::{
+    CommonError, GenericError, GenericErrorBox, PathError, PathErrorStep, RequestError,
+};
 use paste::paste;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::proto::auth_error::AuthErrorReason;
 use crate::proto::common_error::CommonErrorReason;
+use crate::proto::idempotency_error::IdempotencyErrorReason;
+use crate::proto::post_error::PostErrorReason;
+use crate::proto::social_error::SocialErrorReason;
 use crate::proto::user_error::UserErrorReason;
 
-#[derive(Error, Debug, PartialEq)]
-#[error(transparent)]
-pub enum SkyError {
-    User(#[from] UserError),
-}
-
 pub type SkyResult<T> = Result<T, SkyError>;
 
 #[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
@@ -27,10 +34,67 @@ pub struct SkyErrorMetadata {
         with = "metadata_field_serde"
     )]
     pub user_name: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub canonical_name: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub min_length: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub max_length: Option<usize>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub permission: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub post_id: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub user_id: Option<String>,
+    /// Set by the server on every error, for correlation with its logs.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "metadata_field_serde"
+    )]
+    pub request_id: Option<String>,
 }
 
-pub const COMMON_ERROR_DOMAIN: &str = "common.example.com";
-pub const SKY_ERROR_DOMAIN: &str = "sky.example.com";
+/// Derived from the type URL domain when building, e.g. `sky.dev` of `type.sky.dev`.
+pub const SKY_ERROR_DOMAIN: &str = env!("SKY_ERROR_DOMAIN");
+pub const COMMON_ERROR_DOMAIN: &str = concat!("common.", env!("SKY_ERROR_DOMAIN"));
+pub const RATE_LIMIT_EXCEEDED_REASON: &str = "RATE_LIMIT_EXCEEDED";
+pub const BAD_REQUEST_REASON: &str = "BAD_REQUEST";
+
+/// Metadata of the `BAD_REQUEST` error info with the name of the request message.
+const REQUEST_NAME_METADATA_KEY: &str = "requestName";
+/// Metadata with the index of the field violation that an error info belongs to.
+pub const FIELD_VIOLATION_METADATA_KEY: &str = "fieldViolation";
+/// Metadata of domain and common error infos with the message of the error.
+const MESSAGE_METADATA_KEY: &str = "message";
+/// Metadata of common error infos with the display of the original `CommonError`.
+const COMMON_ERROR_METADATA_KEY: &str = "commonError";
+const EXPECTED_FORMAT_METADATA_KEY: &str = "expectedFormat";
+const NAME_METADATA_KEY: &str = "name";
 
 impl GenericError for SkyError {
     fn as_any(&self) -> &dyn std::any::Any {
@@ -38,6 +102,35 @@ impl GenericError for SkyError {
     }
 }
 
+impl SkyError {
+    /// Decodes the error of the most specific `ErrorInfo` domain of the status.
+    pub fn from_status(status: Status) -> Option<Self> {
+        // Errors of field violations are decoded with the request error
+        let error_infos: Vec<ErrorInfo> = unpack_error_infos(&status)
+            .filter(|error_info| {
+                !error_info
+                    .metadata
+                    .contains_key(FIELD_VIOLATION_METADATA_KEY)
+            })
+            .collect();
+        let error_info = error_infos
+            .iter()
+            .rev()
+            .find(|error_info| error_info.domain != COMMON_ERROR_DOMAIN)
+            .or_else(|| error_infos.first())?;
+
+        if error_info.domain == COMMON_ERROR_DOMAIN {
+            return CommonErrorReason::from_str_name(&error_info.reason).map(SkyError::Common);
+        }
+        match error_info.domain.strip_prefix(SKY_ERROR_DOMAIN)? {
+            "" if error_info.reason == RATE_LIMIT_EXCEEDED_REASON => {
+                RateLimitError::from_status(&status).map(Into::into)
+            }
+            domain => Self::from_domain_status(domain.strip_prefix('/')?, status),
+        }
+    }
+}
+
 /// Custom field serde to handle optional fields.
 mod metadata_field_serde {
     use std::str::FromStr;
@@ -107,17 +200,21 @@ impl Debug for SkyErrorMetadata {
             };
         }
 
-        debug_fields![user_name];
+        debug_fields![
+            user_name,
+            canonical_name,
+            min_length,
+            max_length,
+            permission,
+            post_id,
+            user_id,
+            request_id
+        ];
 
         Ok(())
     }
 }
 
-pub enum SkyErrorReason {
-    Common(CommonErrorReason),
-    User(UserErrorReason),
-}
-
 macro_rules! impl_sky_error_reason_variants {
     ($( ($variant:ident, $type:ty) $(,)? )* ) => {
         $(
@@ -130,8 +227,6 @@ macro_rules! impl_sky_error_reason_variants {
     };
 }
 
-impl_sky_error_reason_variants![(Common, CommonErrorReason), (User, UserErrorReason)];
-
 macro_rules! convert_sky_error_reason {
     ($reason:ident, $type:ty, $kind:ident) => {{
         let parsed_reason: SkyErrorReason = $reason.into();
@@ -144,6 +239,8 @@ macro_rules! convert_sky_error_reason {
             SkyErrorReason::$kind(parsed_reason) => {
                 domain_reason = parsed_reason;
             }
+            // Reasons of other domains can't be represented
+            _ => {}
         }
         (domain_reason, common_reason)
     }};
@@ -202,7 +299,8 @@ macro_rules! impl_domain_error {
                     Self {
                         reason,
                         common_reason,
-                        metadata: Some(metadata),
+                        // Empty metadata is the same as none
+                        metadata: (metadata != SkyErrorMetadata::default()).then_some(metadata),
                         message: None,
                         common_error: None,
                     }
@@ -252,12 +350,7 @@ macro_rules! impl_domain_error {
 
         impl Display for paste! { [<$name Error>] } {
             fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-                if self.common_reason != CommonErrorReason::Unspecified {
-                    write!(f, "{}", self.common_reason.as_str_name())?;
-                } else {
-                    write!(f, "{}", self.reason.as_str_name())?;
-                }
-
+                write!(f, "{}", self.reason_name())?;
                 if let Some(message) = self.message.as_ref() {
                     write!(f, ": {}", message)?;
                 } else if let Some(common_error) = self.common_error.as_ref() {
@@ -276,25 +369,88 @@ macro_rules! impl_domain_error {
             }
 
             fn details(&self) -> Vec<Any> {
-                let mut details = Vec::new();
-                if self.common_reason != CommonErrorReason::Unspecified {
-                    details.push(Any::pack_from(&ErrorInfo {
-                        reason: self.common_reason.as_str_name().into(),
-                        domain: COMMON_ERROR_DOMAIN.into(),
-                        metadata: self.metadata.clone().unwrap_or_default().to_map(),
-                    }).unwrap());
+                self.error_infos(false)
+                    .iter()
+                    .map(|error_info| Any::pack_from(error_info).unwrap())
+                    .collect()
+            }
+        }
+
+        paste! {
+            impl [<$name Error>] {
+                fn domain() -> String {
+                    format!("{}/{}", SKY_ERROR_DOMAIN, stringify!($name))
+                }
+
+                fn reason_name(&self) -> &'static str {
+                    if self.common_reason != CommonErrorReason::Unspecified {
+                        self.common_reason.as_str_name()
+                    } else {
+                        self.reason.as_str_name()
+                    }
                 }
-                if self.reason != paste! { [<$name ErrorReason>]::Unspecified } {
-                    details.push(
-                        Any::pack_from(&ErrorInfo {
+
+                /// Error infos of the common and domain reasons.
+                ///
+                /// The domain error info is always included with `with_domain`,
+                /// so that the domain of common reasons is known.
+                fn error_infos(&self, with_domain: bool) -> Vec<ErrorInfo> {
+                    let mut metadata = self.metadata.clone().unwrap_or_default().to_map();
+                    if let Some(message) = self.message.as_ref() {
+                        metadata.insert(MESSAGE_METADATA_KEY.into(), message.clone());
+                    }
+                    let mut error_infos = Vec::new();
+                    if self.common_reason != CommonErrorReason::Unspecified {
+                        let mut common_metadata = metadata.clone();
+                        if let Some(common_error) = self.common_error.as_ref() {
+                            common_metadata.extend(encode_common_error(common_error));
+                        }
+                        error_infos.push(ErrorInfo {
+                            reason: self.common_reason.as_str_name().into(),
+                            domain: COMMON_ERROR_DOMAIN.into(),
+                            metadata: common_metadata,
+                        });
+                    }
+                    if with_domain || self.reason != [<$name ErrorReason>]::Unspecified {
+                        error_infos.push(ErrorInfo {
                             reason: self.reason.as_str_name().into(),
-                            domain: format!("{}/{}", SKY_ERROR_DOMAIN, stringify!($name)),
-                            metadata: self.metadata.clone().unwrap_or_default().to_map(),
-                        })
-                        .unwrap(),
-                    );
+                            domain: Self::domain(),
+                            metadata,
+                        });
+                    }
+                    error_infos
+                }
+
+                /// Decodes the error from its error infos.
+                fn from_error_infos(
+                    common_info: Option<&ErrorInfo>,
+                    domain_info: Option<&ErrorInfo>,
+                ) -> Option<Self> {
+                    let reason = match domain_info {
+                        Some(error_info) => [<$name ErrorReason>]::from_str_name(&error_info.reason)?,
+                        None => [<$name ErrorReason>]::Unspecified,
+                    };
+                    let common_reason = match common_info {
+                        Some(error_info) => CommonErrorReason::from_str_name(&error_info.reason)?,
+                        None => CommonErrorReason::Unspecified,
+                    };
+                    let mut metadata = domain_info.or(common_info)?.metadata.clone();
+                    let message = metadata.remove(MESSAGE_METADATA_KEY);
+                    // Empty metadata is the same as none
+                    let metadata = SkyErrorMetadata::from_map(metadata)
+                        .filter(|metadata| *metadata != SkyErrorMetadata::default());
+                    let common_error = common_info.and_then(|error_info| {
+                        decode_common_error(common_reason, &error_info.metadata)
+                    });
+
+                    Some(Self {
+                        reason,
+                        message,
+                        common_reason,
+                        common_error,
+                        metadata,
+                    })
                 }
-                details
             }
         }
 
@@ -319,38 +475,25 @@ macro_rules! impl_domain_error {
                     Status::from(RequestError::generic(self))
                 }
 
-                pub fn from_status(mut status: Status) -> Option<Self> {
-                    if status.details.is_empty() {
-                        return None;
+                /// Decodes the error from the status of `into_status`, including its message.
+                pub fn from_status(status: Status) -> Option<Self> {
+                    let domain = Self::domain();
+                    let mut common_info = None;
+                    let mut domain_info = None;
+                    for error_info in unpack_error_infos(&status) {
+                        if error_info.metadata.contains_key(FIELD_VIOLATION_METADATA_KEY) {
+                            continue;
+                        }
+                        if error_info.domain == COMMON_ERROR_DOMAIN {
+                            common_info.get_or_insert(error_info);
+                        } else if error_info.domain == domain {
+                            domain_info.get_or_insert(error_info);
+                        }
                     }
-                    let error_info: ErrorInfo = Any::unpack_into(status.details.remove(0)).ok()?;
-                    let metadata = if error_info.metadata.is_empty() {
-                        None
-                    } else {
-                        SkyErrorMetadata::from_map(error_info.metadata)
-                    };
-
-                    if error_info.domain == COMMON_ERROR_DOMAIN {
-                        let reason = CommonErrorReason::from_str_name(&error_info.reason)?;
-                        return Some([<$name Error>] {
-                            reason: [<$name ErrorReason>]::Unspecified,
-                            common_reason: reason,
-                            metadata,
-                            message: None,
-                            common_error: None,
-                        });
-                    } else if error_info.domain == format!("{}/{}", SKY_ERROR_DOMAIN, stringify!($name)) {
-                        let reason = [<$name ErrorReason>]::from_str_name(&error_info.reason)?;
-                        return Some([<$name Error>] {
-                            reason,
-                            common_reason: CommonErrorReason::Unspecified,
-                            metadata,
-                            message: None,
-                            common_error: None,
-                        });
+                    if common_info.is_none() && domain_info.is_none() {
+                        return None;
                     }
-
-                    None
+                    Self::from_error_infos(common_info.as_ref(), domain_info.as_ref())
                 }
             }
 
@@ -372,7 +515,85 @@ macro_rules! impl_domain_error {
     };
 }
 
-impl_domain_error!(User);
+/// Registers the error domains, each with an error and a reason enum of `sky.v1`.
+macro_rules! sky_error_domains {
+    ($($name:ident),* $(,)?) => {
+        paste! {
+            #[derive(Error, Debug, PartialEq)]
+            pub enum SkyError {
+                /// Error without a domain reason, e.g. from request validation.
+                #[error("{}", .0.as_str_name())]
+                Common(CommonErrorReason),
+                $(
+                    #[error(transparent)]
+                    $name(#[from] [<$name Error>]),
+                )*
+                #[error(transparent)]
+                RateLimit(#[from] RateLimitError),
+            }
+
+            pub enum SkyErrorReason {
+                Common(CommonErrorReason),
+                $($name([<$name ErrorReason>]),)*
+            }
+
+            impl_sky_error_reason_variants![
+                (Common, CommonErrorReason),
+                $(($name, [<$name ErrorReason>]),)*
+            ];
+
+            impl SkyError {
+                /// Decodes the error of a domain, e.g. `User` of `sky.dev/User`.
+                fn from_domain_status(domain: &str, status: Status) -> Option<Self> {
+                    match domain {
+                        $(stringify!($name) => [<$name Error>]::from_status(status).map(Into::into),)*
+                        _ => None,
+                    }
+                }
+
+                /// Request error of errors that have error infos.
+                fn into_request_error(self) -> Option<RequestError> {
+                    match self {
+                        SkyError::Common(_) => None,
+                        $(SkyError::$name(err) => Some(RequestError::generic(err)),)*
+                        SkyError::RateLimit(err) => Some(RequestError::generic(err)),
+                    }
+                }
+            }
+
+            /// Error infos of a domain error, with the domain error info even for common reasons.
+            fn encode_domain_error(error: &dyn std::any::Any) -> Option<Vec<ErrorInfo>> {
+                $(
+                    if let Some(err) = error.downcast_ref::<[<$name Error>]>() {
+                        return Some(err.error_infos(true));
+                    }
+                )*
+                None
+            }
+
+            /// Decodes the error of a domain, e.g. `User` of `sky.dev/User`.
+            fn decode_domain_error(
+                domain: &str,
+                common_info: Option<&ErrorInfo>,
+                domain_info: &ErrorInfo,
+            ) -> Option<GenericErrorBox> {
+                let error: GenericErrorBox = match domain {
+                    $(
+                        stringify!($name) => Box::new(
+                            [<$name Error>]::from_error_infos(common_info, Some(domain_info))?,
+                        ),
+                    )*
+                    _ => return None,
+                };
+                Some(error)
+            }
+        }
+
+        $(impl_domain_error!($name);)*
+    };
+}
+
+sky_error_domains![User, Post, Social, Auth, Idempotency];
 
 pub fn get_common_error_reason(error: &CommonError) -> CommonErrorReason {
     match error {
@@ -399,6 +620,248 @@ pub fn get_common_error_reason(error: &CommonError) -> CommonErrorReason {
     }
 }
 
+fn unpack_error_infos(status: &Status) -> impl Iterator<Item = ErrorInfo> + '_ {
+    status
+        .details
+        .iter()
+        .filter_map(|detail| Any::unpack_into(detail.clone()).ok())
+}
+
+/// Metadata of the common error info that [`decode_common_error`] reverses.
+fn encode_common_error(error: &CommonError) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+    metadata.insert(COMMON_ERROR_METADATA_KEY.into(), error.to_string());
+    match error {
+        CommonError::InvalidName {
+            expected_format,
+            name,
+        } => {
+            metadata.insert(EXPECTED_FORMAT_METADATA_KEY.into(), expected_format.clone());
+            metadata.insert(NAME_METADATA_KEY.into(), name.clone());
+        }
+        CommonError::InvalidStringFormat { expected_format } => {
+            metadata.insert(EXPECTED_FORMAT_METADATA_KEY.into(), expected_format.clone());
+        }
+        _ => {}
+    }
+    metadata
+}
+
+/// Rebuilds the common error of a common error info, if it was encoded with one.
+///
+/// Only variants whose fields are encoded can be decoded.
+fn decode_common_error(
+    reason: CommonErrorReason,
+    metadata: &BTreeMap<String, String>,
+) -> Option<CommonError> {
+    let display = metadata.get(COMMON_ERROR_METADATA_KEY)?;
+    let expected_format = || metadata.get(EXPECTED_FORMAT_METADATA_KEY).cloned();
+    let error = match reason {
+        CommonErrorReason::ResourceNotFound => CommonError::ResourceNotFound,
+        CommonErrorReason::Unauthorized => CommonError::Unauthorized,
+        CommonErrorReason::RequiredFieldMissing => CommonError::RequiredFieldMissing,
+        CommonErrorReason::InvalidName => CommonError::InvalidName {
+            expected_format: expected_format()?,
+            name: metadata.get(NAME_METADATA_KEY)?.clone(),
+        },
+        CommonErrorReason::InvalidStringFormat => CommonError::InvalidStringFormat {
+            expected_format: expected_format()?,
+        },
+        CommonErrorReason::InvalidId => CommonError::InvalidId,
+        CommonErrorReason::DuplicateId => CommonError::DuplicateId,
+        CommonErrorReason::InvalidDisplayName => CommonError::InvalidDisplayName,
+        CommonErrorReason::InvalidDateTime => CommonError::InvalidDateTime,
+        CommonErrorReason::InvalidEnumValue => CommonError::InvalidEnumValue,
+        CommonErrorReason::UnknownOneofVariant => CommonError::UnknownOneofVariant,
+        CommonErrorReason::InvalidNumericValue => CommonError::InvalidNumericValue,
+        CommonErrorReason::FailedConvertValue => CommonError::FailedConvertValue,
+        CommonErrorReason::NumericOutOfRange => CommonError::NumericOutOfRange,
+        CommonErrorReason::DuplicateValue => CommonError::DuplicateValue,
+        CommonErrorReason::AlreadyExists => CommonError::AlreadyExists,
+        CommonErrorReason::NotFound => CommonError::NotFound,
+        CommonErrorReason::TypeMismatch => CommonError::TypeMismatch,
+        CommonErrorReason::Unspecified | CommonErrorReason::InvalidParent => return None,
+    };
+    // E.g. an alternative name error has the same reason
+    (error.to_string() == *display).then_some(error)
+}
+
+/// Converts a request error into a status that [`decode_request_error`] reverses.
+///
+/// Field violations of bad requests are followed by the error infos of their errors,
+/// with the index of the violation in the `fieldViolation` metadata.
+pub fn encode_request_error(err: RequestError) -> Status {
+    let RequestError::BadRequest { name, violations } = &err else {
+        return Status::from(err);
+    };
+
+    let mut error_infos = vec![ErrorInfo {
+        reason: BAD_REQUEST_REASON.into(),
+        domain: SKY_ERROR_DOMAIN.into(),
+        metadata: BTreeMap::from([(REQUEST_NAME_METADATA_KEY.into(), name.clone())]),
+    }];
+    let mut field_violations = Vec::with_capacity(violations.len());
+    for (index, violation) in violations.iter().enumerate() {
+        field_violations.push(FieldViolation {
+            field: format_field_path(&violation.path),
+            description: violation.error.to_string(),
+            ..Default::default()
+        });
+        for mut error_info in encode_violation_error(&violation.error) {
+            error_info
+                .metadata
+                .insert(FIELD_VIOLATION_METADATA_KEY.into(), index.to_string());
+            error_infos.push(error_info);
+        }
+    }
+
+    let mut details = vec![Any::pack_from(&BadRequest { field_violations }).unwrap()];
+    details.extend(
+        error_infos
+            .iter()
+            .map(|error_info| Any::pack_from(error_info).unwrap()),
+    );
+    Status {
+        code: tonic::Code::InvalidArgument as i32,
+        message: err.to_string(),
+        details,
+    }
+}
+
+/// Decodes bad requests with their field violations, and domain errors.
+pub fn decode_request_error(status: Status) -> Option<RequestError> {
+    let error_infos: Vec<ErrorInfo> = unpack_error_infos(&status).collect();
+    let Some(bad_request_info) = error_infos.iter().find(|error_info| {
+        error_info.domain == SKY_ERROR_DOMAIN && error_info.reason == BAD_REQUEST_REASON
+    }) else {
+        return SkyError::from_status(status)?.into_request_error();
+    };
+    let name = bad_request_info
+        .metadata
+        .get(REQUEST_NAME_METADATA_KEY)?
+        .clone();
+    let bad_request: BadRequest = status
+        .details
+        .iter()
+        .find_map(|detail| Any::unpack_into(detail.clone()).ok())?;
+
+    let violations = bad_request
+        .field_violations
+        .into_iter()
+        .enumerate()
+        .map(|(index, field_violation)| {
+            let index = index.to_string();
+            let violation_infos: Vec<&ErrorInfo> = error_infos
+                .iter()
+                .filter(|error_info| {
+                    error_info.metadata.get(FIELD_VIOLATION_METADATA_KEY) == Some(&index)
+                })
+                .collect();
+            Some(PathError {
+                path: parse_field_path(&field_violation.field)?,
+                error: decode_violation_error(&violation_infos)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(RequestError::BadRequest { name, violations })
+}
+
+/// Error infos of a violation error, with the domain error info even for common reasons.
+fn encode_violation_error(error: &GenericErrorBox) -> Vec<ErrorInfo> {
+    let error = error.as_any();
+    if let Some(common_error) = error.downcast_ref::<CommonError>() {
+        return vec![ErrorInfo {
+            reason: get_common_error_reason(common_error).as_str_name().into(),
+            domain: COMMON_ERROR_DOMAIN.into(),
+            metadata: encode_common_error(common_error),
+        }];
+    }
+
+    // Other errors are only described by the field violation
+    encode_domain_error(error).unwrap_or_default()
+}
+
+fn decode_violation_error(error_infos: &[&ErrorInfo]) -> Option<GenericErrorBox> {
+    let common_info = error_infos
+        .iter()
+        .copied()
+        .find(|error_info| error_info.domain == COMMON_ERROR_DOMAIN);
+    let Some(domain_info) = error_infos
+        .iter()
+        .copied()
+        .find(|error_info| error_info.domain != COMMON_ERROR_DOMAIN)
+    else {
+        let common_info = common_info?;
+        let reason = CommonErrorReason::from_str_name(&common_info.reason)?;
+        return Some(Box::new(decode_common_error(
+            reason,
+            &common_info.metadata,
+        )?));
+    };
+
+    let domain = domain_info
+        .domain
+        .strip_prefix(SKY_ERROR_DOMAIN)?
+        .strip_prefix('/')?;
+    decode_domain_error(domain, common_info, domain_info)
+}
+
+/// Formats a path like `user.links[0].labels["key"]`.
+pub fn format_field_path(path: &[PathErrorStep]) -> String {
+    let mut field_path = String::new();
+    for step in path {
+        match step {
+            PathErrorStep::Field(field) => {
+                if !field_path.is_empty() {
+                    field_path.push('.');
+                }
+                field_path.push_str(field);
+            }
+            PathErrorStep::Index(index) => {
+                field_path.push_str(&format!("[{}]", index));
+            }
+            PathErrorStep::Key(key) => {
+                field_path.push_str(&format!("[{}]", serde_json::to_string(key).unwrap()));
+            }
+        }
+    }
+    field_path
+}
+
+/// Parses a path formatted with [`format_field_path`].
+pub fn parse_field_path(field_path: &str) -> Option<Vec<PathErrorStep>> {
+    let mut path = Vec::new();
+    let mut rest = field_path;
+    while !rest.is_empty() {
+        if let Some(subscript) = rest.strip_prefix('[') {
+            if subscript.starts_with('"') {
+                let mut deserializer =
+                    serde_json::Deserializer::from_str(subscript).into_iter::<String>();
+                let key = deserializer.next()?.ok()?;
+                rest = subscript[deserializer.byte_offset()..].strip_prefix(']')?;
+                path.push(PathErrorStep::Key(key));
+            } else {
+                let (index, next) = subscript.split_once(']')?;
+                path.push(PathErrorStep::Index(index.parse().ok()?));
+                rest = next;
+            }
+        } else {
+            // Fields after the first one are separated with dots
+            if !path.is_empty() {
+                rest = rest.strip_prefix('.')?;
+            }
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            path.push(PathErrorStep::Field(rest[..end].into()));
+            rest = &rest[end..];
+        }
+    }
+    Some(path)
+}
+
 macro_rules! impl_sky_metadata_field {
     ($ident:ident, $type:ty, $convert:ident) => {
         paste! {
@@ -426,10 +889,91 @@ macro_rules! impl_sky_metadata_field {
 
 impl UserError {
     impl_sky_metadata_field!(user_name, &str, into);
+    impl_sky_metadata_field!(canonical_name, &str, into);
+    impl_sky_metadata_field!(min_length, usize, into);
+    impl_sky_metadata_field!(max_length, usize, into);
+    impl_sky_metadata_field!(permission, &str, into);
+    impl_sky_metadata_field!(request_id, &str, into);
+}
+
+impl PostError {
+    impl_sky_metadata_field!(post_id, &str, into);
+}
+
+impl SocialError {
+    impl_sky_metadata_field!(user_id, &str, into);
+    impl_sky_metadata_field!(user_name, &str, into);
+}
+
+impl AuthError {
+    impl_sky_metadata_field!(permission, &str, into);
+}
+
+/// Error of calls rejected by the rate limiter.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{}: {description}", RATE_LIMIT_EXCEEDED_REASON)]
+pub struct RateLimitError {
+    /// Rate limited subject, e.g. `user:{id}` or `address:{ip}`.
+    pub subject: String,
+    pub description: String,
+    pub retry_delay: Duration,
+}
+
+impl RateLimitError {
+    pub fn from_status(status: &Status) -> Option<Self> {
+        let mut violation = None;
+        let mut retry_delay = None;
+        for detail in &status.details {
+            if let Ok(quota_failure) = Any::unpack_into::<QuotaFailure>(detail.clone()) {
+                violation = quota_failure.violations.into_iter().next();
+            } else if let Ok(retry_info) = Any::unpack_into::<RetryInfo>(detail.clone()) {
+                retry_delay = retry_info.retry_delay;
+            }
+        }
+        let violation = violation?;
+        Some(Self {
+            subject: violation.subject,
+            description: violation.description,
+            retry_delay: Duration::from_secs(retry_delay?.seconds.try_into().ok()?),
+        })
+    }
+}
+
+impl GenericError for RateLimitError {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn details(&self) -> Vec<Any> {
+        vec![
+            Any::pack_from(&ErrorInfo {
+                reason: RATE_LIMIT_EXCEEDED_REASON.into(),
+                domain: SKY_ERROR_DOMAIN.into(),
+                metadata: Default::default(),
+            })
+            .unwrap(),
+            Any::pack_from(&QuotaFailure {
+                violations: vec![Violation {
+                    subject: self.subject.clone(),
+                    description: self.description.clone(),
+                }],
+            })
+            .unwrap(),
+            Any::pack_from(&RetryInfo {
+                retry_delay: Some(ProtoDuration {
+                    // Round up, so that clients don't retry too early
+                    seconds: self.retry_delay.as_secs_f64().ceil() as i64,
+                    nanos: 0,
+                }),
+            })
+            .unwrap(),
+        ]
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::{collection, option, prelude::*};
     use serde_json::json;
 
     use super::*;
@@ -451,7 +995,7 @@ mod tests {
                     {
                         "@type": "type.googleapis.com/google.rpc.ErrorInfo",
                         "reason": "USER_ERROR_REASON_INVALID_NAME",
-                        "domain": "sky.example.com/User",
+                        "domain": format!("{}/User", SKY_ERROR_DOMAIN),
                         "metadata": {
                             "userName": "tester"
                         }
@@ -460,4 +1004,301 @@ mod tests {
             }),
         );
     }
+
+    #[test]
+    fn decode_sky_error() {
+        let err = PostError::new(PostErrorReason::PostNotFound).with_post_id("1");
+        assert_eq!(
+            SkyError::from_status(err.clone().into_status()),
+            Some(SkyError::Post(err))
+        );
+
+        let err = SocialError::new(SocialErrorReason::AlreadyFollowing).with_user_id("1");
+        assert_eq!(
+            SkyError::from_status(err.clone().into_status()),
+            Some(SkyError::Social(err))
+        );
+
+        assert_eq!(
+            SkyError::from_status(AuthError::new(CommonErrorReason::NotFound).into_status()),
+            Some(SkyError::Common(CommonErrorReason::NotFound))
+        );
+
+        let err = RateLimitError {
+            subject: "address:127.0.0.1".into(),
+            description: "Too many calls".into(),
+            retry_delay: Duration::from_secs(3),
+        };
+        assert_eq!(
+            SkyError::from_status(Status::from(RequestError::generic(err.clone()))),
+            Some(SkyError::RateLimit(err))
+        );
+
+        assert_eq!(SkyError::from_status(Status::default()), None);
+    }
+
+    #[test]
+    fn decode_bad_request() {
+        let err = RequestError::BadRequest {
+            name: "sky.v1.UpdateProfileRequest".into(),
+            violations: vec![
+                PathError {
+                    path: vec![
+                        PathErrorStep::Field("user".into()),
+                        PathErrorStep::Field("bio".into()),
+                    ],
+                    error: Box::new(
+                        UserError::new(UserErrorReason::BioTooLong).with_max_length(160),
+                    ),
+                },
+                PathError {
+                    path: vec![PathErrorStep::Field("update_mask".into())],
+                    error: Box::new(CommonError::InvalidName {
+                        expected_format: "display_name|bio".into(),
+                        name: "email".into(),
+                    }),
+                },
+            ],
+        };
+        let status = encode_request_error(err);
+        let bad_request: BadRequest = Any::unpack_into(status.details[0].clone()).unwrap();
+        assert_eq!(bad_request.field_violations[0].field, "user.bio");
+        assert_eq!(bad_request.field_violations[1].field, "update_mask");
+
+        let RequestError::BadRequest { name, violations } =
+            decode_request_error(status.clone()).unwrap()
+        else {
+            panic!("expected a bad request");
+        };
+        assert_eq!(name, "sky.v1.UpdateProfileRequest");
+        assert_eq!(
+            violations[0].error.as_any().downcast_ref::<UserError>(),
+            Some(&UserError::new(UserErrorReason::BioTooLong).with_max_length(160))
+        );
+        assert_eq!(
+            violations[1].error.as_any().downcast_ref::<CommonError>(),
+            Some(&CommonError::InvalidName {
+                expected_format: "display_name|bio".into(),
+                name: "email".into(),
+            })
+        );
+        assert_eq!(
+            encode_request_error(RequestError::BadRequest { name, violations }),
+            status
+        );
+
+        // Errors of violations are not errors of the status
+        assert_eq!(UserError::from_status(status.clone()), None);
+        assert_eq!(SkyError::from_status(status), None);
+    }
+
+    #[test]
+    fn parse_field_paths() {
+        let path = vec![
+            PathErrorStep::Field("user".into()),
+            PathErrorStep::Field("links".into()),
+            PathErrorStep::Index(2),
+            PathErrorStep::Key("a\"]b".into()),
+            PathErrorStep::Field("url".into()),
+        ];
+        let field_path = format_field_path(&path);
+        assert_eq!(field_path, r#"user.links[2]["a\"]b"].url"#);
+        assert_eq!(parse_field_path(&field_path), Some(path));
+
+        for field_path in ["user..name", ".name", "links[a]", "labels[\"a\"", "a[0]b"] {
+            assert_eq!(parse_field_path(field_path), None, "{}", field_path);
+        }
+    }
+
+    fn metadata_strategy() -> impl Strategy<Value = Option<SkyErrorMetadata>> {
+        (
+            option::of("\\PC{1,12}"),
+            option::of(any::<usize>()),
+            option::of("[0-9]{1,8}"),
+            option::of("[0-9a-f]{32}"),
+        )
+            .prop_map(|(user_name, max_length, post_id, request_id)| {
+                let metadata = SkyErrorMetadata {
+                    user_name,
+                    max_length,
+                    post_id,
+                    request_id,
+                    ..Default::default()
+                };
+                // Empty metadata is decoded as none
+                (metadata != SkyErrorMetadata::default()).then_some(metadata)
+            })
+    }
+
+    fn common_error_strategy() -> impl Strategy<Value = CommonError> {
+        prop_oneof![
+            Just(CommonError::RequiredFieldMissing),
+            Just(CommonError::NotFound),
+            Just(CommonError::AlreadyExists),
+            "\\PC{1,12}"
+                .prop_map(|expected_format| CommonError::InvalidStringFormat { expected_format }),
+            ("\\PC{1,12}", "\\PC{0,12}").prop_map(|(expected_format, name)| {
+                CommonError::InvalidName {
+                    expected_format,
+                    name,
+                }
+            }),
+        ]
+    }
+
+    /// Common reason and error of domain errors.
+    fn common_strategy() -> impl Strategy<Value = (CommonErrorReason, Option<CommonError>)> {
+        prop_oneof![
+            Just((CommonErrorReason::Unspecified, None)),
+            (1..20i32).prop_map(|reason| (CommonErrorReason::try_from(reason).unwrap(), None)),
+            common_error_strategy().prop_map(|common_error| (
+                get_common_error_reason(&common_error),
+                Some(common_error)
+            )),
+        ]
+    }
+
+    /// Any valid value of a reason enum.
+    fn reason_strategy<R: TryFrom<i32> + Clone + Debug + 'static>() -> impl Strategy<Value = R> {
+        let reasons: Vec<R> = (0..64)
+            .filter_map(|reason| R::try_from(reason).ok())
+            .collect();
+        prop::sample::select(reasons)
+    }
+
+    /// Strategies and status round trips of the errors of each domain.
+    macro_rules! domain_error_tests {
+        ($($name:ident),* $(,)?) => {
+            paste! {
+                $(
+                    fn [<$name:snake _error_strategy>]() -> impl Strategy<Value = [<$name Error>]> {
+                        (
+                            reason_strategy::<[<$name ErrorReason>]>(),
+                            common_strategy(),
+                            option::of("\\PC{0,24}"),
+                            metadata_strategy(),
+                        )
+                            .prop_map(|(reason, (common_reason, common_error), message, metadata)| {
+                                [<$name Error>] {
+                                    reason,
+                                    message,
+                                    common_reason,
+                                    common_error,
+                                    metadata,
+                                }
+                            })
+                            .prop_filter("errors have a reason", |err| {
+                                err.reason != [<$name ErrorReason>]::Unspecified
+                                    || err.common_reason != CommonErrorReason::Unspecified
+                            })
+                    }
+                )*
+
+                proptest! {
+                    $(
+                        #[test]
+                        fn [<$name:snake _error_status_round_trip>](err in [<$name:snake _error_strategy>]()) {
+                            let status = err.clone().into_status();
+                            prop_assert_eq!([<$name Error>]::from_status(status.clone()), Some(err.clone()));
+                            if err.reason != [<$name ErrorReason>]::Unspecified {
+                                prop_assert_eq!(SkyError::from_status(status), Some(SkyError::$name(err)));
+                            }
+                        }
+                    )*
+                }
+
+                /// Error of a violation, which can be boxed more than once.
+                #[derive(Debug, Clone)]
+                enum ViolationError {
+                    Common(CommonError),
+                    $($name([<$name Error>]),)*
+                }
+
+                impl ViolationError {
+                    fn to_box(&self) -> GenericErrorBox {
+                        match self.clone() {
+                            Self::Common(err) => Box::new(err),
+                            $(Self::$name(err) => Box::new(err),)*
+                        }
+                    }
+
+                    fn assert_decoded(&self, error: &GenericErrorBox) {
+                        let error = error.as_any();
+                        match self {
+                            Self::Common(err) => {
+                                assert_eq!(error.downcast_ref::<CommonError>(), Some(err))
+                            }
+                            $(
+                                Self::$name(err) => {
+                                    assert_eq!(error.downcast_ref::<[<$name Error>]>(), Some(err))
+                                }
+                            )*
+                        }
+                    }
+                }
+
+                fn violation_error_strategy() -> impl Strategy<Value = ViolationError> {
+                    prop_oneof![
+                        common_error_strategy().prop_map(ViolationError::Common)
+                        $(, [<$name:snake _error_strategy>]().prop_map(ViolationError::$name))*
+                    ]
+                }
+            }
+        };
+    }
+
+    domain_error_tests![User, Post, Social, Auth, Idempotency];
+
+    fn violation_strategy() -> impl Strategy<Value = (Vec<PathErrorStep>, ViolationError)> {
+        let step = prop_oneof![
+            "[a-z_]{1,8}".prop_map(PathErrorStep::Field),
+            any::<u16>().prop_map(|index| PathErrorStep::Index(index.into())),
+            "\\PC{0,8}".prop_map(PathErrorStep::Key),
+        ];
+        (collection::vec(step, 0..4), violation_error_strategy())
+    }
+
+    #[test]
+    fn message_round_trip() {
+        // Messages that are the same as the display without them
+        let err = UserError::new(UserErrorReason::InvalidName)
+            .with_user_name("tester")
+            .with_message(r#"{"userName": "tester"}"#);
+        assert_eq!(UserError::from_status(err.clone().into_status()), Some(err));
+
+        let err = AuthError::new_common(CommonError::NotFound).with_message("not found");
+        assert_eq!(AuthError::from_status(err.clone().into_status()), Some(err));
+    }
+
+    proptest! {
+        #[test]
+        fn bad_request_status_round_trip(
+            name in "[a-zA-Z.]{1,32}",
+            violations in collection::vec(violation_strategy(), 1..4),
+        ) {
+            let status = encode_request_error(RequestError::BadRequest {
+                name: name.clone(),
+                violations: violations
+                    .iter()
+                    .map(|(path, error)| PathError {
+                        path: path.clone(),
+                        error: error.to_box(),
+                    })
+                    .collect(),
+            });
+            let Some(RequestError::BadRequest {
+                name: decoded_name,
+                violations: decoded_violations,
+            }) = decode_request_error(status)
+            else {
+                panic!("expected a bad request");
+            };
+            prop_assert_eq!(decoded_name, name);
+            prop_assert_eq!(decoded_violations.len(), violations.len());
+            for ((path, error), decoded_violation) in violations.iter().zip(&decoded_violations) {
+                prop_assert_eq!(&decoded_violation.path, path);
+                error.assert_decoded(&decoded_violation.error);
+            }
+        }
+    }
 }
@@ -1,10 +1,11 @@
 pub mod dto;
 pub mod error;
+pub mod user_name;
 
 pub mod proto {
-    tonic::include_proto!("sky");
-    bomboni_proto::include_proto!("sky.plus");
-    tonic::include_proto!("sky.error");
+    tonic::include_proto!("sky.v1");
+    bomboni_proto::include_proto!("sky.v1.plus");
+    tonic::include_proto!("sky.v1.error");
 
     pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("sky_fd");
 }
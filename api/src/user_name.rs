@@ -0,0 +1,71 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{skeleton, MixedScript};
+
+/// Maximum number of characters of a canonical user name, the size of its database column.
+///
+/// Compatibility normalization can expand a single character into many, e.g. `ﷺ` into 18.
+pub const MAX_CANONICAL_USER_NAME_LENGTH: usize = 64;
+
+/// Returns the canonical form of a user name, used for lookups and uniqueness.
+///
+/// The name is NFKC normalized, lowercased as an approximation of case folding,
+/// and reduced to its UTS #39 confusable skeleton.
+/// As a result, `Tester`, `TESTER` and `tеster` (with a Cyrillic `е`) share the same form.
+pub fn canonicalize_user_name(name: &str) -> String {
+    let folded: String = name.nfkc().flat_map(char::to_lowercase).nfkc().collect();
+    skeleton(&folded).collect()
+}
+
+/// Checks that a user name doesn't mix scripts, such as Latin and Cyrillic letters.
+pub fn is_single_script_user_name(name: &str) -> bool {
+    name.is_single_script()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalize() {
+        assert_eq!(
+            canonicalize_user_name("Tester"),
+            canonicalize_user_name("tester")
+        );
+        assert_eq!(
+            canonicalize_user_name("ＴＥＳＴＥＲ"),
+            canonicalize_user_name("tester")
+        );
+        assert_eq!(
+            canonicalize_user_name("t\u{435}ster"),
+            canonicalize_user_name("tester")
+        );
+        assert_ne!(
+            canonicalize_user_name("tester"),
+            canonicalize_user_name("taster")
+        );
+
+        assert_eq!(
+            canonicalize_user_name(&"\u{fdfa}".repeat(16))
+                .chars()
+                .count(),
+            288
+        );
+
+        assert!(is_single_script_user_name("tester_42"));
+        assert!(!is_single_script_user_name("t\u{435}ster"));
+    }
+
+    #[test]
+    fn canonicalize_ascii() {
+        // Migration 000003 canonicalizes existing names in SQL with these replacements
+        for c in ('a'..='z').chain('A'..='Z').chain('0'..='9').chain(['_']) {
+            let expected = match c.to_ascii_lowercase() {
+                'm' => "rn".to_string(),
+                '0' => "O".to_string(),
+                '1' => "l".to_string(),
+                c => c.to_string(),
+            };
+            assert_eq!(canonicalize_user_name(&c.to_string()), expected);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Checks the API against the committed baseline, so that changes don't break existing clients.
+//!
+//! Run with `UPDATE_PROTO_BASELINE=1` to accept the current API as the new baseline.
+
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FileDescriptorSet,
+};
+use std::{collections::BTreeMap, env, fs};
+
+use grpc_sky_api::proto::FILE_DESCRIPTOR_SET;
+
+const API_PACKAGE: &str = "sky.v1";
+const BASELINE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/proto/baseline/sky.v1.txt");
+const UPDATE_BASELINE_VAR: &str = "UPDATE_PROTO_BASELINE";
+
+/// Describes every field, enum value and RPC of the API, keyed by the name clients know it by.
+fn describe_api(fd_set: &FileDescriptorSet) -> BTreeMap<String, String> {
+    let mut entries = BTreeMap::new();
+    for file in &fd_set.file {
+        let package = file.package();
+        if package != API_PACKAGE && !package.starts_with(&format!("{}.", API_PACKAGE)) {
+            continue;
+        }
+        for message in &file.message_type {
+            describe_message(&mut entries, package, message);
+        }
+        for enum_type in &file.enum_type {
+            describe_enum(&mut entries, package, enum_type);
+        }
+        for service in &file.service {
+            for method in &service.method {
+                let key = format!("rpc {}.{}.{}", package, service.name(), method.name());
+                let entry = format!(
+                    "{}({}) returns ({})",
+                    key,
+                    method.input_type().trim_start_matches('.'),
+                    method.output_type().trim_start_matches('.'),
+                );
+                entries.insert(key, entry);
+            }
+        }
+    }
+    entries
+}
+
+fn describe_message(
+    entries: &mut BTreeMap<String, String>,
+    scope: &str,
+    message: &DescriptorProto,
+) {
+    let full_name = format!("{}.{}", scope, message.name());
+    for field in &message.field {
+        let key = format!("field {}.{}", full_name, field.name());
+        let type_name = match field.r#type() {
+            Type::Message | Type::Enum | Type::Group => {
+                field.type_name().trim_start_matches('.').to_string()
+            }
+            scalar => scalar
+                .as_str_name()
+                .trim_start_matches("TYPE_")
+                .to_lowercase(),
+        };
+        let label = if field.label() == Label::Repeated {
+            "repeated "
+        } else {
+            ""
+        };
+        let entry = format!("{} = {} {}{}", key, field.number(), label, type_name);
+        entries.insert(key, entry);
+    }
+    for nested in &message.nested_type {
+        describe_message(entries, &full_name, nested);
+    }
+    for enum_type in &message.enum_type {
+        describe_enum(entries, &full_name, enum_type);
+    }
+}
+
+fn describe_enum(
+    entries: &mut BTreeMap<String, String>,
+    scope: &str,
+    enum_type: &EnumDescriptorProto,
+) {
+    let full_name = format!("{}.{}", scope, enum_type.name());
+    for value in &enum_type.value {
+        let key = format!("enum_value {}.{}", full_name, value.name());
+        let entry = format!("{} = {}", key, value.number());
+        entries.insert(key, entry);
+    }
+}
+
+/// Returns baseline entries that were removed or changed.
+///
+/// Added entries are compatible, while renames show up as removals.
+fn find_breaking_changes(baseline: &str, current: &BTreeMap<String, String>) -> Vec<String> {
+    baseline
+        .lines()
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let key = line.split(['(', '=']).next().unwrap().trim_end();
+            match current.get(key) {
+                Some(entry) if entry == line => None,
+                Some(entry) => Some(format!("changed `{}` to `{}`", line, entry)),
+                None => Some(format!("removed `{}`", line)),
+            }
+        })
+        .collect()
+}
+
+fn format_baseline(entries: &BTreeMap<String, String>) -> String {
+    entries
+        .values()
+        .map(|entry| format!("{}\n", entry))
+        .collect()
+}
+
+#[test]
+fn no_breaking_changes() {
+    let current = describe_api(&FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap());
+    if env::var_os(UPDATE_BASELINE_VAR).is_some() {
+        fs::write(BASELINE_PATH, format_baseline(&current)).unwrap();
+        return;
+    }
+
+    let baseline = fs::read_to_string(BASELINE_PATH).unwrap();
+    let breaking_changes = find_breaking_changes(&baseline, &current);
+    assert!(
+        breaking_changes.is_empty(),
+        "breaking API changes, set {} to accept them:\n{}",
+        UPDATE_BASELINE_VAR,
+        breaking_changes.join("\n")
+    );
+}
+
+#[test]
+fn detect_breaking_changes() {
+    let mut fd_set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+    let baseline = format_baseline(&describe_api(&fd_set));
+
+    for file in &mut fd_set.file {
+        for message in &mut file.message_type {
+            match message.name() {
+                "SignUpRequest" => message.field.retain(|field| field.name() != "password"),
+                "SignInRequest" => message.field[0].number = Some(10),
+                _ => {}
+            }
+        }
+        for service in &mut file.service {
+            for method in &mut service.method {
+                if method.name() == "GetMe" {
+                    method.name = Some("GetSelf".into());
+                }
+            }
+        }
+    }
+    let current = describe_api(&fd_set);
+
+    assert_eq!(
+        find_breaking_changes(&baseline, &current),
+        vec![
+            "changed `field sky.v1.SignInRequest.name = 1 string` to `field sky.v1.SignInRequest.name = 10 string`",
+            "removed `field sky.v1.SignUpRequest.password = 2 string`",
+            "removed `rpc sky.v1.UserService.GetMe(sky.v1.GetMeRequest) returns (sky.v1.User)`",
+        ]
+    );
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use grpc_sky_client::auth::AccessToken;
+
+use crate::error::{CliError, CliResult};
+
+/// Stored in `sky/config.toml` of the user config directory, unless set with `--config`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliConfig {
+    /// Address of the service, such as `http://localhost:9000`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Session of the signed in user. Passwords are never stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<StoredCredentials>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredCredentials {
+    /// Address the session was signed in to, which is empty in configs of older versions.
+    #[serde(default)]
+    pub address: String,
+    pub user_id: String,
+    pub name: String,
+    pub access_token: String,
+}
+
+impl CliConfig {
+    pub fn default_path() -> CliResult<PathBuf> {
+        dirs::config_dir()
+            .map(|config_dir| config_dir.join("sky").join("config.toml"))
+            .ok_or(CliError::MissingConfigDirectory)
+    }
+
+    /// Loads the config, which is empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> CliResult<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the config, readable only by the user since it contains a session token.
+    ///
+    /// The config is written to a temporary file that replaces the old one,
+    /// so that a config with other permissions is never written to.
+    pub fn save(&self, path: &Path) -> CliResult<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temp_path)?;
+        // The mode only applies to new files, not to one left by a failed save
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        file.write_all(toml::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Returns the session token, if it hasn't expired and was signed in to the address.
+    pub fn access_token(&self, address: &str) -> CliResult<&str> {
+        let credentials = self.credentials.as_ref().ok_or(CliError::NotSignedIn)?;
+        if credentials.address != address {
+            return Err(CliError::OtherAddress(credentials.address.clone()));
+        }
+        let access_token = AccessToken::new(credentials.access_token.clone());
+        if access_token
+            .expire_time
+            .is_some_and(|expire_time| expire_time <= SystemTime::now())
+        {
+            return Err(CliError::SessionExpired);
+        }
+        Ok(&credentials.access_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("sky-cli-{}", std::process::id()))
+            .join("config.toml");
+        assert_eq!(CliConfig::load(&path).unwrap(), CliConfig::default());
+        assert!(matches!(
+            CliConfig::default().access_token("http://localhost:9000"),
+            Err(CliError::NotSignedIn)
+        ));
+
+        let config = CliConfig {
+            address: Some("http://localhost:9000".into()),
+            credentials: Some(StoredCredentials {
+                address: "http://localhost:9000".into(),
+                user_id: "1".into(),
+                name: "tester".into(),
+                access_token: "opaque".into(),
+            }),
+        };
+        config.save(&path).unwrap();
+        let loaded = CliConfig::load(&path).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(
+            loaded.access_token("http://localhost:9000").unwrap(),
+            "opaque"
+        );
+        // Sessions are never sent to other addresses
+        assert!(matches!(
+            loaded.access_token("http://example.com:9000"),
+            Err(CliError::OtherAddress(_))
+        ));
+
+        // Configs that other users could read are replaced
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            config.save(&path).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+use std::io;
+use thiserror::Error;
+use tonic::Code;
+
+use grpc_sky_client::error::ClientError;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid config: {0}")]
+    InvalidConfig(#[from] toml::de::Error),
+    #[error("failed to write config: {0}")]
+    WriteConfig(#[from] toml::ser::Error),
+    #[error("no config directory, set the config path with `--config`")]
+    MissingConfigDirectory,
+    #[error("not signed in, run `sky login` first")]
+    NotSignedIn,
+    #[error("session expired, run `sky login` again")]
+    SessionExpired,
+    #[error("signed in to `{0}`, run `sky login` to sign in to this address")]
+    OtherAddress(String),
+    #[error("passwords don't match")]
+    PasswordMismatch,
+    #[error("message `{0}` not found")]
+    MessageNotFound(String),
+    #[error("failed to decode message: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("failed to encode JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub type CliResult<T> = Result<T, CliError>;
+
+impl CliError {
+    pub fn code(&self) -> Code {
+        match self {
+            CliError::Client(err) => err.code(),
+            CliError::NotSignedIn | CliError::SessionExpired | CliError::OtherAddress(_) => {
+                Code::Unauthenticated
+            }
+            CliError::PasswordMismatch => Code::InvalidArgument,
+            _ => Code::Unknown,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use clap::{Args, Parser, Subcommand};
+use futures::TryStreamExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use tonic::transport::{Certificate, ClientTlsConfig};
+
+use grpc_sky_api::proto::{ListPostsRequest, ListPostsResponse};
+use grpc_sky_client::{auth::Credentials, SkyClient};
+
+use crate::{
+    config::{CliConfig, StoredCredentials},
+    error::{CliError, CliResult},
+    output::{OutputFormat, Printer},
+};
+
+mod config;
+mod error;
+mod output;
+
+const DEFAULT_ADDRESS: &str = "http://localhost:9000";
+const POST_COLUMNS: &[(&str, &str)] = &[
+    ("ID", "id"),
+    ("User ID", "userId"),
+    ("Content", "content"),
+    ("Created", "createTime"),
+];
+
+/// Command-line client of the social media service.
+#[derive(Debug, Parser)]
+#[command(name = "sky", version)]
+struct Cli {
+    /// Address of the service, instead of the one in the config.
+    #[arg(long, env = "SKY_ADDRESS", global = true)]
+    address: Option<String>,
+
+    /// PEM file of the certificate authority of the service, for TLS with a private CA.
+    #[arg(long, env = "SKY_CA_CERT", global = true)]
+    ca_cert: Option<PathBuf>,
+
+    /// Path of the config file that stores the session.
+    #[arg(long, env = "SKY_CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    #[arg(long, short, value_enum, default_value = "table", global = true)]
+    output: OutputFormat,
+
+    /// Preferred locale of error messages, such as `de`.
+    #[arg(long, env = "SKY_LOCALE", global = true)]
+    locale: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Creates a user and signs in.
+    Signup { name: String },
+    /// Signs in and stores the session in the config.
+    Login { name: String },
+    /// Publishes a post as the signed in user.
+    Post { content: String },
+    /// Lists posts.
+    Posts {
+        #[command(subcommand)]
+        command: PostsCommand,
+    },
+    /// Shows the signed in user.
+    Me,
+}
+
+#[derive(Debug, Subcommand)]
+enum PostsCommand {
+    List(ListPostsArgs),
+}
+
+#[derive(Debug, Args)]
+struct ListPostsArgs {
+    /// Filter expression, such as `userId = "1"`.
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Fields to order by, such as `createTime desc`.
+    #[arg(long)]
+    order_by: Option<String>,
+
+    #[arg(long)]
+    page_size: Option<i32>,
+
+    /// Token of the page to list, from the previous page.
+    #[arg(long, conflicts_with = "all")]
+    page_token: Option<String>,
+
+    /// Lists posts of all pages.
+    #[arg(long)]
+    all: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let printer = Printer::new(cli.output);
+    match run(cli, &printer).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            printer.print_error(&err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, printer: &Printer) -> CliResult<()> {
+    let config_path = match cli.config {
+        Some(config_path) => config_path,
+        None => CliConfig::default_path()?,
+    };
+    let mut config = CliConfig::load(&config_path)?;
+
+    let address = cli
+        .address
+        .or_else(|| config.address.clone())
+        .unwrap_or_else(|| DEFAULT_ADDRESS.into());
+    let tls_config = match cli.ca_cert {
+        Some(ca_cert) => {
+            Some(ClientTlsConfig::new().ca_certificate(Certificate::from_pem(fs::read(ca_cert)?)))
+        }
+        None => None,
+    };
+    let mut client = SkyClient::connect(address.clone(), tls_config).await?;
+    if let Some(locale) = cli.locale {
+        client = client.with_locale(locale);
+    }
+
+    match cli.command {
+        Command::Signup { name } => {
+            let password = rpassword::prompt_password("Password: ")?;
+            if rpassword::prompt_password("Repeat password: ")? != password {
+                return Err(CliError::PasswordMismatch);
+            }
+            client.sign_up(&name, &password).await?;
+            sign_in(
+                client,
+                &mut config,
+                &config_path,
+                address,
+                name,
+                &password,
+                printer,
+            )
+            .await
+        }
+        Command::Login { name } => {
+            let password = rpassword::prompt_password("Password: ")?;
+            sign_in(
+                client,
+                &mut config,
+                &config_path,
+                address,
+                name,
+                &password,
+                printer,
+            )
+            .await
+        }
+        Command::Post { content } => {
+            let client = authenticate(client, &config, &address)?;
+            printer.print_message(&client.post(&content).await?)
+        }
+        Command::Posts {
+            command: PostsCommand::List(args),
+        } => {
+            let client = authenticate(client, &config, &address)?;
+            list_posts(&client, args, printer).await
+        }
+        Command::Me => {
+            let client = authenticate(client, &config, &address)?;
+            printer.print_message(&client.get_me().await?)
+        }
+    }
+}
+
+/// Signs in, stores the session with its address and shows the user.
+async fn sign_in(
+    mut client: SkyClient,
+    config: &mut CliConfig,
+    config_path: &Path,
+    address: String,
+    name: String,
+    password: &str,
+    printer: &Printer,
+) -> CliResult<()> {
+    let response = client.sign_in(&name, password).await?;
+    // Later commands use the address of the session, unless given another one
+    config.address = Some(address.clone());
+    config.credentials = Some(StoredCredentials {
+        address,
+        user_id: response.user_id,
+        name,
+        access_token: response.access_token,
+    });
+    config.save(config_path)?;
+    printer.print_message(&client.get_me().await?)
+}
+
+fn authenticate(client: SkyClient, config: &CliConfig, address: &str) -> CliResult<SkyClient> {
+    let access_token = config.access_token(address)?;
+    Ok(client.with_credentials(Credentials::AccessToken(access_token.into())))
+}
+
+async fn list_posts(client: &SkyClient, args: ListPostsArgs, printer: &Printer) -> CliResult<()> {
+    let request = ListPostsRequest {
+        page_size: args.page_size,
+        page_token: args.page_token,
+        filter: args.filter,
+        order_by: args.order_by,
+    };
+
+    let response = if args.all {
+        let posts: Vec<_> = client.list_all_posts(request).try_collect().await?;
+        ListPostsResponse {
+            total_size: posts.len() as i64,
+            posts,
+            next_page_token: None,
+        }
+    } else {
+        client.list_posts(request).await?
+    };
+
+    printer.print_list(&response, &response.posts, POST_COLUMNS)?;
+    if let Some(next_page_token) = response
+        .next_page_token
+        .as_ref()
+        .filter(|token| !token.is_empty())
+    {
+        printer.print_note(&format!(
+            "{} of {} posts, next page: --page-token {}",
+            response.posts.len(),
+            response.total_size,
+            next_page_token
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "sky",
+            "posts",
+            "list",
+            "--filter",
+            r#"userId = "1""#,
+            "--order-by",
+            "createTime desc",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(matches!(
+            cli.command,
+            Command::Posts {
+                command: PostsCommand::List(ListPostsArgs {
+                    filter: Some(_),
+                    order_by: Some(_),
+                    all: false,
+                    ..
+                })
+            }
+        ));
+
+        assert!(
+            Cli::try_parse_from(["sky", "posts", "list", "--all", "--page-token", "a"]).is_err()
+        );
+    }
+}
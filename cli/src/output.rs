@@ -0,0 +1,287 @@
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{BadRequest, ErrorInfo, Status as ProtoStatus},
+};
+use clap::ValueEnum;
+use comfy_table::{presets::UTF8_FULL_CONDENSED, Table};
+use prost::{Message, Name};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use serde_json::Value as JsonValue;
+use std::{fmt::Write, sync::LazyLock};
+
+use grpc_sky_api::{
+    error::{BAD_REQUEST_REASON, FIELD_VIOLATION_METADATA_KEY},
+    proto::FILE_DESCRIPTOR_SET,
+};
+
+use crate::error::{CliError, CliResult};
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+static DESCRIPTOR_POOL: LazyLock<DescriptorPool> = LazyLock::new(|| {
+    DescriptorPool::decode(FILE_DESCRIPTOR_SET).expect("invalid file descriptor set")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    /// Canonical JSON mapping of messages, as returned by the REST gateway.
+    Json,
+}
+
+pub struct Printer {
+    format: OutputFormat,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    /// Prints a message, as a table of its fields in declaration order.
+    pub fn print_message<M: Message + Name>(&self, message: &M) -> CliResult<()> {
+        let (descriptor, value) = to_json(message)?;
+        if self.format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&value)?);
+            return Ok(());
+        }
+
+        let mut table = make_table();
+        for field in descriptor.fields() {
+            if let Some(field_value) = value.get(field.json_name()) {
+                table.add_row(vec![
+                    field.json_name().to_string(),
+                    format_value(field_value),
+                ]);
+            }
+        }
+        println!("{table}");
+        Ok(())
+    }
+
+    /// Prints a list response, as a table of its items with a column for each `(header, JSON field)`.
+    pub fn print_list<L, M>(
+        &self,
+        response: &L,
+        items: &[M],
+        columns: &[(&str, &str)],
+    ) -> CliResult<()>
+    where
+        L: Message + Name,
+        M: Message + Name,
+    {
+        if self.format == OutputFormat::Json {
+            let (_, value) = to_json(response)?;
+            println!("{}", serde_json::to_string_pretty(&value)?);
+            return Ok(());
+        }
+
+        let mut table = make_table();
+        table.set_header(columns.iter().map(|(header, _)| *header));
+        for item in items {
+            let (_, value) = to_json(item)?;
+            table.add_row(
+                columns
+                    .iter()
+                    .map(|(_, field)| value.get(*field).map(format_value).unwrap_or_default()),
+            );
+        }
+        println!("{table}");
+        Ok(())
+    }
+
+    /// Prints a note to stderr, so that it isn't mixed with JSON output.
+    pub fn print_note(&self, note: &str) {
+        if self.format == OutputFormat::Table {
+            eprintln!("{note}");
+        }
+    }
+
+    pub fn print_error(&self, err: &CliError) {
+        match self.format {
+            OutputFormat::Table => eprintln!("{}", format_error(err)),
+            OutputFormat::Json => {
+                let proto_status = make_proto_status(err);
+                eprintln!(
+                    "{}",
+                    serde_json::to_string_pretty(&proto_status).unwrap_or_default()
+                );
+            }
+        }
+    }
+}
+
+fn to_json<M: Message + Name>(message: &M) -> CliResult<(MessageDescriptor, JsonValue)> {
+    let descriptor = DESCRIPTOR_POOL
+        .get_message_by_name(&M::full_name())
+        .ok_or_else(|| CliError::MessageNotFound(M::full_name()))?;
+    let message = DynamicMessage::decode(descriptor.clone(), message.encode_to_vec().as_slice())?;
+    Ok((descriptor, serde_json::to_value(&message)?))
+}
+
+fn make_table() -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED);
+    table
+}
+
+fn format_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(value) => value.clone(),
+        JsonValue::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Returns the status of the error, or a status with its message for errors of the CLI.
+fn make_proto_status(err: &CliError) -> ProtoStatus {
+    if let CliError::Client(client_err) = err {
+        if let Some(proto_status) = client_err.proto_status() {
+            return proto_status;
+        }
+        if let Some(status) = client_err.status() {
+            return ProtoStatus {
+                code: status.code() as i32,
+                message: status.message().into(),
+                details: Vec::new(),
+            };
+        }
+    }
+    ProtoStatus {
+        code: err.code() as i32,
+        message: err.to_string(),
+        details: Vec::new(),
+    }
+}
+
+/// Formats the error for users, with the reasons and field violations of its details.
+fn format_error(err: &CliError) -> String {
+    let CliError::Client(client_err) = err else {
+        return format!("error: {err}");
+    };
+    let Some(status) = client_err.status() else {
+        return format!("error: {err}");
+    };
+
+    let message = client_err
+        .localized_message()
+        .map(|localized_message| localized_message.message)
+        .unwrap_or_else(|| status.message().to_string());
+    let mut output = format!("error: {}\n  code: {:?}", message, status.code());
+
+    let details = client_err
+        .proto_status()
+        .map(|proto_status| proto_status.details)
+        .unwrap_or_default();
+    let error_infos: Vec<ErrorInfo> = details
+        .iter()
+        .filter_map(|detail| Any::unpack_into(detail.clone()).ok())
+        .collect();
+    // Violations are shown with their fields instead
+    let (violation_infos, error_infos): (Vec<_>, Vec<_>) = error_infos
+        .iter()
+        .filter(|error_info| error_info.reason != BAD_REQUEST_REASON)
+        .partition(|error_info| {
+            error_info
+                .metadata
+                .contains_key(FIELD_VIOLATION_METADATA_KEY)
+        });
+
+    for error_info in error_infos {
+        write!(
+            output,
+            "\n  reason: {} ({})",
+            error_info.reason, error_info.domain
+        )
+        .unwrap();
+        let mut metadata: Vec<_> = error_info.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            write!(output, "\n    {key}: {value}").unwrap();
+        }
+    }
+
+    let bad_requests: Vec<BadRequest> = details
+        .iter()
+        .filter_map(|detail| Any::unpack_into(detail.clone()).ok())
+        .collect();
+    for (index, violation) in bad_requests
+        .into_iter()
+        .flat_map(|bad_request| bad_request.field_violations)
+        .enumerate()
+    {
+        write!(output, "\n  {}: {}", violation.field, violation.description).unwrap();
+        let index = index.to_string();
+        if let Some(error_info) = violation_infos.iter().find(|error_info| {
+            error_info.metadata.get(FIELD_VIOLATION_METADATA_KEY) == Some(&index)
+        }) {
+            write!(output, " ({})", error_info.reason).unwrap();
+        }
+    }
+
+    if let Some(request_id) = status
+        .metadata()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        write!(output, "\n  request ID: {request_id}").unwrap();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use grpc_sky_api::{error::UserError, proto::user_error::UserErrorReason};
+    use grpc_sky_client::error::ClientError;
+    use tonic::{Code, Status};
+
+    use super::*;
+
+    #[test]
+    fn format_errors() {
+        let proto_status = UserError::new(UserErrorReason::BioTooLong)
+            .with_max_length(160)
+            .into_status();
+        let mut status = Status::with_details(
+            Code::from_i32(proto_status.code),
+            proto_status.message.clone(),
+            proto_status.encode_to_vec().into(),
+        );
+        status
+            .metadata_mut()
+            .insert(REQUEST_ID_HEADER, "abc".parse().unwrap());
+        let err = CliError::Client(ClientError::from(status));
+
+        let output = format_error(&err);
+        assert!(output.starts_with(&format!(
+            "error: {}\n  code: InvalidArgument",
+            proto_status.message
+        )));
+        assert!(output.contains("reason: BIO_TOO_LONG"));
+        assert!(output.contains(": 160"));
+        assert!(output.ends_with("request ID: abc"));
+        assert_eq!(make_proto_status(&err), proto_status);
+
+        let err = CliError::NotSignedIn;
+        assert_eq!(
+            format_error(&err),
+            "error: not signed in, run `sky login` first"
+        );
+        assert_eq!(make_proto_status(&err).code, Code::Unauthenticated as i32);
+    }
+
+    #[test]
+    fn print_messages_as_json() {
+        let post = grpc_sky_api::proto::Post {
+            id: "1".into(),
+            user_id: "2".into(),
+            content: "Hello".into(),
+            create_time: None,
+        };
+        let (_, value) = to_json(&post).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"id": "1", "userId": "2", "content": "Hello"})
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Session tokens are refreshed this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// How the client authenticates its calls.
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    /// Signs in for session tokens, and again whenever they expire.
+    Password { name: String, password: String },
+    /// Session token that can't be refreshed.
+    AccessToken(String),
+    /// Personal API key.
+    ApiKey(String),
+}
+
+/// Session token of a signed in user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessToken {
+    pub value: String,
+    /// Read from the token, if it has an expiry.
+    pub expire_time: Option<SystemTime>,
+}
+
+#[derive(Deserialize)]
+struct TokenClaims {
+    exp: Option<u64>,
+}
+
+impl AccessToken {
+    pub fn new(value: String) -> Self {
+        Self {
+            expire_time: read_expire_time(&value),
+            value,
+        }
+    }
+
+    /// Tokens that expire soon are not fresh, so that they don't expire during a call.
+    pub fn is_fresh(&self) -> bool {
+        match self.expire_time {
+            Some(expire_time) => SystemTime::now() + REFRESH_MARGIN < expire_time,
+            None => true,
+        }
+    }
+}
+
+/// Reads the `exp` claim of a JWT, without verifying it.
+fn read_expire_time(token: &str) -> Option<SystemTime> {
+    let payload = token.split('.').nth(1)?;
+    let claims: TokenClaims =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(claims.exp?))
+}
+
+impl Credentials {
+    /// Returns the `authorization` metadata of credentials that don't need a session.
+    pub fn static_authorization(&self) -> Option<String> {
+        match self {
+            Credentials::Password { .. } => None,
+            Credentials::AccessToken(token) => Some(format!("Bearer {}", token)),
+            Credentials::ApiKey(key) => Some(format!("ApiKey {}", key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_token_expiry() {
+        let make_token = |claims: &str| {
+            format!(
+                "eyJhbGciOiJIUzI1NiJ9.{}.c2lnbmF0dXJl",
+                URL_SAFE_NO_PAD.encode(claims)
+            )
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let token = AccessToken::new(make_token(&format!(
+            r#"{{"sub":"1","exp":{}}}"#,
+            now + 3600
+        )));
+        assert_eq!(
+            token.expire_time,
+            Some(UNIX_EPOCH + Duration::from_secs(now + 3600))
+        );
+        assert!(token.is_fresh());
+
+        let token = AccessToken::new(make_token(&format!(r#"{{"exp":{}}}"#, now + 30)));
+        assert!(!token.is_fresh());
+
+        let token = AccessToken::new("opaque".into());
+        assert_eq!(token.expire_time, None);
+        assert!(token.is_fresh());
+    }
+}
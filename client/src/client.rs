@@ -0,0 +1,769 @@
+use futures::Stream;
+use rand::RngCore;
+use std::future::Future;
+use tokio::sync::Mutex;
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Code, Extensions, Request, Response, Status,
+};
+
+use grpc_sky_api::proto::{
+    post_service_client::PostServiceClient, user_service_client::UserServiceClient,
+    BatchGetPostsRequest, BatchGetPostsResponse, BatchGetUsersRequest, BatchGetUsersResponse,
+    ChangePasswordRequest, ExchangeOidcTokenRequest, ExchangeOidcTokenResponse, GetMeRequest,
+    GetUserByNameRequest, GetUserRequest, ListPostsRequest, ListPostsResponse, ListUsersRequest,
+    ListUsersResponse, Post, PostRequest, PostResponse, SignInRequest, SignInResponse,
+    SignUpRequest, SignUpResponse, UnlockAccountRequest, UpdateProfileRequest, User,
+};
+
+use crate::{
+    auth::{AccessToken, Credentials},
+    error::{ClientError, ClientResult},
+    pagination::paginate,
+    retry::RetryPolicy,
+};
+
+const AUTHORIZATION_HEADER: &str = "authorization";
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const ACCEPT_LANGUAGE_HEADER: &str = "accept-language";
+
+/// Client of the user and post services.
+///
+/// Calls are authenticated with the credentials of the client, and session tokens are
+/// refreshed by signing in again. Calls that create resources are sent with an idempotency key,
+/// so that they can be retried safely.
+pub struct SkyClient {
+    user_client: UserServiceClient<Channel>,
+    post_client: PostServiceClient<Channel>,
+    credentials: Option<Credentials>,
+    // Held while signing in, so that concurrent calls share a new token
+    access_token: Mutex<Option<AccessToken>>,
+    retry_policy: RetryPolicy,
+    /// Sent as `accept-language`, for localized error messages.
+    locale: Option<String>,
+}
+
+impl SkyClient {
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            user_client: UserServiceClient::new(channel.clone()),
+            post_client: PostServiceClient::new(channel),
+            credentials: None,
+            access_token: Mutex::new(None),
+            retry_policy: RetryPolicy::default(),
+            locale: None,
+        }
+    }
+
+    /// Connects to an address such as `http://localhost:9000`, with TLS if a config is given.
+    pub async fn connect(
+        address: impl Into<String>,
+        tls_config: Option<ClientTlsConfig>,
+    ) -> ClientResult<Self> {
+        let mut endpoint = Endpoint::from_shared(address.into())?;
+        if let Some(tls_config) = tls_config {
+            endpoint = endpoint.tls_config(tls_config)?;
+        }
+        Ok(Self::new(endpoint.connect().await?))
+    }
+
+    #[must_use]
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        if let Credentials::AccessToken(token) = &credentials {
+            self.access_token = Mutex::new(Some(AccessToken::new(token.clone())));
+        }
+        self.credentials = Some(credentials);
+        self
+    }
+
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    #[must_use]
+    pub fn with_locale<S: ToString>(mut self, locale: S) -> Self {
+        self.locale = Some(locale.to_string());
+        self
+    }
+
+    /// Returns the current session token, if signed in.
+    pub async fn access_token(&self) -> Option<AccessToken> {
+        self.access_token.lock().await.clone()
+    }
+
+    pub async fn sign_up(&self, name: &str, password: &str) -> ClientResult<SignUpResponse> {
+        let request = SignUpRequest {
+            name: name.into(),
+            password: password.into(),
+        };
+        self.send(
+            request,
+            None,
+            Some(&generate_idempotency_key()),
+            |request| {
+                let mut client = self.user_client.clone();
+                async move { client.sign_up(request).await }
+            },
+        )
+        .await
+    }
+
+    /// Signs in and uses the password as the credentials of the client.
+    pub async fn sign_in(&mut self, name: &str, password: &str) -> ClientResult<SignInResponse> {
+        self.credentials = Some(Credentials::Password {
+            name: name.into(),
+            password: password.into(),
+        });
+        let mut access_token = self.access_token.lock().await;
+        let response = self.send_sign_in(name, password).await?;
+        *access_token = Some(AccessToken::new(response.access_token.clone()));
+        Ok(response)
+    }
+
+    /// Exchanges an ID token of an identity provider for a session token.
+    ///
+    /// Clients with credentials link the identity to their user, and other clients use the
+    /// session token as their credentials.
+    pub async fn exchange_oidc_token(
+        &mut self,
+        id_token: &str,
+    ) -> ClientResult<ExchangeOidcTokenResponse> {
+        let request = ExchangeOidcTokenRequest {
+            id_token: id_token.into(),
+        };
+        let response = self
+            .call(request, false, |request| {
+                let mut client = self.user_client.clone();
+                async move { client.exchange_oidc_token(request).await }
+            })
+            .await?;
+        if self.credentials.is_none() {
+            self.credentials = Some(Credentials::AccessToken(response.access_token.clone()));
+            *self.access_token.lock().await = Some(AccessToken::new(response.access_token.clone()));
+        }
+        Ok(response)
+    }
+
+    /// Changes the password of the caller, and the credentials of the client if they used it.
+    pub async fn change_password(
+        &mut self,
+        current_password: Option<&str>,
+        new_password: &str,
+    ) -> ClientResult<()> {
+        let request = ChangePasswordRequest {
+            current_password: current_password.map(Into::into),
+            new_password: new_password.into(),
+        };
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.change_password(request).await }
+        })
+        .await?;
+        if let Some(Credentials::Password { password, .. }) = self.credentials.as_mut() {
+            *password = new_password.into();
+        }
+        Ok(())
+    }
+
+    pub async fn get_me(&self) -> ClientResult<User> {
+        self.call(GetMeRequest {}, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.get_me(request).await }
+        })
+        .await
+    }
+
+    pub async fn get_user_by_name(&self, name: &str) -> ClientResult<User> {
+        let request = GetUserByNameRequest { name: name.into() };
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.get_user_by_name(request).await }
+        })
+        .await
+    }
+
+    /// Gets a user by its resource name, such as `users/{id}`.
+    pub async fn get_user(&self, name: &str) -> ClientResult<User> {
+        let request = GetUserRequest { name: name.into() };
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.get_user(request).await }
+        })
+        .await
+    }
+
+    pub async fn batch_get_users(
+        &self,
+        names: Vec<String>,
+        allow_missing: bool,
+    ) -> ClientResult<BatchGetUsersResponse> {
+        let request = BatchGetUsersRequest {
+            names,
+            allow_missing,
+        };
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.batch_get_users(request).await }
+        })
+        .await
+    }
+
+    pub async fn update_profile(&self, request: UpdateProfileRequest) -> ClientResult<User> {
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.update_profile(request).await }
+        })
+        .await
+    }
+
+    /// Unlocks an account that was locked after failed sign ins.
+    pub async fn unlock_account(&self, name: &str) -> ClientResult<()> {
+        let request = UnlockAccountRequest { name: name.into() };
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.unlock_account(request).await }
+        })
+        .await
+    }
+
+    pub async fn list_users(&self, request: ListUsersRequest) -> ClientResult<ListUsersResponse> {
+        self.call(request, false, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.list_users(request).await }
+        })
+        .await
+    }
+
+    /// Streams users of all pages, starting at the page token of the request.
+    pub fn list_all_users(
+        &self,
+        request: ListUsersRequest,
+    ) -> impl Stream<Item = ClientResult<User>> + '_ {
+        paginate(request.page_token.clone(), move |page_token| {
+            let request = ListUsersRequest {
+                page_token,
+                ..request.clone()
+            };
+            async move {
+                let response = self.list_users(request).await?;
+                Ok((response.users, response.next_page_token))
+            }
+        })
+    }
+
+    pub async fn post(&self, content: &str) -> ClientResult<PostResponse> {
+        let request = PostRequest {
+            content: content.into(),
+        };
+        self.call(request, true, |request| {
+            let mut client = self.post_client.clone();
+            async move { client.post(request).await }
+        })
+        .await
+    }
+
+    pub async fn list_posts(&self, request: ListPostsRequest) -> ClientResult<ListPostsResponse> {
+        self.call(request, false, |request| {
+            let mut client = self.post_client.clone();
+            async move { client.list_posts(request).await }
+        })
+        .await
+    }
+
+    pub async fn batch_get_posts(
+        &self,
+        names: Vec<String>,
+        allow_missing: bool,
+    ) -> ClientResult<BatchGetPostsResponse> {
+        let request = BatchGetPostsRequest {
+            names,
+            allow_missing,
+        };
+        self.call(request, false, |request| {
+            let mut client = self.post_client.clone();
+            async move { client.batch_get_posts(request).await }
+        })
+        .await
+    }
+
+    /// Streams posts of all pages, starting at the page token of the request.
+    pub fn list_all_posts(
+        &self,
+        request: ListPostsRequest,
+    ) -> impl Stream<Item = ClientResult<Post>> + '_ {
+        paginate(request.page_token.clone(), move |page_token| {
+            let request = ListPostsRequest {
+                page_token,
+                ..request.clone()
+            };
+            async move {
+                let response = self.list_posts(request).await?;
+                Ok((response.posts, response.next_page_token))
+            }
+        })
+    }
+
+    /// Sends an authenticated call, signing in again once if the session expired.
+    async fn call<Req, Res, F, Fut>(
+        &self,
+        message: Req,
+        idempotent: bool,
+        send_request: F,
+    ) -> ClientResult<Res>
+    where
+        Req: Clone,
+        F: Fn(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<Res>, Status>>,
+    {
+        let idempotency_key = idempotent.then(generate_idempotency_key);
+        let mut authorization = self.authorize(false).await?;
+        let mut refreshed = false;
+        loop {
+            let result = self
+                .send(
+                    message.clone(),
+                    authorization.as_deref(),
+                    idempotency_key.as_deref(),
+                    &send_request,
+                )
+                .await;
+            match result {
+                Err(err)
+                    if err.code() == Code::Unauthenticated
+                        && !refreshed
+                        && matches!(self.credentials, Some(Credentials::Password { .. })) =>
+                {
+                    authorization = self.authorize(true).await?;
+                    refreshed = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the `authorization` metadata, signing in if there is no fresh session token.
+    async fn authorize(&self, refresh: bool) -> ClientResult<Option<String>> {
+        let Some(credentials) = self.credentials.as_ref() else {
+            return Ok(None);
+        };
+        let Credentials::Password { name, password } = credentials else {
+            return Ok(credentials.static_authorization());
+        };
+
+        let mut access_token = self.access_token.lock().await;
+        match access_token.as_ref() {
+            Some(token) if !refresh && token.is_fresh() => {}
+            _ => {
+                let response = self.send_sign_in(name, password).await?;
+                *access_token = Some(AccessToken::new(response.access_token));
+            }
+        }
+        Ok(access_token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token.value)))
+    }
+
+    async fn send_sign_in(&self, name: &str, password: &str) -> ClientResult<SignInResponse> {
+        let request = SignInRequest {
+            name: name.into(),
+            password: password.into(),
+        };
+        self.send(request, None, None, |request| {
+            let mut client = self.user_client.clone();
+            async move { client.sign_in(request).await }
+        })
+        .await
+    }
+
+    /// Sends a call, and retries it with backoff while the service is unavailable.
+    async fn send<Req, Res, F, Fut>(
+        &self,
+        message: Req,
+        authorization: Option<&str>,
+        idempotency_key: Option<&str>,
+        send_request: F,
+    ) -> ClientResult<Res>
+    where
+        Req: Clone,
+        F: Fn(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<Res>, Status>>,
+    {
+        let metadata = self.make_metadata(authorization, idempotency_key)?;
+        let mut retry = 0;
+        loop {
+            let request = Request::from_parts(metadata.clone(), Extensions::new(), message.clone());
+            match send_request(request).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status)
+                    if retry + 1 < self.retry_policy.max_attempts
+                        && self
+                            .retry_policy
+                            .is_retryable(status.code(), idempotency_key.is_some()) =>
+                {
+                    retry += 1;
+                    tokio::time::sleep(self.retry_policy.backoff(retry)).await;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+    }
+
+    fn make_metadata(
+        &self,
+        authorization: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> ClientResult<MetadataMap> {
+        let mut metadata = MetadataMap::new();
+        for (key, value) in [
+            (AUTHORIZATION_HEADER, authorization),
+            (IDEMPOTENCY_KEY_HEADER, idempotency_key),
+            (ACCEPT_LANGUAGE_HEADER, self.locale.as_deref()),
+        ] {
+            if let Some(value) = value {
+                metadata.insert(key, MetadataValue::try_from(value)?);
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+fn generate_idempotency_key() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+impl From<ClientError> for Status {
+    fn from(err: ClientError) -> Self {
+        match err {
+            ClientError::Sky { status, .. }
+            | ClientError::BadRequest { status, .. }
+            | ClientError::Status(status) => status,
+            err => Status::internal(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+
+    use grpc_sky_api::proto::user_service_server::{UserService, UserServiceServer};
+
+    use super::*;
+
+    /// Users of the mock service, listed by the index of the first user as the page token.
+    const USER_NAMES: &[&str] = &["ada", "bob", "cy", "dee", "eve"];
+
+    #[derive(Default)]
+    struct MockState {
+        sign_ins: usize,
+        /// Idempotency keys of every sign up attempt.
+        sign_up_keys: Vec<String>,
+        /// Token of the current session, if it didn't expire.
+        session_token: Option<String>,
+        /// Password that signs in, if any password does.
+        password: Option<String>,
+        /// Errors of the next calls, before they succeed.
+        errors: Vec<Code>,
+    }
+
+    #[derive(Clone, Default)]
+    struct MockUserService {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    impl MockUserService {
+        fn next_error(&self) -> Result<(), Status> {
+            let mut state = self.state.lock().unwrap();
+            if state.errors.is_empty() {
+                return Ok(());
+            }
+            Err(Status::new(state.errors.remove(0), "mock error"))
+        }
+
+        fn start_session(&self) -> String {
+            let mut state = self.state.lock().unwrap();
+            state.sign_ins += 1;
+            let access_token = format!("token-{}", state.sign_ins);
+            state.session_token = Some(access_token.clone());
+            access_token
+        }
+    }
+
+    #[tonic::async_trait]
+    impl UserService for MockUserService {
+        async fn sign_up(
+            &self,
+            request: Request<SignUpRequest>,
+        ) -> Result<Response<SignUpResponse>, Status> {
+            let idempotency_key = request
+                .metadata()
+                .get(IDEMPOTENCY_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            self.state
+                .lock()
+                .unwrap()
+                .sign_up_keys
+                .push(idempotency_key.into());
+            self.next_error()?;
+            Ok(Response::new(SignUpResponse {
+                user_id: "1".into(),
+            }))
+        }
+
+        async fn sign_in(
+            &self,
+            request: Request<SignInRequest>,
+        ) -> Result<Response<SignInResponse>, Status> {
+            let password = self.state.lock().unwrap().password.clone();
+            if password.is_some_and(|password| password != request.into_inner().password) {
+                return Err(Status::unauthenticated("wrong password"));
+            }
+            Ok(Response::new(SignInResponse {
+                user_id: "1".into(),
+                access_token: self.start_session(),
+            }))
+        }
+
+        async fn exchange_oidc_token(
+            &self,
+            _request: Request<ExchangeOidcTokenRequest>,
+        ) -> Result<Response<ExchangeOidcTokenResponse>, Status> {
+            Ok(Response::new(ExchangeOidcTokenResponse {
+                user_id: "1".into(),
+                access_token: self.start_session(),
+                created: true,
+            }))
+        }
+
+        async fn get_me(&self, request: Request<GetMeRequest>) -> Result<Response<User>, Status> {
+            self.next_error()?;
+            let authorization = request
+                .metadata()
+                .get(AUTHORIZATION_HEADER)
+                .and_then(|value| value.to_str().ok());
+            let session_token = self.state.lock().unwrap().session_token.clone();
+            match (authorization, session_token) {
+                (Some(authorization), Some(session_token))
+                    if authorization == format!("Bearer {}", session_token) =>
+                {
+                    Ok(Response::new(User {
+                        name: USER_NAMES[0].into(),
+                        ..Default::default()
+                    }))
+                }
+                _ => Err(Status::unauthenticated("session expired")),
+            }
+        }
+
+        async fn get_user(
+            &self,
+            _request: Request<GetUserRequest>,
+        ) -> Result<Response<User>, Status> {
+            Err(Status::unimplemented("GetUser"))
+        }
+
+        async fn get_user_by_name(
+            &self,
+            _request: Request<GetUserByNameRequest>,
+        ) -> Result<Response<User>, Status> {
+            Err(Status::unimplemented("GetUserByName"))
+        }
+
+        async fn list_users(
+            &self,
+            request: Request<ListUsersRequest>,
+        ) -> Result<Response<ListUsersResponse>, Status> {
+            let request = request.into_inner();
+            let page_size = request.page_size.unwrap_or(2) as usize;
+            let start = match request.page_token.as_deref() {
+                None | Some("") => 0,
+                Some(page_token) => page_token
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|start| *start < USER_NAMES.len())
+                    .ok_or_else(|| Status::invalid_argument("invalid page token"))?,
+            };
+            let end = (start + page_size).min(USER_NAMES.len());
+            Ok(Response::new(ListUsersResponse {
+                users: USER_NAMES[start..end]
+                    .iter()
+                    .map(|name| User {
+                        name: name.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                next_page_token: (end < USER_NAMES.len()).then(|| end.to_string()),
+                total_size: USER_NAMES.len() as i64,
+            }))
+        }
+
+        async fn batch_get_users(
+            &self,
+            _request: Request<BatchGetUsersRequest>,
+        ) -> Result<Response<BatchGetUsersResponse>, Status> {
+            Err(Status::unimplemented("BatchGetUsers"))
+        }
+
+        async fn update_profile(
+            &self,
+            _request: Request<UpdateProfileRequest>,
+        ) -> Result<Response<User>, Status> {
+            Err(Status::unimplemented("UpdateProfile"))
+        }
+
+        async fn change_password(
+            &self,
+            request: Request<ChangePasswordRequest>,
+        ) -> Result<Response<()>, Status> {
+            let mut state = self.state.lock().unwrap();
+            state.password = Some(request.into_inner().new_password);
+            // Other sessions end when the password changes
+            state.session_token = None;
+            Ok(Response::new(()))
+        }
+
+        async fn unlock_account(
+            &self,
+            _request: Request<UnlockAccountRequest>,
+        ) -> Result<Response<()>, Status> {
+            Err(Status::unimplemented("UnlockAccount"))
+        }
+    }
+
+    /// Serves a mock user service and connects a client to it.
+    async fn connect() -> (SkyClient, Arc<Mutex<MockState>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let service = MockUserService::default();
+        let state = service.state.clone();
+        tokio::spawn(
+            Server::builder()
+                .add_service(UserServiceServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let client = SkyClient::connect(format!("http://{}", address), None)
+            .await
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+                multiplier: 2.0,
+            });
+        (client, state)
+    }
+
+    #[tokio::test]
+    async fn refresh_session() {
+        let (mut client, state) = connect().await;
+        client.sign_in("ada", "password").await.unwrap();
+        assert_eq!(client.get_me().await.unwrap().name, "ada");
+
+        state.lock().unwrap().session_token = None;
+        assert_eq!(client.get_me().await.unwrap().name, "ada");
+        assert_eq!(state.lock().unwrap().sign_ins, 2);
+        assert_eq!(client.access_token().await.unwrap().value, "token-2");
+
+        // Sessions are refreshed once per call
+        state.lock().unwrap().errors = vec![Code::Unauthenticated; 2];
+        assert_eq!(
+            client.get_me().await.unwrap_err().code(),
+            Code::Unauthenticated
+        );
+        assert_eq!(state.lock().unwrap().sign_ins, 3);
+    }
+
+    #[tokio::test]
+    async fn change_credentials() {
+        let (mut client, state) = connect().await;
+        let response = client.exchange_oidc_token("id-token").await.unwrap();
+        assert_eq!(
+            client.access_token().await.unwrap().value,
+            response.access_token
+        );
+        assert_eq!(client.get_me().await.unwrap().name, "ada");
+
+        // Calls sign in with the new password once the session ends
+        client.sign_in("ada", "password").await.unwrap();
+        client
+            .change_password(Some("password"), "new-password")
+            .await
+            .unwrap();
+        assert_eq!(client.get_me().await.unwrap().name, "ada");
+        assert_eq!(state.lock().unwrap().sign_ins, 3);
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff() {
+        let (client, state) = connect().await;
+
+        // Retries are sent with the same idempotency key
+        state.lock().unwrap().errors = vec![Code::Unavailable, Code::Aborted];
+        client.sign_up("ada", "password").await.unwrap();
+        let sign_up_keys = std::mem::take(&mut state.lock().unwrap().sign_up_keys);
+        assert_eq!(sign_up_keys.len(), 3);
+        assert!(sign_up_keys.iter().all(|key| *key == sign_up_keys[0]));
+
+        state.lock().unwrap().errors = vec![Code::Unavailable; 3];
+        assert_eq!(
+            client.sign_up("ada", "password").await.unwrap_err().code(),
+            Code::Unavailable
+        );
+        assert_eq!(state.lock().unwrap().sign_up_keys.len(), 3);
+
+        // Calls without idempotency keys are not retried while in progress
+        state.lock().unwrap().errors = vec![Code::Aborted, Code::Aborted];
+        assert_eq!(client.get_me().await.unwrap_err().code(), Code::Aborted);
+        assert_eq!(state.lock().unwrap().errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn list_all_pages() {
+        let (client, _) = connect().await;
+
+        let users: Vec<User> = client
+            .list_all_users(ListUsersRequest {
+                page_size: Some(2),
+                ..Default::default()
+            })
+            .try_collect()
+            .await
+            .unwrap();
+        let names: Vec<&str> = users.iter().map(|user| user.name.as_str()).collect();
+        assert_eq!(names, USER_NAMES);
+
+        let users: Vec<User> = client
+            .list_all_users(ListUsersRequest {
+                page_token: Some("3".into()),
+                ..Default::default()
+            })
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 2);
+
+        let err = client
+            .list_all_users(ListUsersRequest {
+                page_token: Some("invalid".into()),
+                ..Default::default()
+            })
+            .try_collect::<Vec<User>>()
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,168 @@
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{LocalizedMessage, Status as ProtoStatus},
+};
+use bomboni_request::error::RequestError;
+use prost::Message;
+use thiserror::Error;
+use tonic::{metadata::errors::InvalidMetadataValue, transport, Code, Status};
+
+use grpc_sky_api::error::{decode_request_error, SkyError};
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("transport error: {0}")]
+    Transport(#[from] transport::Error),
+    #[error("invalid metadata value: {0}")]
+    InvalidMetadataValue(#[from] InvalidMetadataValue),
+    /// Status with a known error, decoded from its `ErrorInfo` details.
+    #[error("{error}")]
+    Sky { error: SkyError, status: Status },
+    /// Invalid request, with the errors of its fields decoded from its `BadRequest` details.
+    #[error("{error}")]
+    BadRequest { error: RequestError, status: Status },
+    #[error("{}: {}", .0.code(), .0.message())]
+    Status(Status),
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+impl From<Status> for ClientError {
+    fn from(status: Status) -> Self {
+        let Some(proto_status) = decode_proto_status(&status) else {
+            return ClientError::Status(status);
+        };
+        if let Some(error) = SkyError::from_status(proto_status.clone()) {
+            return ClientError::Sky { error, status };
+        }
+        match decode_request_error(proto_status) {
+            Some(error @ RequestError::BadRequest { .. }) => {
+                ClientError::BadRequest { error, status }
+            }
+            _ => ClientError::Status(status),
+        }
+    }
+}
+
+impl ClientError {
+    pub fn status(&self) -> Option<&Status> {
+        match self {
+            ClientError::Sky { status, .. }
+            | ClientError::BadRequest { status, .. }
+            | ClientError::Status(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.status().map_or(Code::Unknown, Status::code)
+    }
+
+    /// Returns the status with its decoded details.
+    pub fn proto_status(&self) -> Option<ProtoStatus> {
+        decode_proto_status(self.status()?)
+    }
+
+    /// Returns the message for users, in the locale that was requested if it is supported.
+    pub fn localized_message(&self) -> Option<LocalizedMessage> {
+        self.proto_status()?
+            .details
+            .into_iter()
+            .find_map(|detail| Any::unpack_into(detail).ok())
+    }
+}
+
+fn decode_proto_status(status: &Status) -> Option<ProtoStatus> {
+    if status.details().is_empty() {
+        return None;
+    }
+    ProtoStatus::decode(status.details()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use bomboni_request::error::{PathError, PathErrorStep};
+    use grpc_sky_api::{
+        error::{encode_request_error, PostError, UserError},
+        proto::{common_error::CommonErrorReason, user_error::UserErrorReason},
+    };
+
+    use super::*;
+
+    fn make_status(proto_status: ProtoStatus) -> Status {
+        Status::with_details(
+            Code::from_i32(proto_status.code),
+            proto_status.message.clone(),
+            proto_status.encode_to_vec().into(),
+        )
+    }
+
+    #[test]
+    fn decode_errors() {
+        let user_error = UserError::new(UserErrorReason::BioTooLong).with_max_length(160);
+        let mut proto_status = user_error.clone().into_status();
+        proto_status.details.push(
+            Any::pack_from(&LocalizedMessage {
+                locale: "en".into(),
+                message: "The bio can have at most 160 characters.".into(),
+            })
+            .unwrap(),
+        );
+        let err = ClientError::from(make_status(proto_status));
+        assert!(matches!(
+            &err,
+            ClientError::Sky { error: SkyError::User(error), .. } if *error == user_error
+        ));
+        assert_eq!(err.code(), Code::InvalidArgument);
+        assert_eq!(
+            err.localized_message().map(|message| message.message),
+            Some("The bio can have at most 160 characters.".into())
+        );
+
+        let err = ClientError::from(make_status(ProtoStatus::from(RequestError::generic(
+            PostError::new(CommonErrorReason::NotFound),
+        ))));
+        assert!(matches!(
+            err,
+            ClientError::Sky {
+                error: SkyError::Common(CommonErrorReason::NotFound),
+                ..
+            }
+        ));
+
+        let err = ClientError::from(Status::unavailable("connection refused"));
+        assert!(matches!(err, ClientError::Status(_)));
+        assert_eq!(err.code(), Code::Unavailable);
+        assert_eq!(err.localized_message(), None);
+    }
+
+    #[test]
+    fn decode_bad_requests() {
+        let user_error = UserError::new(UserErrorReason::BioTooLong).with_max_length(160);
+        let err = ClientError::from(make_status(encode_request_error(
+            RequestError::BadRequest {
+                name: "sky.v1.UpdateProfileRequest".into(),
+                violations: vec![PathError {
+                    path: vec![
+                        PathErrorStep::Field("user".into()),
+                        PathErrorStep::Field("bio".into()),
+                    ],
+                    error: Box::new(user_error.clone()),
+                }],
+            },
+        )));
+        let ClientError::BadRequest {
+            error: RequestError::BadRequest { name, violations },
+            ..
+        } = &err
+        else {
+            panic!("expected a bad request");
+        };
+        assert_eq!(name, "sky.v1.UpdateProfileRequest");
+        assert_eq!(
+            violations[0].error.as_any().downcast_ref::<UserError>(),
+            Some(&user_error)
+        );
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+}
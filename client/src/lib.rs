@@ -0,0 +1,7 @@
+pub mod auth;
+mod client;
+pub mod error;
+pub mod pagination;
+pub mod retry;
+
+pub use client::SkyClient;
@@ -0,0 +1,70 @@
+use futures::{stream, Stream, TryStreamExt};
+use std::future::Future;
+
+use crate::error::ClientResult;
+
+/// Streams the items of every page, starting at the page of `page_token`.
+///
+/// `fetch_page` returns the items of a page and the token of the next one,
+/// which is missing or empty on the last page.
+pub fn paginate<'a, T, F, Fut>(
+    page_token: Option<String>,
+    fetch_page: F,
+) -> impl Stream<Item = ClientResult<T>> + 'a
+where
+    T: 'a,
+    F: FnMut(Option<String>) -> Fut + 'a,
+    Fut: Future<Output = ClientResult<(Vec<T>, Option<String>)>> + 'a,
+{
+    // The next page token is `None` after the last page
+    stream::try_unfold(
+        (fetch_page, Some(page_token)),
+        |(mut fetch_page, page_token)| async move {
+            let Some(page_token) = page_token else {
+                return Ok(None);
+            };
+            let (items, next_page_token) = fetch_page(page_token).await?;
+            let next_page_token = next_page_token.filter(|token| !token.is_empty()).map(Some);
+            Ok(Some((
+                stream::iter(items.into_iter().map(Ok)),
+                (fetch_page, next_page_token),
+            )))
+        },
+    )
+    .try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, StreamExt};
+    use tonic::Status;
+
+    use crate::error::ClientError;
+
+    use super::*;
+
+    #[test]
+    fn stream_pages() {
+        let pages = |page_token: Option<String>| async move {
+            match page_token.as_deref() {
+                None => Ok((vec![1, 2], Some("a".to_string()))),
+                Some("a") => Ok((Vec::new(), Some("b".to_string()))),
+                Some("b") => Ok((vec![3], Some(String::new()))),
+                _ => Err(ClientError::from(Status::invalid_argument(
+                    "invalid page token",
+                ))),
+            }
+        };
+
+        let items: Vec<i32> = block_on(paginate(None, pages).try_collect()).unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+
+        let items: Vec<i32> = block_on(paginate(Some("b".into()), pages).try_collect()).unwrap();
+        assert_eq!(items, vec![3]);
+
+        // Stops at the first error
+        let results: Vec<ClientResult<i32>> = block_on(paginate(Some("c".into()), pages).collect());
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
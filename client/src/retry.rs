@@ -0,0 +1,65 @@
+use rand::Rng;
+use std::time::Duration;
+use tonic::Code;
+
+/// Retries of calls that failed because the service was unavailable.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first call, so `1` disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Calls with idempotency keys are also retried while the first attempt is in progress.
+    pub fn is_retryable(&self, code: Code, idempotent: bool) -> bool {
+        match code {
+            Code::Unavailable => true,
+            Code::Aborted => idempotent,
+            _ => false,
+        }
+    }
+
+    /// Returns a random delay up to the exponential backoff of the retry, starting at `1`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .mul_f64(self.multiplier.powi(retry.saturating_sub(1) as i32))
+            .min(self.max_backoff);
+        // Full jitter, so that clients don't retry in lockstep
+        backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_with_backoff() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(Code::Unavailable, false));
+        assert!(policy.is_retryable(Code::Aborted, true));
+        assert!(!policy.is_retryable(Code::Aborted, false));
+        assert!(!policy.is_retryable(Code::InvalidArgument, true));
+
+        for (retry, max_backoff) in [(1, 100), (2, 200), (3, 400), (10, 5000)] {
+            for _ in 0..10 {
+                assert!(policy.backoff(retry) <= Duration::from_millis(max_backoff));
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+ALTER TABLE users
+    DROP COLUMN display_name,
+    DROP COLUMN bio,
+    DROP COLUMN avatar_url,
+    DROP COLUMN location,
+    DROP COLUMN website,
+    DROP COLUMN create_time,
+    DROP COLUMN update_time;
@@ -0,0 +1,8 @@
+ALTER TABLE users
+    ADD COLUMN display_name VARCHAR(50) NULL,
+    ADD COLUMN bio VARCHAR(160) NULL,
+    ADD COLUMN avatar_url VARCHAR(255) NULL,
+    ADD COLUMN location VARCHAR(30) NULL,
+    ADD COLUMN website VARCHAR(255) NULL,
+    ADD COLUMN create_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN update_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP;
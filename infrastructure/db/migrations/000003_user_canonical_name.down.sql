@@ -0,0 +1,8 @@
+-- Renamed users keep their new names
+DROP INDEX users_canonical_name_idx ON users;
+
+CREATE UNIQUE INDEX users_name_idx ON users ((LOWER(name)));
+
+DROP TABLE user_name_conflicts;
+
+ALTER TABLE users DROP COLUMN canonical_name;
@@ -0,0 +1,38 @@
+-- Canonical names are compared byte for byte, so that `O` and `o` stay distinct
+ALTER TABLE users ADD COLUMN canonical_name VARCHAR(64) COLLATE utf8mb4_bin NULL;
+
+-- Existing names only have ASCII letters, digits and underscores, for which
+-- `canonicalize_user_name` lowercases and replaces `m`, `0` and `1` with `rn`, `O` and `l`
+UPDATE users
+SET canonical_name = REPLACE(REPLACE(REPLACE(LOWER(name), 'm', 'rn'), '0', 'O'), '1', 'l');
+
+-- Users whose names clash with an older user's once canonicalized are renamed,
+-- and their previous names are kept so that they can be told
+CREATE TABLE user_name_conflicts (
+    user_id CHAR(26) PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    previous_name VARCHAR(16) NOT NULL,
+    create_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO user_name_conflicts (user_id, previous_name)
+SELECT u.id, u.name
+FROM users AS u
+WHERE EXISTS (
+    SELECT 1 FROM users AS older
+    WHERE older.canonical_name = u.canonical_name AND older.id < u.id
+);
+
+UPDATE users
+JOIN user_name_conflicts ON user_name_conflicts.user_id = users.id
+SET users.name = CONCAT('user_', LOWER(RIGHT(users.id, 11)));
+
+UPDATE users
+JOIN user_name_conflicts ON user_name_conflicts.user_id = users.id
+SET users.canonical_name =
+    REPLACE(REPLACE(REPLACE(LOWER(users.name), 'm', 'rn'), '0', 'O'), '1', 'l');
+
+ALTER TABLE users MODIFY canonical_name VARCHAR(64) COLLATE utf8mb4_bin NOT NULL;
+
+DROP INDEX users_name_idx ON users;
+
+CREATE UNIQUE INDEX users_canonical_name_idx ON users (canonical_name);
@@ -0,0 +1,3 @@
+-- The first admin has to be promoted manually, e.g.
+-- UPDATE users SET role = 'admin' WHERE canonical_name = '...';
+ALTER TABLE users ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'user';
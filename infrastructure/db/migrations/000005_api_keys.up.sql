@@ -0,0 +1,17 @@
+CREATE TABLE api_keys (
+    id CHAR(26) PRIMARY KEY,
+    user_id CHAR(26) NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    display_name VARCHAR(50) NOT NULL,
+    prefix CHAR(8) NOT NULL,
+    -- SHA-256 of the full key
+    key_hash CHAR(64) NOT NULL,
+    -- Space separated permissions
+    scopes VARCHAR(512) NOT NULL,
+    create_time DATETIME NOT NULL,
+    expire_time DATETIME NOT NULL,
+    last_use_time DATETIME NULL
+);
+
+CREATE UNIQUE INDEX api_keys_prefix_idx ON api_keys (prefix);
+
+CREATE INDEX api_keys_user_id_idx ON api_keys (user_id);
@@ -0,0 +1,5 @@
+UPDATE users SET password_hash = '' WHERE password_hash IS NULL;
+
+ALTER TABLE users MODIFY password_hash VARCHAR(255) NOT NULL;
+
+DROP TABLE user_identities;
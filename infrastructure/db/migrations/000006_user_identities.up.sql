@@ -0,0 +1,13 @@
+-- Links users to accounts of external identity providers
+CREATE TABLE user_identities (
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id CHAR(26) NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    create_time DATETIME NOT NULL,
+    PRIMARY KEY (issuer, subject)
+);
+
+CREATE INDEX user_identities_user_id_idx ON user_identities (user_id);
+
+-- Users who sign up with an identity provider have no password until they set one
+ALTER TABLE users MODIFY password_hash VARCHAR(255) NULL;
@@ -0,0 +1,18 @@
+-- Responses of mutating calls, replayed for retries with the same key
+CREATE TABLE idempotency_keys (
+    scope VARCHAR(64) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    -- SHA-256 of the encoded request
+    request_hash CHAR(64) NOT NULL,
+    -- Encoded response, unset while the first call is in progress
+    response BLOB NULL,
+    -- Encoded `google.rpc.Status` of a failed call that is not retried
+    error BLOB NULL,
+    create_time DATETIME NOT NULL,
+    expire_time DATETIME NOT NULL,
+    -- A call in progress that didn't finish by this time is taken over by a retry
+    locked_until DATETIME NOT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX idempotency_keys_expire_time_idx ON idempotency_keys (expire_time);
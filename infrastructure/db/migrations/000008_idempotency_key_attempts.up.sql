@@ -0,0 +1,3 @@
+-- Call that holds the key, so that a call whose key was taken over by a retry
+-- can't overwrite or release the retry's key
+ALTER TABLE idempotency_keys ADD COLUMN attempt_id CHAR(26) NOT NULL DEFAULT '';
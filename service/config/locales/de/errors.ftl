@@ -0,0 +1,60 @@
+# Messages of error reasons, with the `ErrorInfo` metadata of errors as variables.
+
+BAD_REQUEST = Die Anfrage ist ungültig.
+RATE_LIMIT_EXCEEDED = Zu viele Anfragen. Versuche es später erneut.
+
+COMMON_ERROR_REASON_RESOURCE_NOT_FOUND = Die angeforderte Ressource wurde nicht gefunden.
+COMMON_ERROR_REASON_UNAUTHORIZED = Du bist nicht angemeldet.
+COMMON_ERROR_REASON_REQUIRED_FIELD_MISSING = Ein Pflichtfeld fehlt.
+COMMON_ERROR_REASON_INVALID_NAME = Der Ressourcenname ist ungültig.
+COMMON_ERROR_REASON_INVALID_PARENT = Der Name der übergeordneten Ressource ist ungültig.
+COMMON_ERROR_REASON_INVALID_STRING_FORMAT = Der Wert hat ein ungültiges Format.
+COMMON_ERROR_REASON_INVALID_ID = Die ID ist ungültig.
+COMMON_ERROR_REASON_DUPLICATE_ID = Die ID wurde mehrfach angegeben.
+COMMON_ERROR_REASON_INVALID_DISPLAY_NAME = Der Anzeigename ist ungültig.
+COMMON_ERROR_REASON_INVALID_DATE_TIME = Datum und Uhrzeit sind ungültig.
+COMMON_ERROR_REASON_INVALID_ENUM_VALUE = Der Wert ist keine der erlaubten Optionen.
+COMMON_ERROR_REASON_UNKNOWN_ONEOF_VARIANT = Der Wert ist keine der erlaubten Arten.
+COMMON_ERROR_REASON_INVALID_NUMERIC_VALUE = Die Zahl ist ungültig.
+COMMON_ERROR_REASON_FAILED_CONVERT_VALUE = Der Wert konnte nicht umgewandelt werden.
+COMMON_ERROR_REASON_NUMERIC_OUT_OF_RANGE = Die Zahl liegt außerhalb des erlaubten Bereichs.
+COMMON_ERROR_REASON_DUPLICATE_VALUE = Der Wert wurde mehrfach angegeben.
+COMMON_ERROR_REASON_ALREADY_EXISTS = Es existiert bereits.
+COMMON_ERROR_REASON_NOT_FOUND = Es wurde nicht gefunden.
+COMMON_ERROR_REASON_TYPE_MISMATCH = Der Wert hat den falschen Typ.
+
+USER_ERROR_REASON_INVALID_NAME = Der Benutzername „{ $userName }“ ist ungültig.
+USER_ERROR_REASON_INVALID_PASSWORD = Das Passwort ist ungültig.
+USER_ERROR_REASON_INCORRECT_CREDENTIALS = Benutzername oder Passwort ist falsch.
+USER_ERROR_REASON_BIO_TOO_LONG = Die Biografie darf höchstens { $maxLength } Zeichen lang sein.
+USER_ERROR_REASON_LOCATION_TOO_LONG = Der Ort darf höchstens { $maxLength } Zeichen lang sein.
+USER_ERROR_REASON_INVALID_AVATAR_URL = Die Avatar-URL ist ungültig.
+USER_ERROR_REASON_INVALID_WEBSITE = Die Website ist keine gültige URL.
+USER_ERROR_REASON_PASSWORD_TOO_SHORT = Das Passwort muss mindestens { $minLength } Zeichen lang sein.
+USER_ERROR_REASON_PASSWORD_MISSING_LOWERCASE = Das Passwort muss einen Kleinbuchstaben enthalten.
+USER_ERROR_REASON_PASSWORD_MISSING_UPPERCASE = Das Passwort muss einen Großbuchstaben enthalten.
+USER_ERROR_REASON_PASSWORD_MISSING_DIGIT = Das Passwort muss eine Ziffer enthalten.
+USER_ERROR_REASON_PASSWORD_MISSING_SYMBOL = Das Passwort muss ein Sonderzeichen enthalten.
+USER_ERROR_REASON_PASSWORD_BREACHED = Dieses Passwort ist aus einem Datenleck bekannt. Wähle ein anderes.
+USER_ERROR_REASON_PASSWORD_SIMILAR_TO_NAME = Das Passwort ist dem Benutzernamen zu ähnlich.
+USER_ERROR_REASON_ACCOUNT_LOCKED = Das Konto ist nach zu vielen fehlgeschlagenen Anmeldungen gesperrt. Versuche es später erneut.
+USER_ERROR_REASON_TOO_MANY_ATTEMPTS = Zu viele fehlgeschlagene Anmeldungen. Versuche es später erneut.
+USER_ERROR_REASON_INVALID_API_KEY_SCOPE = „{ $permission }“ ist kein gültiger Bereich für API-Schlüssel.
+USER_ERROR_REASON_TOO_MANY_API_KEYS = Du hast zu viele API-Schlüssel. Widerrufe einen, um einen neuen zu erstellen.
+
+IDEMPOTENCY_ERROR_REASON_INVALID_KEY = Der Idempotenzschlüssel ist ungültig.
+IDEMPOTENCY_ERROR_REASON_KEY_REUSED = Der Idempotenzschlüssel wurde bereits für eine andere Anfrage verwendet.
+IDEMPOTENCY_ERROR_REASON_REQUEST_IN_PROGRESS = Eine Anfrage mit diesem Idempotenzschlüssel wird noch bearbeitet.
+
+POST_ERROR_REASON_POST_NOT_FOUND = Der Beitrag wurde nicht gefunden.
+
+SOCIAL_ERROR_REASON_CANNOT_FOLLOW_SELF = Du kannst dir nicht selbst folgen.
+SOCIAL_ERROR_REASON_ALREADY_FOLLOWING = Du folgst diesem Benutzer bereits.
+SOCIAL_ERROR_REASON_NOT_FOLLOWING = Du folgst diesem Benutzer nicht.
+SOCIAL_ERROR_REASON_USER_BLOCKED = Du kannst nicht mit diesem Benutzer interagieren.
+
+AUTH_ERROR_REASON_MISSING_CREDENTIALS = Melde dich an, um fortzufahren.
+AUTH_ERROR_REASON_INVALID_CREDENTIALS = Deine Sitzung ist ungültig. Melde dich erneut an.
+AUTH_ERROR_REASON_INVALID_ID_TOKEN = Die Anmeldung bei deinem Identitätsanbieter ist ungültig.
+AUTH_ERROR_REASON_MISSING_CLIENT_CERTIFICATE = Ein Client-Zertifikat ist erforderlich.
+AUTH_ERROR_REASON_PERMISSION_DENIED = Dazu fehlt dir die Berechtigung.
@@ -0,0 +1,60 @@
+# Messages of error reasons, with the `ErrorInfo` metadata of errors as variables.
+
+BAD_REQUEST = The request is not valid.
+RATE_LIMIT_EXCEEDED = Too many requests. Try again later.
+
+COMMON_ERROR_REASON_RESOURCE_NOT_FOUND = The requested resource was not found.
+COMMON_ERROR_REASON_UNAUTHORIZED = You are not signed in.
+COMMON_ERROR_REASON_REQUIRED_FIELD_MISSING = A required field is missing.
+COMMON_ERROR_REASON_INVALID_NAME = The resource name is not valid.
+COMMON_ERROR_REASON_INVALID_PARENT = The parent resource name is not valid.
+COMMON_ERROR_REASON_INVALID_STRING_FORMAT = The value has an invalid format.
+COMMON_ERROR_REASON_INVALID_ID = The ID is not valid.
+COMMON_ERROR_REASON_DUPLICATE_ID = The ID is given more than once.
+COMMON_ERROR_REASON_INVALID_DISPLAY_NAME = The display name is not valid.
+COMMON_ERROR_REASON_INVALID_DATE_TIME = The date and time are not valid.
+COMMON_ERROR_REASON_INVALID_ENUM_VALUE = The value is not one of the allowed options.
+COMMON_ERROR_REASON_UNKNOWN_ONEOF_VARIANT = The value is not one of the allowed kinds.
+COMMON_ERROR_REASON_INVALID_NUMERIC_VALUE = The number is not valid.
+COMMON_ERROR_REASON_FAILED_CONVERT_VALUE = The value could not be converted.
+COMMON_ERROR_REASON_NUMERIC_OUT_OF_RANGE = The number is out of range.
+COMMON_ERROR_REASON_DUPLICATE_VALUE = The value is given more than once.
+COMMON_ERROR_REASON_ALREADY_EXISTS = It already exists.
+COMMON_ERROR_REASON_NOT_FOUND = It was not found.
+COMMON_ERROR_REASON_TYPE_MISMATCH = The value has the wrong type.
+
+USER_ERROR_REASON_INVALID_NAME = The user name "{ $userName }" is not valid.
+USER_ERROR_REASON_INVALID_PASSWORD = The password is not valid.
+USER_ERROR_REASON_INCORRECT_CREDENTIALS = The user name or password is incorrect.
+USER_ERROR_REASON_BIO_TOO_LONG = The bio can have at most { $maxLength } characters.
+USER_ERROR_REASON_LOCATION_TOO_LONG = The location can have at most { $maxLength } characters.
+USER_ERROR_REASON_INVALID_AVATAR_URL = The avatar URL is not valid.
+USER_ERROR_REASON_INVALID_WEBSITE = The website is not a valid URL.
+USER_ERROR_REASON_PASSWORD_TOO_SHORT = The password must have at least { $minLength } characters.
+USER_ERROR_REASON_PASSWORD_MISSING_LOWERCASE = The password must contain a lowercase letter.
+USER_ERROR_REASON_PASSWORD_MISSING_UPPERCASE = The password must contain an uppercase letter.
+USER_ERROR_REASON_PASSWORD_MISSING_DIGIT = The password must contain a digit.
+USER_ERROR_REASON_PASSWORD_MISSING_SYMBOL = The password must contain a symbol.
+USER_ERROR_REASON_PASSWORD_BREACHED = This password appeared in a data breach. Choose another one.
+USER_ERROR_REASON_PASSWORD_SIMILAR_TO_NAME = The password is too similar to the user name.
+USER_ERROR_REASON_ACCOUNT_LOCKED = The account is locked after too many failed sign-ins. Try again later.
+USER_ERROR_REASON_TOO_MANY_ATTEMPTS = Too many failed sign-ins. Try again later.
+USER_ERROR_REASON_INVALID_API_KEY_SCOPE = "{ $permission }" is not a valid API key scope.
+USER_ERROR_REASON_TOO_MANY_API_KEYS = You have too many API keys. Revoke one to create another.
+
+IDEMPOTENCY_ERROR_REASON_INVALID_KEY = The idempotency key is not valid.
+IDEMPOTENCY_ERROR_REASON_KEY_REUSED = The idempotency key was already used for a different request.
+IDEMPOTENCY_ERROR_REASON_REQUEST_IN_PROGRESS = A request with this idempotency key is still in progress.
+
+POST_ERROR_REASON_POST_NOT_FOUND = The post was not found.
+
+SOCIAL_ERROR_REASON_CANNOT_FOLLOW_SELF = You can't follow yourself.
+SOCIAL_ERROR_REASON_ALREADY_FOLLOWING = You already follow this user.
+SOCIAL_ERROR_REASON_NOT_FOLLOWING = You don't follow this user.
+SOCIAL_ERROR_REASON_USER_BLOCKED = You can't interact with this user.
+
+AUTH_ERROR_REASON_MISSING_CREDENTIALS = Sign in to continue.
+AUTH_ERROR_REASON_INVALID_CREDENTIALS = Your session is not valid. Sign in again.
+AUTH_ERROR_REASON_INVALID_ID_TOKEN = The sign-in with your identity provider is not valid.
+AUTH_ERROR_REASON_MISSING_CLIENT_CERTIFICATE = A client certificate is required.
+AUTH_ERROR_REASON_PERMISSION_DENIED = You don't have permission to do this.
@@ -0,0 +1,115 @@
+use bomboni_request::parse::RequestParse;
+use std::fmt::Debug;
+use tonic::{Request, Response};
+
+use grpc_sky_api::{
+    dto::{CreateApiKeyRequestDto, RevokeApiKeyRequestDto},
+    proto::{
+        api_key_service_server::ApiKeyService, CreateApiKeyRequest, CreateApiKeyResponse,
+        ListApiKeysRequest, ListApiKeysResponse, RevokeApiKeyRequest,
+    },
+};
+
+use crate::{
+    api_key::{
+        create_command::{CreateApiKeyCommand, CreateApiKeyCommandInput},
+        query_manager::{make_api_key, ApiKeyQueryManager},
+        revoke_command::{RevokeApiKeyCommand, RevokeApiKeyCommandInput},
+    },
+    context::Context,
+};
+
+pub struct ApiKeyAdapter {
+    create_api_key_command: CreateApiKeyCommand,
+    revoke_api_key_command: RevokeApiKeyCommand,
+    api_key_query_manager: ApiKeyQueryManager,
+}
+
+impl ApiKeyAdapter {
+    pub fn new(
+        create_api_key_command: CreateApiKeyCommand,
+        revoke_api_key_command: RevokeApiKeyCommand,
+        api_key_query_manager: ApiKeyQueryManager,
+    ) -> Self {
+        Self {
+            create_api_key_command,
+            revoke_api_key_command,
+            api_key_query_manager,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ApiKeyService for ApiKeyAdapter {
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = CreateApiKeyRequestDto::parse(request.into_inner())?;
+
+        let output = self
+            .create_api_key_command
+            .execute(
+                &context,
+                CreateApiKeyCommandInput {
+                    display_name: &request.display_name,
+                    scopes: &request.scopes,
+                    expire_time: request.expire_time,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(CreateApiKeyResponse {
+            api_key: Some(make_api_key(output.api_key)),
+            key: output.key,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let api_keys = self.api_key_query_manager.list(&context).await?;
+
+        Ok(Response::new(ListApiKeysResponse { api_keys }))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<()>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = RevokeApiKeyRequestDto::parse(request.into_inner())?;
+
+        self.revoke_api_key_command
+            .execute(
+                &context,
+                RevokeApiKeyCommandInput {
+                    api_key_id: request.name,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(()))
+    }
+}
+
+impl Debug for ApiKeyAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyAdapter").finish()
+    }
+}
@@ -0,0 +1,140 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::error::{CommonError, PathError, PathErrorStep, RequestError};
+use prost::Name;
+use tonic::Code;
+
+use grpc_sky_api::{
+    error::UserError,
+    proto::{user_error::UserErrorReason, CreateApiKeyRequest},
+};
+
+use crate::{
+    api_key::{
+        key::generate_api_key,
+        repository::{ApiKeyInsertRecord, ApiKeyRecord, ApiKeyRepositoryArc},
+    },
+    auth::permission::Permission,
+    config::ApiKeyConfig,
+    context::Context,
+    date_time::add_seconds,
+    error::{make_status, AppResult},
+};
+
+pub struct CreateApiKeyCommand {
+    api_key_repository: ApiKeyRepositoryArc,
+    config: ApiKeyConfig,
+}
+
+pub struct CreateApiKeyCommandInput<'a> {
+    pub display_name: &'a str,
+    pub scopes: &'a [String],
+    pub expire_time: Option<UtcDateTime>,
+}
+
+pub struct CreateApiKeyCommandOutput {
+    pub api_key: ApiKeyRecord,
+    /// The secret key, which is not stored.
+    pub key: String,
+}
+
+impl CreateApiKeyCommand {
+    pub const PERMISSION: Permission = Permission::ApiKeysManage;
+
+    pub fn new(api_key_repository: ApiKeyRepositoryArc, config: ApiKeyConfig) -> Self {
+        Self {
+            api_key_repository,
+            config,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: CreateApiKeyCommandInput<'_>,
+    ) -> AppResult<CreateApiKeyCommandOutput> {
+        let user_id = context.authorize(Self::PERMISSION)?;
+
+        let mut violations = Vec::new();
+
+        // Keys can't be granted more than the caller has
+        for (i, scope) in input.scopes.iter().enumerate() {
+            let granted = Permission::parse(scope).is_some_and(|permission| {
+                context
+                    .principal()
+                    .is_some_and(|principal| principal.has_permission(permission))
+            });
+            if !granted {
+                violations.push(PathError {
+                    path: vec![
+                        PathErrorStep::Field("scopes".into()),
+                        PathErrorStep::Index(i),
+                    ],
+                    error: Box::new(
+                        UserError::new(UserErrorReason::InvalidApiKeyScope).with_permission(scope),
+                    ),
+                });
+            }
+        }
+
+        let create_time = UtcDateTime::now();
+        let expire_time = input
+            .expire_time
+            .unwrap_or_else(|| add_seconds(create_time, self.config.default_ttl_seconds));
+        if expire_time <= create_time
+            || expire_time > add_seconds(create_time, self.config.max_ttl_seconds)
+        {
+            violations.push(PathError {
+                path: vec![PathErrorStep::Field("expire_time".into())],
+                error: Box::new(CommonError::InvalidDateTime),
+            });
+        }
+
+        if !violations.is_empty() {
+            return Err(RequestError::BadRequest {
+                name: CreateApiKeyRequest::NAME.into(),
+                violations,
+            }
+            .into());
+        }
+
+        let api_key_count = self.api_key_repository.select_by_user(user_id).await?.len();
+        if api_key_count >= self.config.max_keys_per_user {
+            return Err(make_status(
+                Code::ResourceExhausted,
+                RequestError::generic(UserError::new(UserErrorReason::TooManyApiKeys)),
+            )
+            .into());
+        }
+
+        let generated = generate_api_key();
+        let api_key = ApiKeyRecord {
+            id: Id::generate(),
+            user_id,
+            display_name: input.display_name.into(),
+            prefix: generated.prefix,
+            key_hash: generated.key_hash,
+            scopes: input.scopes.to_vec(),
+            create_time,
+            expire_time,
+            last_use_time: None,
+        };
+
+        self.api_key_repository
+            .insert(ApiKeyInsertRecord {
+                id: api_key.id,
+                user_id,
+                display_name: &api_key.display_name,
+                prefix: &api_key.prefix,
+                key_hash: &api_key.key_hash,
+                scopes: &api_key.scopes,
+                create_time,
+                expire_time,
+            })
+            .await?;
+
+        Ok(CreateApiKeyCommandOutput {
+            api_key,
+            key: generated.key,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use rand::{distributions::Uniform, Rng};
+use sha2::{Digest, Sha256};
+
+/// Keys look like `sky_{prefix}_{secret}`.
+pub const API_KEY_PREFIX: &str = "sky_";
+
+const PREFIX_LENGTH: usize = 8;
+const SECRET_LENGTH: usize = 40;
+const PREFIX_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const SECRET_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+pub struct GeneratedApiKey {
+    pub prefix: String,
+    pub key: String,
+    pub key_hash: String,
+}
+
+pub fn generate_api_key() -> GeneratedApiKey {
+    let prefix = random_string(PREFIX_CHARSET, PREFIX_LENGTH);
+    let key = format!(
+        "{}{}_{}",
+        API_KEY_PREFIX,
+        prefix,
+        random_string(SECRET_CHARSET, SECRET_LENGTH)
+    );
+    let key_hash = hash_api_key(&key);
+    GeneratedApiKey {
+        prefix,
+        key,
+        key_hash,
+    }
+}
+
+/// Returns the identifying prefix of a well-formed key.
+pub fn parse_api_key_prefix(key: &str) -> Option<&str> {
+    let (prefix, secret) = key.strip_prefix(API_KEY_PREFIX)?.split_once('_')?;
+    (prefix.len() == PREFIX_LENGTH && secret.len() == SECRET_LENGTH).then_some(prefix)
+}
+
+/// Keys are random enough that a fast hash is sufficient.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn random_string(charset: &[u8], length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(Uniform::from(0..charset.len()))
+        .take(length)
+        .map(|i| charset[i] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_and_parse() {
+        let generated = generate_api_key();
+        assert_eq!(
+            parse_api_key_prefix(&generated.key),
+            Some(generated.prefix.as_str())
+        );
+        assert_eq!(hash_api_key(&generated.key), generated.key_hash);
+        assert_ne!(generate_api_key().key, generated.key);
+
+        assert!(parse_api_key_prefix("sky_abc_def").is_none());
+        assert!(parse_api_key_prefix(&generated.key[1..]).is_none());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod adapter;
+pub mod create_command;
+pub mod key;
+pub mod query_manager;
+pub mod repository;
+pub mod revoke_command;
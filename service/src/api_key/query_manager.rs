@@ -0,0 +1,45 @@
+use grpc_sky_api::{dto::make_api_key_name, proto::ApiKey};
+
+use crate::{
+    api_key::{
+        key::API_KEY_PREFIX,
+        repository::{ApiKeyRecord, ApiKeyRepositoryArc},
+    },
+    auth::permission::Permission,
+    context::Context,
+    error::AppResult,
+};
+
+pub struct ApiKeyQueryManager {
+    api_key_repository: ApiKeyRepositoryArc,
+}
+
+impl ApiKeyQueryManager {
+    pub fn new(api_key_repository: ApiKeyRepositoryArc) -> Self {
+        Self { api_key_repository }
+    }
+
+    /// Lists keys of the caller, including expired ones.
+    pub async fn list(&self, context: &Context) -> AppResult<Vec<ApiKey>> {
+        let user_id = context.authorize(Permission::ApiKeysManage)?;
+        Ok(self
+            .api_key_repository
+            .select_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(make_api_key)
+            .collect())
+    }
+}
+
+pub fn make_api_key(record: ApiKeyRecord) -> ApiKey {
+    ApiKey {
+        name: make_api_key_name(record.id),
+        display_name: record.display_name,
+        prefix: format!("{}{}", API_KEY_PREFIX, record.prefix),
+        scopes: record.scopes,
+        create_time: Some(record.create_time.into()),
+        expire_time: Some(record.expire_time.into()),
+        last_use_time: record.last_use_time.map(Into::into),
+    }
+}
@@ -0,0 +1,80 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{
+    api_key::repository::{ApiKeyInsertRecord, ApiKeyRecord, ApiKeyRepository},
+    error::AppResult,
+};
+
+/// Keeps API keys in memory, for tests of the auth layer and commands.
+#[derive(Default)]
+pub struct ApiKeyMemoryRepository {
+    api_keys: Mutex<BTreeMap<Id, ApiKeyRecord>>,
+}
+
+#[tonic::async_trait]
+impl ApiKeyRepository for ApiKeyMemoryRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn select_by_prefix(&self, prefix: &str) -> AppResult<Option<ApiKeyRecord>> {
+        Ok(self
+            .api_keys
+            .lock()
+            .unwrap()
+            .values()
+            .find(|api_key| api_key.prefix == prefix)
+            .cloned())
+    }
+
+    async fn select_by_user(&self, user_id: Id) -> AppResult<Vec<ApiKeyRecord>> {
+        let mut api_keys: Vec<_> = self
+            .api_keys
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|api_key| api_key.user_id == user_id)
+            .cloned()
+            .collect();
+        api_keys.sort_by(|a, b| b.create_time.partial_cmp(&a.create_time).unwrap());
+        Ok(api_keys)
+    }
+
+    async fn insert(&self, record: ApiKeyInsertRecord<'_>) -> AppResult<()> {
+        self.api_keys.lock().unwrap().insert(
+            record.id,
+            ApiKeyRecord {
+                id: record.id,
+                user_id: record.user_id,
+                display_name: record.display_name.into(),
+                prefix: record.prefix.into(),
+                key_hash: record.key_hash.into(),
+                scopes: record.scopes.to_vec(),
+                create_time: record.create_time,
+                expire_time: record.expire_time,
+                last_use_time: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn update_last_use_time(&self, id: Id, last_use_time: UtcDateTime) -> AppResult<()> {
+        if let Some(api_key) = self.api_keys.lock().unwrap().get_mut(&id) {
+            api_key.last_use_time = Some(last_use_time);
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: Id, user_id: Id) -> AppResult<bool> {
+        let mut api_keys = self.api_keys.lock().unwrap();
+        if !api_keys
+            .get(&id)
+            .is_some_and(|api_key| api_key.user_id == user_id)
+        {
+            return Ok(false);
+        }
+        api_keys.remove(&id);
+        Ok(true)
+    }
+}
@@ -0,0 +1,59 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use std::sync::Arc;
+
+use crate::{error::AppResult, health::HealthCheck};
+
+#[cfg(test)]
+pub mod memory;
+pub mod mysql;
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyRecord {
+    pub id: Id,
+    pub user_id: Id,
+    pub display_name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub create_time: UtcDateTime,
+    pub expire_time: UtcDateTime,
+    pub last_use_time: Option<UtcDateTime>,
+}
+
+pub struct ApiKeyInsertRecord<'a> {
+    pub id: Id,
+    pub user_id: Id,
+    pub display_name: &'a str,
+    pub prefix: &'a str,
+    pub key_hash: &'a str,
+    pub scopes: &'a [String],
+    pub create_time: UtcDateTime,
+    pub expire_time: UtcDateTime,
+}
+
+#[tonic::async_trait]
+pub trait ApiKeyRepository {
+    /// Checks that the backend is reachable.
+    async fn health_check(&self) -> AppResult<()>;
+
+    async fn select_by_prefix(&self, prefix: &str) -> AppResult<Option<ApiKeyRecord>>;
+
+    /// Lists keys of the user, newest first.
+    async fn select_by_user(&self, user_id: Id) -> AppResult<Vec<ApiKeyRecord>>;
+
+    async fn insert(&self, record: ApiKeyInsertRecord<'_>) -> AppResult<()>;
+
+    async fn update_last_use_time(&self, id: Id, last_use_time: UtcDateTime) -> AppResult<()>;
+
+    /// Returns `false` if the user has no such key.
+    async fn delete(&self, id: Id, user_id: Id) -> AppResult<bool>;
+}
+
+pub type ApiKeyRepositoryArc = Arc<dyn ApiKeyRepository + Send + Sync>;
+
+#[tonic::async_trait]
+impl HealthCheck for ApiKeyRepositoryArc {
+    async fn health_check(&self) -> AppResult<()> {
+        ApiKeyRepository::health_check(self.as_ref()).await
+    }
+}
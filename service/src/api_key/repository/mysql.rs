@@ -0,0 +1,168 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use mysql_async::{prelude::*, Pool};
+use tracing::info;
+
+use crate::{
+    api_key::repository::{ApiKeyInsertRecord, ApiKeyRecord, ApiKeyRepository},
+    error::AppResult,
+    metrics::get_mysql_conn,
+};
+
+pub struct ApiKeyMySqlRepository {
+    pool: Pool,
+}
+
+const API_KEY_COLUMNS: &str = r#"
+    id,
+    user_id,
+    display_name,
+    prefix,
+    key_hash,
+    scopes,
+    create_time,
+    expire_time,
+    last_use_time
+"#;
+
+type ApiKeyRow = (
+    Id,
+    Id,
+    String,
+    String,
+    String,
+    String,
+    UtcDateTime,
+    UtcDateTime,
+    Option<UtcDateTime>,
+);
+
+impl ApiKeyMySqlRepository {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl ApiKeyRepository for ApiKeyMySqlRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+        conn.ping().await?;
+        Ok(())
+    }
+
+    async fn select_by_prefix(&self, prefix: &str) -> AppResult<Option<ApiKeyRecord>> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let api_keys = format!(
+            r#"SELECT {} FROM api_keys WHERE prefix = :prefix LIMIT 1"#,
+            API_KEY_COLUMNS
+        )
+        .with(params! {
+            "prefix" => prefix,
+        })
+        .map(&mut *conn, make_api_key_record)
+        .await?;
+
+        Ok(api_keys.into_iter().next())
+    }
+
+    async fn select_by_user(&self, user_id: Id) -> AppResult<Vec<ApiKeyRecord>> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let api_keys = format!(
+            r#"SELECT {} FROM api_keys WHERE user_id = :user_id ORDER BY create_time DESC"#,
+            API_KEY_COLUMNS
+        )
+        .with(params! {
+            "user_id" => user_id,
+        })
+        .map(&mut *conn, make_api_key_record)
+        .await?;
+
+        Ok(api_keys)
+    }
+
+    async fn insert(&self, record: ApiKeyInsertRecord<'_>) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"
+            INSERT INTO api_keys (id, user_id, display_name, prefix, key_hash, scopes, create_time, expire_time)
+            VALUES(:id, :user_id, :display_name, :prefix, :key_hash, :scopes, :create_time, :expire_time)
+        "#
+        .with(params! {
+            "id" => record.id,
+            "user_id" => record.user_id,
+            "display_name" => record.display_name,
+            "prefix" => record.prefix,
+            "key_hash" => record.key_hash,
+            "scopes" => record.scopes.join(" "),
+            "create_time" => record.create_time,
+            "expire_time" => record.expire_time,
+        })
+        .ignore(&mut *conn)
+        .await?;
+
+        info!("inserted api key: {:?}", record.id);
+
+        Ok(())
+    }
+
+    async fn update_last_use_time(&self, id: Id, last_use_time: UtcDateTime) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"UPDATE api_keys SET last_use_time = :last_use_time WHERE id = :id"#
+            .with(params! {
+                "id" => id,
+                "last_use_time" => last_use_time,
+            })
+            .ignore(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Id, user_id: Id) -> AppResult<bool> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"DELETE FROM api_keys WHERE id = :id AND user_id = :user_id"#
+            .with(params! {
+                "id" => id,
+                "user_id" => user_id,
+            })
+            .ignore(&mut *conn)
+            .await?;
+
+        let deleted = conn.affected_rows() > 0;
+        if deleted {
+            info!("deleted api key: {:?}", id);
+        }
+
+        Ok(deleted)
+    }
+}
+
+fn make_api_key_record(
+    (
+        id,
+        user_id,
+        display_name,
+        prefix,
+        key_hash,
+        scopes,
+        create_time,
+        expire_time,
+        last_use_time,
+    ): ApiKeyRow,
+) -> ApiKeyRecord {
+    ApiKeyRecord {
+        id,
+        user_id,
+        display_name,
+        prefix,
+        key_hash,
+        scopes: scopes.split_whitespace().map(Into::into).collect(),
+        create_time,
+        expire_time,
+        last_use_time,
+    }
+}
@@ -0,0 +1,42 @@
+use bomboni_common::id::Id;
+use bomboni_request::error::CommonError;
+
+use crate::{
+    api_key::repository::ApiKeyRepositoryArc, auth::permission::Permission, context::Context,
+    error::AppResult,
+};
+
+pub struct RevokeApiKeyCommand {
+    api_key_repository: ApiKeyRepositoryArc,
+}
+
+pub struct RevokeApiKeyCommandInput {
+    pub api_key_id: Id,
+}
+
+impl RevokeApiKeyCommand {
+    pub const PERMISSION: Permission = Permission::ApiKeysManage;
+
+    pub fn new(api_key_repository: ApiKeyRepositoryArc) -> Self {
+        Self { api_key_repository }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: RevokeApiKeyCommandInput,
+    ) -> AppResult<()> {
+        let user_id = context.authorize(Self::PERMISSION)?;
+
+        // Keys of other users are reported as missing
+        if !self
+            .api_key_repository
+            .delete(input.api_key_id, user_id)
+            .await?
+        {
+            return Err(CommonError::ResourceNotFound.into());
+        }
+
+        Ok(())
+    }
+}
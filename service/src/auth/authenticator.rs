@@ -0,0 +1,100 @@
+use bomboni_common::date_time::UtcDateTime;
+use bomboni_request::error::RequestError;
+use http::{header::AUTHORIZATION, HeaderMap};
+use std::sync::Arc;
+use tonic::{Code, Status};
+
+use grpc_sky_api::{error::AuthError, proto::auth_error::AuthErrorReason};
+
+use crate::{
+    api_key::{
+        key::{hash_api_key, parse_api_key_prefix},
+        repository::ApiKeyRepositoryArc,
+    },
+    auth::{permission::Permission, token::TokenManager, Principal},
+    error::{make_status, AppResult},
+    user::repository::UserRepositoryArc,
+};
+
+/// Resolves credentials from the `authorization` header.
+///
+/// Both session tokens (`Bearer {token}`) and API keys (`ApiKey {key}`) are accepted.
+pub struct Authenticator {
+    token_manager: Arc<TokenManager>,
+    api_key_repository: ApiKeyRepositoryArc,
+    user_repository: UserRepositoryArc,
+}
+
+impl Authenticator {
+    pub fn new(
+        token_manager: Arc<TokenManager>,
+        api_key_repository: ApiKeyRepositoryArc,
+        user_repository: UserRepositoryArc,
+    ) -> Self {
+        Self {
+            token_manager,
+            api_key_repository,
+            user_repository,
+        }
+    }
+
+    /// Returns `None` for requests without credentials, and an error for invalid ones.
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, Status> {
+        let Some(authorization) = headers.get(AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let principal = match authorization
+            .to_str()
+            .ok()
+            .and_then(|authorization| authorization.split_once(' '))
+        {
+            Some(("Bearer", token)) => self.token_manager.verify(token),
+            Some(("ApiKey", key)) => self.verify_api_key(key).await?,
+            _ => None,
+        };
+        principal.map(Some).ok_or_else(|| {
+            make_status(
+                Code::Unauthenticated,
+                RequestError::generic(AuthError::new(AuthErrorReason::InvalidCredentials)),
+            )
+        })
+    }
+
+    async fn verify_api_key(&self, key: &str) -> AppResult<Option<Principal>> {
+        let Some(prefix) = parse_api_key_prefix(key) else {
+            return Ok(None);
+        };
+        let Some(api_key) = self
+            .api_key_repository
+            .select_by_prefix(prefix)
+            .await?
+            .filter(|api_key| api_key.key_hash == hash_api_key(key))
+        else {
+            return Ok(None);
+        };
+
+        let now = UtcDateTime::now();
+        if api_key.expire_time <= now {
+            return Ok(None);
+        }
+        let Some(user) = self.user_repository.select(api_key.user_id).await? else {
+            return Ok(None);
+        };
+
+        self.api_key_repository
+            .update_last_use_time(api_key.id, now)
+            .await?;
+
+        Ok(Some(Principal {
+            user_id: user.id,
+            role: user.role,
+            scopes: Some(
+                api_key
+                    .scopes
+                    .iter()
+                    .filter_map(|scope| Permission::parse(scope))
+                    .collect(),
+            ),
+        }))
+    }
+}
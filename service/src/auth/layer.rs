@@ -0,0 +1,187 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tonic::{
+    body::BoxBody,
+    transport::server::{TcpConnectInfo, TlsConnectInfo},
+};
+use tower::{Layer, Service};
+
+use crate::auth::{authenticator::Authenticator, ServicePrincipal};
+
+/// Methods that serve anonymous callers, on which invalid credentials are ignored,
+/// so that clients with an expired session can still sign in again.
+const ANONYMOUS_METHODS: &[&str] = &[
+    "/sky.v1.UserService/SignUp",
+    "/sky.v1.UserService/SignIn",
+    "/sky.v1.UserService/ExchangeOidcToken",
+];
+
+/// Resolves the `authorization` header into a [`Principal`](crate::auth::Principal) for every request.
+///
+/// Requests without credentials pass through unauthenticated, while invalid credentials are
+/// rejected with `UNAUTHENTICATED` before reaching any service, except for anonymous methods.
+/// With mutual TLS, client certificates are also resolved into a [`ServicePrincipal`].
+#[derive(Clone)]
+pub struct AuthLayer {
+    authenticator: Arc<Authenticator>,
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    authenticator: Arc<Authenticator>,
+}
+
+impl AuthLayer {
+    pub fn new(authenticator: Arc<Authenticator>) -> Self {
+        Self { authenticator }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            authenticator: self.authenticator.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready must be the one that is called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let authenticator = self.authenticator.clone();
+
+        Box::pin(async move {
+            let principal = authenticator.authenticate(request.headers()).await;
+            let principal = match principal {
+                Ok(principal) => principal,
+                Err(_) if ANONYMOUS_METHODS.contains(&request.uri().path()) => None,
+                Err(status) => return Ok(status.into_http()),
+            };
+            if let Some(principal) = principal.clone() {
+                request.extensions_mut().insert(principal);
+            }
+            if let Some(service_principal) = get_service_principal(&request) {
+                request.extensions_mut().insert(service_principal);
+            }
+            let mut response = inner.call(request).await?;
+            // Outer layers, such as the access log, can't see request extensions
+            if let Some(principal) = principal {
+                response.extensions_mut().insert(principal);
+            }
+            Ok(response)
+        })
+    }
+}
+
+fn get_service_principal<B>(request: &http::Request<B>) -> Option<ServicePrincipal> {
+    // Only verified certificates are presented with mutual TLS
+    let certificates = request
+        .extensions()
+        .get::<TlsConnectInfo<TcpConnectInfo>>()?
+        .peer_certs()?;
+    ServicePrincipal::from_certificate(certificates.first()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use bomboni_common::id::Id;
+    use http::{header::AUTHORIZATION, HeaderValue};
+    use std::convert::Infallible;
+    use tonic::Code;
+    use tower::{service_fn, ServiceExt};
+
+    use grpc_sky_api::proto::UserRole;
+
+    use crate::{
+        api_key::repository::memory::ApiKeyMemoryRepository,
+        auth::{
+            token::{make_test_auth_config, TokenManager},
+            Principal,
+        },
+        user::repository::memory::UserMemoryRepository,
+    };
+
+    use super::*;
+
+    /// Calls the layer and returns the principal that reached the service, or the error code.
+    async fn call(path: &str, authorization: Option<&str>) -> Result<Option<Principal>, Code> {
+        let token_manager = Arc::new(TokenManager::new(&make_test_auth_config()));
+        let authenticator = Arc::new(Authenticator::new(
+            token_manager,
+            Arc::new(ApiKeyMemoryRepository::default()),
+            Arc::new(UserMemoryRepository::default()),
+        ));
+        let service = AuthLayer::new(authenticator).layer(service_fn(
+            |request: http::Request<()>| async move {
+                let mut response = http::Response::new(tonic::body::empty_body());
+                if let Some(principal) = request.extensions().get::<Principal>().cloned() {
+                    response.extensions_mut().insert(principal);
+                }
+                Ok::<_, Infallible>(response)
+            },
+        ));
+
+        let mut request = http::Request::builder().uri(path);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, HeaderValue::from_str(authorization).unwrap());
+        }
+        let response = service.oneshot(request.body(()).unwrap()).await.unwrap();
+        match tonic::Status::from_header_map(response.headers()) {
+            Some(status) => Err(status.code()),
+            None => Ok(response.extensions().get::<Principal>().cloned()),
+        }
+    }
+
+    #[tokio::test]
+    async fn authenticate() {
+        let token = TokenManager::new(&make_test_auth_config())
+            .issue(Id::generate(), UserRole::User)
+            .unwrap();
+
+        let principal = call(
+            "/sky.v1.UserService/GetMe",
+            Some(&format!("Bearer {}", token)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(principal.unwrap().role, UserRole::User);
+        assert_eq!(call("/sky.v1.UserService/GetMe", None).await, Ok(None));
+        assert_eq!(
+            call("/sky.v1.UserService/GetMe", Some("Bearer invalid")).await,
+            Err(Code::Unauthenticated)
+        );
+
+        // Anonymous methods ignore invalid credentials
+        assert_eq!(
+            call("/sky.v1.UserService/SignIn", Some("Bearer invalid")).await,
+            Ok(None)
+        );
+        assert_eq!(
+            call("/sky.v1.UserService/SignUp", Some("ApiKey invalid")).await,
+            Ok(None)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use bomboni_common::id::Id;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use grpc_sky_api::proto::UserRole;
+
+use crate::auth::permission::Permission;
+
+pub mod authenticator;
+pub mod layer;
+pub mod oidc;
+pub mod permission;
+pub mod token;
+
+/// Authenticated caller, attached to request extensions by the auth layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub user_id: Id,
+    pub role: UserRole,
+    /// Set for API keys, which only get the scoped permissions that the role also grants.
+    pub scopes: Option<Vec<Permission>>,
+}
+
+impl Principal {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        permission::role_permissions(self.role).contains(&permission)
+            && self
+                .scopes
+                .as_ref()
+                .map_or(true, |scopes| scopes.contains(&permission))
+    }
+}
+
+/// Internal service authenticated with a client certificate, attached to request extensions
+/// by the auth layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServicePrincipal {
+    /// Subject of the certificate, e.g. `O=Sky, CN=feed-worker`.
+    pub subject: String,
+}
+
+impl ServicePrincipal {
+    /// Only certificates verified against the client CA may be passed.
+    pub fn from_certificate(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+        Some(Self {
+            subject: certificate.subject().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::pem::parse_x509_pem;
+
+    use super::*;
+
+    const CERTIFICATE_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBnzCCAUWgAwIBAgIUMs5O1cjA6OOpf+XaDcswSUvjRxAwCgYIKoZIzj0EAwIw
+JDEMMAoGA1UECgwDU2t5MRQwEgYDVQQDDAtmZWVkLXdvcmtlcjAgFw0yNjEwMTkw
+MDQwNDlaGA8yMTI2MDkyNTAwNDA0OVowJDEMMAoGA1UECgwDU2t5MRQwEgYDVQQD
+DAtmZWVkLXdvcmtlcjBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABKobh5Fj/fZN
+IlTNrpVndCvg+08o8hKcYYyPG0rkBgAmr1RCWaKzyvBCfq45SHFpLAbn5lfrp2Z1
+K7niCJt9a2KjUzBRMB0GA1UdDgQWBBT1HYQLN9lSWjnLaP555OgBhFb/nDAfBgNV
+HSMEGDAWgBT1HYQLN9lSWjnLaP555OgBhFb/nDAPBgNVHRMBAf8EBTADAQH/MAoG
+CCqGSM49BAMCA0gAMEUCIDAkfkxeWe7evBxW89sXBMhp7YzzYDtL6NqS5bazEWP9
+AiEAzEHv5NtRcLmLJqBx2ZXzIJYHCs/629InMw8xcv9eHl8=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn service_principal_from_certificate() {
+        let (_, pem) = parse_x509_pem(CERTIFICATE_PEM.as_bytes()).unwrap();
+        assert_eq!(
+            ServicePrincipal::from_certificate(&pem.contents),
+            Some(ServicePrincipal {
+                subject: "O=Sky, CN=feed-worker".into(),
+            })
+        );
+        assert_eq!(ServicePrincipal::from_certificate(b"invalid"), None);
+    }
+}
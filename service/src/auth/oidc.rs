@@ -0,0 +1,203 @@
+use jsonwebtoken::{
+    decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Header, Validation,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+use crate::{config::OidcConfig, error::AppResult};
+
+/// Unknown key IDs trigger a refresh, but not more often than this.
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Verifies ID tokens issued by an external OpenID Connect provider.
+pub struct OidcVerifier {
+    config: OidcConfig,
+    http_client: reqwest::Client,
+    jwks: RwLock<Option<CachedJwks>>,
+}
+
+struct CachedJwks {
+    jwks: JwkSet,
+    fetch_time: Instant,
+}
+
+/// External identity of a verified ID token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    preferred_username: Option<String>,
+}
+
+impl OidcVerifier {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            http_client: reqwest::Client::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    /// Returns `None` if the token is invalid or not signed by a key of the provider.
+    pub async fn verify(&self, id_token: &str) -> AppResult<Option<OidcIdentity>> {
+        let Ok(header) = decode_header(id_token) else {
+            return Ok(None);
+        };
+        // Tokens must be signed with the provider's public keys
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Ok(None);
+        }
+        let Some(decoding_key) = self.find_decoding_key(&header).await? else {
+            return Ok(None);
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let Ok(token_data) = decode::<IdTokenClaims>(id_token, &decoding_key, &validation) else {
+            return Ok(None);
+        };
+        Ok(Some(OidcIdentity {
+            issuer: token_data.claims.iss,
+            subject: token_data.claims.sub,
+            preferred_username: token_data.claims.preferred_username,
+        }))
+    }
+
+    async fn find_decoding_key(&self, header: &Header) -> AppResult<Option<DecodingKey>> {
+        let Some(kid) = header.kid.as_deref() else {
+            return Ok(None);
+        };
+
+        let cache_ttl = Duration::from_secs(self.config.jwks_cache_seconds);
+        let should_fetch = match self.jwks.read().await.as_ref() {
+            Some(cached) if cached.fetch_time.elapsed() < cache_ttl => {
+                if let Some(jwk) = cached.jwks.find(kid) {
+                    return Ok(DecodingKey::from_jwk(jwk).ok());
+                }
+                // Keys may have been rotated
+                cached.fetch_time.elapsed() >= MIN_JWKS_REFRESH_INTERVAL
+            }
+            _ => true,
+        };
+        if !should_fetch {
+            return Ok(None);
+        }
+
+        let jwks = self.fetch_jwks().await?;
+        let decoding_key = jwks
+            .find(kid)
+            .and_then(|jwk| DecodingKey::from_jwk(jwk).ok());
+        *self.jwks.write().await = Some(CachedJwks {
+            jwks,
+            fetch_time: Instant::now(),
+        });
+        Ok(decoding_key)
+    }
+
+    /// Fetches the JWKS from a URL, or reads it from a local file.
+    async fn fetch_jwks(&self) -> AppResult<JwkSet> {
+        let jwks_uri = &self.config.jwks_uri;
+        if jwks_uri.starts_with("http://") || jwks_uri.starts_with("https://") {
+            Ok(self
+                .http_client
+                .get(jwks_uri)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?)
+        } else {
+            Ok(serde_json::from_str(&fs::read_to_string(jwks_uri)?)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey};
+    use serde::Serialize;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::*;
+
+    const TEST_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/oidc");
+
+    #[derive(Serialize)]
+    struct TestClaims<'a> {
+        iss: &'a str,
+        sub: &'a str,
+        aud: &'a str,
+        exp: u64,
+        preferred_username: &'a str,
+    }
+
+    fn make_id_token(kid: &str, iss: &str, aud: &str) -> String {
+        let private_key = fs::read(format!("{}/private_key.pem", TEST_DATA_PATH)).unwrap();
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.into());
+        encode(
+            &header,
+            &TestClaims {
+                iss,
+                sub: "external-id",
+                aud,
+                exp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs()
+                    + 60,
+                preferred_username: "tester",
+            },
+            &EncodingKey::from_ec_pem(&private_key).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verify_id_token() {
+        let verifier = OidcVerifier::new(OidcConfig {
+            issuer: "https://idp.example.com".into(),
+            client_id: "sky".into(),
+            jwks_uri: format!("{}/jwks.json", TEST_DATA_PATH),
+            jwks_cache_seconds: 3600,
+        });
+
+        assert_eq!(
+            verifier
+                .verify(&make_id_token("test-key", "https://idp.example.com", "sky"))
+                .await
+                .unwrap(),
+            Some(OidcIdentity {
+                issuer: "https://idp.example.com".into(),
+                subject: "external-id".into(),
+                preferred_username: Some("tester".into()),
+            })
+        );
+
+        for id_token in [
+            make_id_token("other-key", "https://idp.example.com", "sky"),
+            make_id_token("test-key", "https://other.example.com", "sky"),
+            make_id_token("test-key", "https://idp.example.com", "other"),
+            "invalid".into(),
+        ] {
+            assert!(verifier.verify(&id_token).await.unwrap().is_none());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use grpc_sky_api::proto::UserRole;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// Create posts as the signed in user.
+    PostsWrite,
+    /// Delete posts of any user.
+    PostsDelete,
+    UsersBan,
+    UsersUnlock,
+    /// Create, list and revoke own API keys.
+    ApiKeysManage,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 5] = [
+        Permission::PostsWrite,
+        Permission::PostsDelete,
+        Permission::UsersBan,
+        Permission::UsersUnlock,
+        Permission::ApiKeysManage,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::PostsWrite => "posts.write",
+            Permission::PostsDelete => "posts.delete",
+            Permission::UsersBan => "users.ban",
+            Permission::UsersUnlock => "users.unlock",
+            Permission::ApiKeysManage => "api_keys.manage",
+        }
+    }
+}
+
+/// Each role is granted the permissions of the roles below it.
+pub fn role_permissions(role: UserRole) -> &'static [Permission] {
+    match role {
+        UserRole::Unspecified => &[],
+        UserRole::User => &[Permission::PostsWrite, Permission::ApiKeysManage],
+        UserRole::Moderator => &[
+            Permission::PostsWrite,
+            Permission::ApiKeysManage,
+            Permission::PostsDelete,
+            Permission::UsersBan,
+        ],
+        UserRole::Admin => &Permission::ALL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_are_ordered() {
+        let roles = [
+            UserRole::Unspecified,
+            UserRole::User,
+            UserRole::Moderator,
+            UserRole::Admin,
+        ];
+        for pair in roles.windows(2) {
+            let lower = role_permissions(pair[0]);
+            let higher = role_permissions(pair[1]);
+            assert!(lower.iter().all(|permission| higher.contains(permission)));
+            assert!(lower.len() < higher.len());
+        }
+    }
+
+    #[test]
+    fn parse_permissions() {
+        for permission in Permission::ALL {
+            assert_eq!(Permission::parse(permission.as_str()), Some(permission));
+        }
+        assert!(Permission::parse("posts").is_none());
+    }
+}
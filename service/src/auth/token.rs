@@ -0,0 +1,116 @@
+use bomboni_common::id::Id;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use grpc_sky_api::proto::UserRole;
+
+use crate::{auth::Principal, config::AuthConfig, error::AppResult};
+
+/// Issues and verifies signed session tokens.
+///
+/// The role is carried in the token, so role changes apply once the user signs in again.
+pub struct TokenManager {
+    issuer: String,
+    ttl_seconds: u64,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    role: String,
+    iss: String,
+    iat: u64,
+    exp: u64,
+}
+
+impl TokenManager {
+    pub fn new(config: &AuthConfig) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[&config.token_issuer]);
+        Self {
+            issuer: config.token_issuer.clone(),
+            ttl_seconds: config.token_ttl_seconds,
+            encoding_key: EncodingKey::from_secret(config.token_secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(config.token_secret.as_bytes()),
+            validation,
+        }
+    }
+
+    pub fn issue(&self, user_id: Id, role: UserRole) -> AppResult<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let claims = TokenClaims {
+            sub: user_id.to_string(),
+            role: role.as_str_name().into(),
+            iss: self.issuer.clone(),
+            iat: now,
+            exp: now + self.ttl_seconds,
+        };
+        Ok(encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &self.encoding_key,
+        )?)
+    }
+
+    /// Returns `None` for malformed, tampered or expired tokens.
+    pub fn verify(&self, token: &str) -> Option<Principal> {
+        let claims = decode::<TokenClaims>(token, &self.decoding_key, &self.validation)
+            .ok()?
+            .claims;
+        Some(Principal {
+            user_id: Id::from_str(&claims.sub).ok()?,
+            role: UserRole::from_str_name(&claims.role)?,
+            scopes: None,
+        })
+    }
+}
+
+/// Config of token managers in tests.
+#[cfg(test)]
+pub fn make_test_auth_config() -> AuthConfig {
+    AuthConfig {
+        token_secret: "secret".into(),
+        token_issuer: "sky".into(),
+        token_ttl_seconds: 60,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_and_verify() {
+        let config = make_test_auth_config();
+        let token_manager = TokenManager::new(&config);
+        let user_id = Id::generate();
+
+        let token = token_manager.issue(user_id, UserRole::Moderator).unwrap();
+        assert_eq!(
+            token_manager.verify(&token),
+            Some(Principal {
+                user_id,
+                role: UserRole::Moderator,
+                scopes: None,
+            })
+        );
+
+        assert!(token_manager.verify(&format!("{}x", token)).is_none());
+        assert!(TokenManager::new(&AuthConfig {
+            token_secret: "other".into(),
+            ..config
+        })
+        .verify(&token)
+        .is_none());
+    }
+}
@@ -0,0 +1,78 @@
+use bomboni_common::id::Id;
+use bomboni_proto::google::rpc::Status as ProtoStatus;
+use bomboni_request::error::{PathError, PathErrorStep, RequestError};
+use std::collections::BTreeMap;
+use tonic::Code;
+
+use crate::error::{make_proto_status, make_status, AppResult};
+
+/// Batch get of resources by ID, in the style of AIP-231.
+pub struct BatchGet<'a> {
+    /// Name of the request message, reported with violations.
+    pub request_name: &'a str,
+    pub ids: &'a [Id],
+    pub allow_missing: bool,
+}
+
+impl BatchGet<'_> {
+    /// Returns a result per ID, in the same order as the IDs.
+    ///
+    /// Forbidden records are reported the same way as missing ones, so that callers can't tell
+    /// that they exist. Unless `allow_missing` is set, any of them fails the whole request with
+    /// a `NOT_FOUND` violation per item.
+    pub fn collect<R, E>(
+        &self,
+        records: impl IntoIterator<Item = (Id, R)>,
+        can_read: impl Fn(&R) -> bool,
+        make_not_found_error: impl Fn(Id) -> E,
+    ) -> AppResult<Vec<Result<R, ProtoStatus>>>
+    where
+        R: Clone,
+        E: std::error::Error + Send + Sync + 'static,
+        RequestError: From<E>,
+    {
+        let records: BTreeMap<Id, R> = records
+            .into_iter()
+            .filter(|(_, record)| can_read(record))
+            .collect();
+
+        if !self.allow_missing {
+            let violations: Vec<_> = self
+                .ids
+                .iter()
+                .enumerate()
+                .filter(|(_, id)| !records.contains_key(id))
+                .map(|(index, id)| PathError {
+                    path: vec![
+                        PathErrorStep::Field("names".into()),
+                        PathErrorStep::Index(index),
+                    ],
+                    error: Box::new(make_not_found_error(*id)),
+                })
+                .collect();
+            if !violations.is_empty() {
+                return Err(make_status(
+                    Code::NotFound,
+                    RequestError::BadRequest {
+                        name: self.request_name.into(),
+                        violations,
+                    },
+                )
+                .into());
+            }
+        }
+
+        Ok(self
+            .ids
+            .iter()
+            .map(|id| {
+                records.get(id).cloned().ok_or_else(|| {
+                    make_proto_status(
+                        Code::NotFound,
+                        RequestError::from(make_not_found_error(*id)),
+                    )
+                })
+            })
+            .collect())
+    }
+}
@@ -1,20 +1,110 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use std::{env, net::SocketAddr};
 
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 
+use crate::date_time::MAX_DURATION_SECONDS;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     pub distribution: DistributionConfig,
     pub server: ServerConfig,
+    pub tracing: TracingConfig,
     pub database: DatabaseConfig,
+    pub password_policy: PasswordPolicyConfig,
+    pub sign_in_throttle: SignInThrottleConfig,
+    pub rate_limit: RateLimitConfig,
+    pub idempotency: IdempotencyConfig,
+    pub auth: AuthConfig,
+    pub api_key: ApiKeyConfig,
+    /// Login through an external identity provider is disabled if unset.
+    pub oidc: Option<OidcConfig>,
+    /// Errors are not localized if unset.
+    pub localization: Option<LocalizationConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     pub grpc_address: SocketAddr,
+    /// REST/JSON transcoding of the `google.api.http` rules is not served if unset.
+    /// It is served with the same TLS config as gRPC.
+    pub http_address: Option<SocketAddr>,
+    /// How long services are reported as not serving before servers stop accepting connections,
+    /// so that load balancers can stop routing to them.
+    pub drain_delay_seconds: u64,
+    /// How long in-flight requests may run after servers stop accepting connections.
+    pub shutdown_timeout_seconds: u64,
+    /// How often backends are checked for the health service.
+    pub health_check_interval_seconds: u64,
+    /// Prometheus metrics are not exported if unset.
+    pub metrics_address: Option<SocketAddr>,
+    /// Plain TCP is served if unset.
+    pub tls: Option<TlsConfig>,
+    /// Browser clients are not supported if unset.
+    pub grpc_web: Option<GrpcWebConfig>,
+}
+
+/// Paths are relative to the config directory.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// PEM encoded certificate chain.
+    pub cert_path: PathBuf,
+    /// PEM encoded private key.
+    pub key_path: PathBuf,
+    /// Enables mutual TLS, with client certificates verified against this PEM encoded CA.
+    pub client_ca_path: Option<PathBuf>,
+    /// Accept clients without certificates when mutual TLS is enabled.
+    #[serde(default)]
+    pub client_auth_optional: bool,
+}
+
+/// Serves gRPC-Web over HTTP/1.1 next to gRPC, with CORS for browser clients.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcWebConfig {
+    /// Origins allowed to call the service, or `*` for any origin.
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in addition to the ones of gRPC-Web.
+    pub allowed_headers: Vec<String>,
+    /// Response headers readable by browser clients.
+    pub exposed_headers: Vec<String>,
+    /// How long browsers may cache preflight responses.
+    pub max_age_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TracingConfig {
+    pub log_format: LogFormat,
+    /// Writes one JSON line per RPC.
+    pub access_log: bool,
+    pub exporter: TraceExporterConfig,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum LogFormat {
+    Pretty,
+    Compact,
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind")]
+pub enum TraceExporterConfig {
+    /// Spans are not exported.
+    None,
+    Stdout,
+    OtlpGrpc(OtlpExporterConfig),
+    OtlpHttp(OtlpExporterConfig),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OtlpExporterConfig {
+    /// Collector endpoint, e.g. `http://localhost:4317` for gRPC or
+    /// `http://localhost:4318/v1/traces` for HTTP.
+    pub endpoint: String,
+    pub timeout_seconds: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,6 +125,93 @@ pub struct MySqlDatabaseConfig {
     pub connection: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordPolicyConfig {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub reject_similar_to_name: bool,
+    /// File with one breached password per line, relative to the config directory.
+    pub breached_passwords_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignInThrottleConfig {
+    /// Failed attempts per user name allowed before the account is locked.
+    pub max_failed_attempts: u32,
+    /// Failed attempts per remote address allowed before the address is locked.
+    pub max_failed_attempts_per_address: u32,
+    /// Lockout duration, doubled with every further failed attempt.
+    pub base_lockout_seconds: u64,
+    pub max_lockout_seconds: u64,
+    /// Failed attempts are forgotten after this long without another failure.
+    pub reset_after_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// Methods without a rule are not rate limited.
+    pub rules: Vec<RateLimitRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitRule {
+    /// Full gRPC method path, e.g. `/sky.v1.PostService/Post`.
+    pub method: String,
+    /// Calls allowed in a burst, at least one.
+    pub burst: u32,
+    /// Sustained calls allowed per minute, at least one.
+    pub per_minute: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    /// How long responses are kept for retries.
+    pub ttl_seconds: u64,
+    /// How long a call may run before a retry with the same key runs it again.
+    pub lease_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// Secret used to sign session tokens, which has no default.
+    pub token_secret: String,
+    pub token_issuer: String,
+    pub token_ttl_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Lifetime of keys created without an explicit expiry.
+    pub default_ttl_seconds: u64,
+    pub max_ttl_seconds: u64,
+    pub max_keys_per_user: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcConfig {
+    /// Expected `iss` claim of ID tokens.
+    pub issuer: String,
+    /// Expected `aud` claim, the client ID registered with the provider.
+    pub client_id: String,
+    /// URL of the provider's JWKS, or a path to a local JWKS file.
+    ///
+    /// Like every path of the config, a relative path is resolved against the `config` directory.
+    pub jwks_uri: String,
+    pub jwks_cache_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalizationConfig {
+    /// Directory with an `errors.ftl` Fluent catalog per locale, e.g. `en/errors.ftl`,
+    /// relative to the config directory.
+    pub locales_path: PathBuf,
+    /// Locale of callers without a supported `accept-language`.
+    pub default_locale: String,
+}
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const CONFIG_PATH_ENV: &str = "FLINECT_PLATFORM_CONFIG_PATH";
 const ENV_PREFIX: &str = "FLINECT_PLATFORM";
@@ -74,9 +251,81 @@ impl AppConfig {
 
         let config = config_builder.build()?;
 
-        Config::builder()
+        let mut config: Self = Config::builder()
             .add_source(config)
             .build()?
-            .try_deserialize()
+            .try_deserialize()?;
+
+        if config.auth.token_secret.is_empty() {
+            return Err(ConfigError::Message(
+                "`auth.token_secret` must not be empty".into(),
+            ));
+        }
+
+        if let Some(rule) = config
+            .rate_limit
+            .rules
+            .iter()
+            .find(|rule| rule.burst == 0 || rule.per_minute == 0)
+        {
+            return Err(ConfigError::Message(format!(
+                "rate limit of `{}` must allow at least one call",
+                rule.method
+            )));
+        }
+
+        check_duration("idempotency.ttl_seconds", config.idempotency.ttl_seconds)?;
+        check_duration(
+            "idempotency.lease_seconds",
+            config.idempotency.lease_seconds,
+        )?;
+        check_duration(
+            "api_key.default_ttl_seconds",
+            config.api_key.default_ttl_seconds,
+        )?;
+        check_duration("api_key.max_ttl_seconds", config.api_key.max_ttl_seconds)?;
+
+        config.resolve_paths();
+
+        Ok(config)
+    }
+
+    /// Resolves every relative path of the config against the config directory,
+    /// so that they don't depend on the working directory like the config files themselves.
+    fn resolve_paths(&mut self) {
+        // Absolute paths are kept as they are
+        let resolve = |path: &mut PathBuf| *path = Path::new(CONFIG_PATH).join(&*path);
+
+        if let Some(tls) = self.server.tls.as_mut() {
+            resolve(&mut tls.cert_path);
+            resolve(&mut tls.key_path);
+            if let Some(path) = tls.client_ca_path.as_mut() {
+                resolve(path);
+            }
+        }
+        if let Some(path) = self.password_policy.breached_passwords_path.as_mut() {
+            resolve(path);
+        }
+        if let Some(oidc) = self.oidc.as_mut() {
+            if !oidc.jwks_uri.starts_with("http://") && !oidc.jwks_uri.starts_with("https://") {
+                let mut path = PathBuf::from(&oidc.jwks_uri);
+                resolve(&mut path);
+                oidc.jwks_uri = path.to_string_lossy().into_owned();
+            }
+        }
+        if let Some(localization) = self.localization.as_mut() {
+            resolve(&mut localization.locales_path);
+        }
+    }
+}
+
+/// Durations are added to the current time, which only works up to a limit.
+fn check_duration(name: &str, seconds: u64) -> Result<(), ConfigError> {
+    if seconds > MAX_DURATION_SECONDS {
+        return Err(ConfigError::Message(format!(
+            "`{}` must be at most {} seconds",
+            name, MAX_DURATION_SECONDS
+        )));
     }
+    Ok(())
 }
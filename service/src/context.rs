@@ -1,32 +1,150 @@
-use std::str::FromStr;
-
 use bomboni_common::id::Id;
-use bomboni_request::error::CommonError;
-use tonic::Request;
+use bomboni_request::error::RequestError;
+use tonic::{Code, Request};
+
+use grpc_sky_api::{error::AuthError, proto::auth_error::AuthErrorReason};
 
-use crate::error::AppResult;
+use crate::{
+    auth::{permission::Permission, Principal, ServicePrincipal},
+    error::{make_status, AppError, AppResult},
+};
 
 pub struct Context {
-    user_id: Option<Id>,
+    principal: Option<Principal>,
+    service_principal: Option<ServicePrincipal>,
 }
 
-const AUTHORIZATION_METADATA_KEY: &str = "authorization";
-
 impl Context {
+    /// Principals are resolved from credentials and client certificates by the auth layer.
     pub fn from_request<T>(request: &Request<T>) -> Self {
-        // TODO: Make actual auth
-        let user_id = request
-            .metadata()
-            .get(AUTHORIZATION_METADATA_KEY)
-            .and_then(|auth| {
-                let bearer = auth.to_str().ok()?;
-                let token = bearer.strip_prefix("Bearer ")?;
-                Id::from_str(token).ok()
-            });
-        Self { user_id }
+        Self {
+            principal: request.extensions().get::<Principal>().cloned(),
+            service_principal: request.extensions().get::<ServicePrincipal>().cloned(),
+        }
+    }
+
+    pub fn principal(&self) -> Option<&Principal> {
+        self.principal.as_ref()
     }
 
+    /// Authenticates a user signed in with a session.
+    ///
+    /// API keys are denied, since they may only be used for the permissions in their scopes.
     pub fn authenticate(&self) -> AppResult<Id> {
-        self.user_id.ok_or(CommonError::Unauthorized.into())
+        let principal = self.require_principal()?;
+        if principal.scopes.is_some() {
+            return Err(permission_denied(None));
+        }
+        Ok(principal.user_id)
+    }
+
+    /// Authenticates an internal service by its client certificate.
+    pub fn authenticate_service(&self) -> AppResult<&ServicePrincipal> {
+        self.service_principal
+            .as_ref()
+            .ok_or_else(|| unauthenticated(AuthErrorReason::MissingClientCertificate))
+    }
+
+    /// Authenticates the caller and checks that their role, and the scopes of their API key,
+    /// grant the permission.
+    pub fn authorize(&self, permission: Permission) -> AppResult<Id> {
+        let principal = self.require_principal()?;
+        if !principal.has_permission(permission) {
+            return Err(permission_denied(Some(permission)));
+        }
+        Ok(principal.user_id)
+    }
+
+    fn require_principal(&self) -> AppResult<&Principal> {
+        self.principal
+            .as_ref()
+            .ok_or_else(|| unauthenticated(AuthErrorReason::MissingCredentials))
+    }
+}
+
+/// Error of callers that may not act on a resource, e.g. for lack of a permission.
+pub fn permission_denied(permission: Option<Permission>) -> AppError {
+    let mut error = AuthError::new(AuthErrorReason::PermissionDenied);
+    if let Some(permission) = permission {
+        error = error.with_permission(permission.as_str());
+    }
+    make_status(Code::PermissionDenied, RequestError::generic(error)).into()
+}
+
+fn unauthenticated(reason: AuthErrorReason) -> AppError {
+    make_status(
+        Code::Unauthenticated,
+        RequestError::generic(AuthError::new(reason)),
+    )
+    .into()
+}
+
+#[cfg(test)]
+impl Context {
+    pub fn anonymous() -> Self {
+        Self {
+            principal: None,
+            service_principal: None,
+        }
+    }
+
+    pub fn from_principal(principal: Principal) -> Self {
+        Self {
+            principal: Some(principal),
+            service_principal: None,
+        }
+    }
+
+    /// Context of a user signed in with a session token.
+    pub fn from_user(user_id: Id) -> Self {
+        Self::from_principal(Principal {
+            user_id,
+            role: grpc_sky_api::proto::UserRole::User,
+            scopes: None,
+        })
+    }
+
+    /// Context of a user authenticated with an API key of the scopes.
+    pub fn from_api_key(user_id: Id, scopes: Vec<Permission>) -> Self {
+        Self::from_principal(Principal {
+            user_id,
+            role: grpc_sky_api::proto::UserRole::User,
+            scopes: Some(scopes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authenticate_principals() {
+        let user_id = Id::generate();
+        let status = |result: AppResult<Id>| match result.unwrap_err() {
+            AppError::Status(status) => status.code(),
+            err => panic!("unexpected error {:?}", err),
+        };
+
+        assert_eq!(
+            status(Context::anonymous().authenticate()),
+            Code::Unauthenticated
+        );
+        assert_eq!(Context::from_user(user_id).authenticate().unwrap(), user_id);
+        assert_eq!(
+            Context::from_user(user_id)
+                .authorize(Permission::PostsWrite)
+                .unwrap(),
+            user_id
+        );
+
+        // API keys may only be used within their scopes
+        let context = Context::from_api_key(user_id, vec![Permission::PostsWrite]);
+        assert_eq!(status(context.authenticate()), Code::PermissionDenied);
+        assert_eq!(context.authorize(Permission::PostsWrite).unwrap(), user_id);
+        assert_eq!(
+            status(context.authorize(Permission::ApiKeysManage)),
+            Code::PermissionDenied
+        );
     }
 }
@@ -0,0 +1,150 @@
+use http::{HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{config::GrpcWebConfig, error::AppResult};
+
+/// Request headers sent by gRPC-Web clients.
+const GRPC_WEB_ALLOWED_HEADERS: &[&str] =
+    &["x-grpc-web", "content-type", "x-user-agent", "grpc-timeout"];
+
+/// Answers preflight requests of browser clients and exposes gRPC response headers to them.
+pub fn make_cors_layer(grpc_web_config: &GrpcWebConfig) -> AppResult<CorsLayer> {
+    let allow_origin = if grpc_web_config
+        .allowed_origins
+        .iter()
+        .any(|origin| origin == "*")
+    {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            grpc_web_config
+                .allowed_origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+    let allow_methods = grpc_web_config
+        .allowed_methods
+        .iter()
+        .map(|method| method.parse())
+        .collect::<Result<Vec<Method>, _>>()?;
+    let allow_headers = GRPC_WEB_ALLOWED_HEADERS
+        .iter()
+        .copied()
+        .chain(grpc_web_config.allowed_headers.iter().map(String::as_str))
+        .map(|header| header.parse())
+        .collect::<Result<Vec<HeaderName>, _>>()?;
+    let expose_headers = grpc_web_config
+        .exposed_headers
+        .iter()
+        .map(|header| header.parse())
+        .collect::<Result<Vec<HeaderName>, _>>()?;
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .expose_headers(expose_headers)
+        .max_age(Duration::from_secs(grpc_web_config.max_age_seconds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::{
+        ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+        ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+    };
+    use std::convert::Infallible;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    use super::*;
+
+    fn make_config(allowed_origins: &[&str]) -> GrpcWebConfig {
+        GrpcWebConfig {
+            allowed_origins: allowed_origins.iter().map(ToString::to_string).collect(),
+            allowed_methods: vec!["POST".into()],
+            allowed_headers: vec!["authorization".into()],
+            exposed_headers: vec!["grpc-status".into(), "grpc-message".into()],
+            max_age_seconds: 600,
+        }
+    }
+
+    async fn call(
+        config: &GrpcWebConfig,
+        request: http::Request<String>,
+    ) -> http::Response<String> {
+        make_cors_layer(config)
+            .unwrap()
+            .layer(service_fn(|_request: http::Request<String>| async {
+                Ok::<_, Infallible>(http::Response::new(String::new()))
+            }))
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    fn make_preflight_request(origin: &str) -> http::Request<String> {
+        http::Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/sky.v1.UserService/GetMe")
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "x-grpc-web,authorization")
+            .body(String::new())
+            .unwrap()
+    }
+
+    fn get_header<'a>(response: &'a http::Response<String>, name: &HeaderName) -> Option<&'a str> {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn allowed_origin() {
+        let config = make_config(&["https://sky.example.com"]);
+        let response = call(&config, make_preflight_request("https://sky.example.com")).await;
+        assert_eq!(
+            get_header(&response, &ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("https://sky.example.com")
+        );
+        let allowed_headers = get_header(&response, &ACCESS_CONTROL_ALLOW_HEADERS).unwrap();
+        assert!(allowed_headers.contains("x-grpc-web"));
+        assert!(allowed_headers.contains("authorization"));
+
+        let response = call(
+            &make_config(&["*"]),
+            make_preflight_request("https://other.example.com"),
+        )
+        .await;
+        assert_eq!(
+            get_header(&response, &ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("*")
+        );
+    }
+
+    #[tokio::test]
+    async fn denied_origin() {
+        let config = make_config(&["https://sky.example.com"]);
+        let response = call(&config, make_preflight_request("https://evil.example.com")).await;
+        assert_eq!(get_header(&response, &ACCESS_CONTROL_ALLOW_ORIGIN), None);
+    }
+
+    #[tokio::test]
+    async fn exposed_headers() {
+        let config = make_config(&["https://sky.example.com"]);
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri("/sky.v1.UserService/GetMe")
+            .header(ORIGIN, "https://sky.example.com")
+            .body(String::new())
+            .unwrap();
+        let response = call(&config, request).await;
+        let exposed_headers = get_header(&response, &ACCESS_CONTROL_EXPOSE_HEADERS).unwrap();
+        assert!(exposed_headers.contains("grpc-status"));
+        assert!(exposed_headers.contains("grpc-message"));
+    }
+}
@@ -0,0 +1,33 @@
+use bomboni_common::date_time::UtcDateTime;
+use bomboni_proto::google::protobuf::Timestamp;
+
+/// Longest duration that may be added to a time, about 100 years.
+///
+/// Durations of the config are validated against it when it loads.
+pub const MAX_DURATION_SECONDS: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// Returns the time `seconds` later, with longer durations capped at [`MAX_DURATION_SECONDS`].
+pub fn add_seconds(time: UtcDateTime, seconds: u64) -> UtcDateTime {
+    let mut timestamp = Timestamp::from(time);
+    timestamp.seconds += seconds.min(MAX_DURATION_SECONDS) as i64;
+    // Times of the next hundred years are valid
+    UtcDateTime::try_from(timestamp).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_durations() {
+        let time = UtcDateTime::now();
+        let seconds =
+            |later: UtcDateTime| Timestamp::from(later).seconds - Timestamp::from(time).seconds;
+        assert_eq!(seconds(add_seconds(time, 0)), 0);
+        assert_eq!(seconds(add_seconds(time, 60)), 60);
+        assert_eq!(
+            seconds(add_seconds(time, u64::MAX)),
+            MAX_DURATION_SECONDS as i64
+        );
+    }
+}
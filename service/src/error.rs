@@ -1,9 +1,13 @@
+use bomboni_proto::google::rpc::Status as ProtoStatus;
 use bomboni_request::error::{CommonError, RequestError};
+use prost::Message;
 use thiserror::Error;
 use tonic::{transport, Code, Status};
 use tracing::error;
 
-use grpc_sky_api::error::UserError;
+use grpc_sky_api::error::{
+    encode_request_error, AuthError, IdempotencyError, PostError, SocialError, UserError,
+};
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -28,7 +32,24 @@ macro_rules! impl_internal_errors {
         )*
     };
 }
-impl_internal_errors![config::ConfigError, transport::Error, mysql_async::Error];
+impl_internal_errors![
+    config::ConfigError,
+    transport::Error,
+    mysql_async::Error,
+    std::io::Error,
+    jsonwebtoken::errors::Error,
+    reqwest::Error,
+    serde_json::Error,
+    metrics_exporter_prometheus::BuildError,
+    prost::DecodeError,
+    http::header::InvalidHeaderName,
+    http::header::InvalidHeaderValue,
+    http::method::InvalidMethod,
+    http::uri::InvalidUri,
+    prost_reflect::DescriptorError,
+    crate::gateway::GatewayError,
+    crate::localization::LocalizationError,
+];
 
 macro_rules! impl_request_errors {
     ( $( $type:ty ),* $(,)? ) => {
@@ -41,12 +62,19 @@ macro_rules! impl_request_errors {
         )*
     };
 }
-impl_request_errors!(UserError, CommonError);
+impl_request_errors!(
+    UserError,
+    PostError,
+    SocialError,
+    AuthError,
+    IdempotencyError,
+    CommonError
+);
 
 impl From<AppError> for Status {
     fn from(err: AppError) -> Self {
         match err {
-            AppError::Request(err) => err.into(),
+            AppError::Request(err) => from_proto_status(encode_request_error(err)),
             AppError::Status(status) => status,
             _ => {
                 error!("internal service error: {}", err);
@@ -55,3 +83,38 @@ impl From<AppError> for Status {
         }
     }
 }
+
+/// Converts a request error into a proto `Status` with an explicit code.
+pub fn make_proto_status(code: Code, err: RequestError) -> ProtoStatus {
+    let mut status = encode_request_error(err);
+    status.code = code as i32;
+    status
+}
+
+/// Converts a request error into a `Status` with an explicit code, keeping its details.
+pub fn make_status(code: Code, err: RequestError) -> Status {
+    from_proto_status(make_proto_status(code, err))
+}
+
+/// Converts a proto `Status` into a `Status`, keeping its details.
+pub fn from_proto_status(status: ProtoStatus) -> Status {
+    Status::with_details(
+        Code::from_i32(status.code),
+        status.message.clone(),
+        status.encode_to_vec().into(),
+    )
+}
+
+/// Converts a `Status` into a proto `Status`, decoding its details if present.
+pub fn decode_proto_status(status: &Status) -> ProtoStatus {
+    if !status.details().is_empty() {
+        if let Ok(proto_status) = ProtoStatus::decode(status.details()) {
+            return proto_status;
+        }
+    }
+    ProtoStatus {
+        code: status.code() as i32,
+        message: status.message().into(),
+        details: Vec::new(),
+    }
+}
@@ -0,0 +1,234 @@
+use axum::{
+    body::to_bytes,
+    extract::{Request, State},
+    response::Response,
+    Router,
+};
+use http::{uri::PathAndQuery, HeaderMap, Method, StatusCode, Uri};
+use prost::Message;
+use prost_reflect::{DescriptorPool, Kind, MessageDescriptor};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tonic::{
+    body::BoxBody,
+    client::Grpc,
+    codegen::StdError,
+    metadata::MetadataMap,
+    transport::server::{TcpConnectInfo, TlsConnectInfo},
+    Extensions, Status,
+};
+use tower::util::BoxCloneService;
+
+use grpc_sky_api::proto::FILE_DESCRIPTOR_SET;
+
+use crate::{
+    error::AppResult,
+    gateway::{
+        codec::RawCodec,
+        path_template::PathTemplate,
+        transcode::{
+            make_error_response, make_json_response, make_request_message, make_response_body,
+        },
+    },
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+    localization::ACCEPT_LANGUAGE_HEADER,
+    tracing::request_id::REQUEST_ID_HEADER,
+};
+
+pub mod codec;
+pub mod path_template;
+pub mod transcode;
+
+/// Services whose `google.api.http` rules are served.
+const GATEWAY_SERVICES: &[&str] = &["sky.v1.UserService", "sky.v1.PostService"];
+
+/// Request headers that are passed on as gRPC metadata.
+const FORWARDED_HEADERS: &[&str] = &[
+    "authorization",
+    ACCEPT_LANGUAGE_HEADER,
+    IDEMPOTENCY_KEY_HEADER,
+    REQUEST_ID_HEADER,
+    "traceparent",
+    "tracestate",
+];
+
+/// Same as the default message size limit of the gRPC server.
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+pub type GatewayService =
+    BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, StdError>;
+
+#[derive(Debug, Error)]
+pub enum GatewayError {
+    #[error("invalid path template `{0}`")]
+    InvalidPathTemplate(String),
+    #[error("descriptor `{0}` not found")]
+    DescriptorNotFound(String),
+    #[error("invalid HTTP rule of method `{0}`")]
+    InvalidRule(String),
+}
+
+/// Maps an HTTP method and path to a gRPC method.
+struct HttpRoute {
+    method: Method,
+    template: PathTemplate,
+    /// Request field mapped to the body, or `*` for the whole request.
+    body_field: Option<String>,
+    grpc_path: PathAndQuery,
+    input: MessageDescriptor,
+    output: MessageDescriptor,
+}
+
+/// Transcodes REST/JSON requests to calls of the gRPC services.
+pub struct Gateway {
+    routes: Vec<HttpRoute>,
+    // Not `Sync`, so a clone is taken for every call
+    service: Mutex<GatewayService>,
+}
+
+impl Gateway {
+    /// Reads the HTTP rules of the gateway services, which are called through `service`.
+    pub fn new(service: GatewayService) -> AppResult<Self> {
+        let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET)?;
+        let http_extension = pool
+            .get_extension_by_name("google.api.http")
+            .ok_or_else(|| GatewayError::DescriptorNotFound("google.api.http".into()))?;
+
+        let mut routes = Vec::new();
+        for service_name in GATEWAY_SERVICES {
+            let service_descriptor = pool
+                .get_service_by_name(service_name)
+                .ok_or_else(|| GatewayError::DescriptorNotFound(service_name.to_string()))?;
+            for method in service_descriptor.methods() {
+                let options = method.options();
+                if !options.has_extension(&http_extension) {
+                    continue;
+                }
+                let invalid_rule = || GatewayError::InvalidRule(method.full_name().into());
+                let rule = options.get_extension(&http_extension);
+                let rule = rule.as_message().ok_or_else(invalid_rule)?;
+
+                let (http_method, template) = [
+                    ("get", Method::GET),
+                    ("put", Method::PUT),
+                    ("post", Method::POST),
+                    ("delete", Method::DELETE),
+                    ("patch", Method::PATCH),
+                ]
+                .into_iter()
+                .find(|(name, _)| rule.has_field_by_name(name))
+                .and_then(|(name, http_method)| {
+                    Some((
+                        http_method,
+                        rule.get_field_by_name(name)?.as_str()?.to_string(),
+                    ))
+                })
+                .ok_or_else(invalid_rule)?;
+
+                let body_field = rule
+                    .get_field_by_name("body")
+                    .and_then(|body| body.as_str().map(ToString::to_string))
+                    .filter(|body| !body.is_empty());
+                if let Some(body_field) = body_field.as_deref().filter(|body| *body != "*") {
+                    match method.input().get_field_by_name(body_field) {
+                        Some(field) if matches!(field.kind(), Kind::Message(_)) => {}
+                        _ => return Err(invalid_rule().into()),
+                    }
+                }
+
+                routes.push(HttpRoute {
+                    method: http_method,
+                    template: PathTemplate::parse(&template)?,
+                    body_field,
+                    grpc_path: format!("/{}/{}", service_name, method.name()).parse()?,
+                    input: method.input(),
+                    output: method.output(),
+                });
+            }
+        }
+
+        Ok(Self {
+            routes,
+            service: Mutex::new(service),
+        })
+    }
+
+    /// The router is served by a tonic server, which adds the connection info of requests.
+    pub fn into_router(self) -> Router {
+        Router::new().fallback(handle).with_state(Arc::new(self))
+    }
+
+    async fn call(&self, request: Request) -> Result<Response, Status> {
+        let (parts, body) = request.into_parts();
+        // Rules are matched in declaration order
+        let (route, variables) = self
+            .routes
+            .iter()
+            .filter(|route| route.method == parts.method)
+            .find_map(|route| Some((route, route.template.matches(parts.uri.path())?)))
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "no route for {} {}",
+                    parts.method,
+                    parts.uri.path()
+                ))
+            })?;
+
+        let body = to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .map_err(|_| Status::invalid_argument("failed to read request body"))?;
+        let message = make_request_message(
+            &route.input,
+            route.body_field.as_deref(),
+            variables,
+            parts.uri.query(),
+            &body,
+        )?;
+
+        let mut headers = HeaderMap::new();
+        for name in FORWARDED_HEADERS {
+            for value in parts.headers.get_all(*name) {
+                headers.append(*name, value.clone());
+            }
+        }
+        // The peer address and client certificates are checked by the rate limit and auth layers
+        let mut extensions = Extensions::new();
+        if let Some(connect_info) = parts.extensions.get::<TcpConnectInfo>() {
+            extensions.insert(connect_info.clone());
+        }
+        if let Some(connect_info) = parts.extensions.get::<TlsConnectInfo<TcpConnectInfo>>() {
+            extensions.insert(connect_info.clone());
+        }
+        let grpc_request = tonic::Request::from_parts(
+            MetadataMap::from_headers(headers),
+            extensions,
+            message.encode_to_vec(),
+        );
+
+        let service = self.service.lock().unwrap().clone();
+        let mut grpc = Grpc::with_origin(service, Uri::from_static("http://localhost"));
+        grpc.ready()
+            .await
+            .map_err(|err| Status::unavailable(err.to_string()))?;
+        let (metadata, body, _) = grpc
+            .unary(grpc_request, route.grpc_path.clone(), RawCodec)
+            .await?
+            .into_parts();
+
+        let mut response =
+            make_json_response(StatusCode::OK, make_response_body(&route.output, &body)?);
+        if let Some(request_id) = metadata.into_headers().get(REQUEST_ID_HEADER) {
+            response
+                .headers_mut()
+                .insert(REQUEST_ID_HEADER, request_id.clone());
+        }
+        Ok(response)
+    }
+}
+
+async fn handle(State(gateway): State<Arc<Gateway>>, request: Request) -> Response {
+    gateway
+        .call(request)
+        .await
+        .unwrap_or_else(|status| make_error_response(&status))
+}
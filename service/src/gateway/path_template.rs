@@ -0,0 +1,202 @@
+use percent_encoding::percent_decode_str;
+
+use crate::gateway::GatewayError;
+
+/// Path template of a `google.api.http` rule, e.g. `/v1/{name=users/*}:verb`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+    variables: Vec<Variable>,
+    verb: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// Matches a single path segment.
+    Wildcard,
+    /// Matches the remaining path segments.
+    DoubleWildcard,
+}
+
+/// Field captured by the segments in `start..end`.
+#[derive(Debug, Clone, PartialEq)]
+struct Variable {
+    field_path: String,
+    start: usize,
+    end: usize,
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> Result<Self, GatewayError> {
+        let invalid = || GatewayError::InvalidPathTemplate(template.into());
+
+        let path = template.strip_prefix('/').ok_or_else(invalid)?;
+        let (mut rest, verb) = split_verb(path);
+
+        let mut segments = Vec::new();
+        let mut variables = Vec::new();
+        while !rest.is_empty() {
+            if let Some(variable) = rest.strip_prefix('{') {
+                let end = variable.find('}').ok_or_else(invalid)?;
+                let (field_path, pattern) = variable[..end]
+                    .split_once('=')
+                    .unwrap_or((&variable[..end], "*"));
+                if field_path.is_empty() {
+                    return Err(invalid());
+                }
+                let start = segments.len();
+                for segment in pattern.split('/') {
+                    segments.push(parse_segment(segment).ok_or_else(invalid)?);
+                }
+                variables.push(Variable {
+                    field_path: field_path.into(),
+                    start,
+                    end: segments.len(),
+                });
+                rest = &variable[end + 1..];
+            } else {
+                let end = rest.find('/').unwrap_or(rest.len());
+                segments.push(parse_segment(&rest[..end]).ok_or_else(invalid)?);
+                rest = &rest[end..];
+            }
+
+            rest = match rest.strip_prefix('/') {
+                Some(next) if !next.is_empty() => next,
+                None if rest.is_empty() => rest,
+                _ => return Err(invalid()),
+            };
+        }
+
+        // Only trailing segments can be matched unambiguously
+        if segments
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|segment| *segment == Segment::DoubleWildcard)
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            segments,
+            variables,
+            verb: verb.map(Into::into),
+        })
+    }
+
+    /// Returns the captured field paths and their percent-decoded values if the path matches.
+    pub fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let (path, verb) = split_verb(path.strip_prefix('/')?);
+        if verb != self.verb.as_deref() {
+            return None;
+        }
+        let parts: Vec<&str> = path.split('/').collect();
+
+        // Index of the first part after each segment
+        let mut ends = Vec::with_capacity(self.segments.len());
+        let mut index = 0;
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.get(index)? != literal {
+                        return None;
+                    }
+                    index += 1;
+                }
+                Segment::Wildcard => {
+                    if parts.get(index)?.is_empty() {
+                        return None;
+                    }
+                    index += 1;
+                }
+                Segment::DoubleWildcard => index = parts.len(),
+            }
+            ends.push(index);
+        }
+        if index != parts.len() {
+            return None;
+        }
+
+        self.variables
+            .iter()
+            .map(|variable| {
+                let start = match variable.start {
+                    0 => 0,
+                    start => ends[start - 1],
+                };
+                let value = parts[start..ends[variable.end - 1]]
+                    .iter()
+                    .map(|part| percent_decode_str(part).decode_utf8().ok())
+                    .collect::<Option<Vec<_>>>()?
+                    .join("/");
+                Some((variable.field_path.clone(), value))
+            })
+            .collect()
+    }
+}
+
+/// Splits a custom verb, e.g. `:signUp`, from the last segment.
+fn split_verb(path: &str) -> (&str, Option<&str>) {
+    match path.rsplit_once(':') {
+        Some((path, verb)) if !verb.contains(['/', '}']) => (path, Some(verb)),
+        _ => (path, None),
+    }
+}
+
+fn parse_segment(segment: &str) -> Option<Segment> {
+    match segment {
+        "" => None,
+        "*" => Some(Segment::Wildcard),
+        "**" => Some(Segment::DoubleWildcard),
+        _ if segment.contains(['{', '}', '=', '*']) => None,
+        _ => Some(Segment::Literal(segment.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_templates() {
+        let template = PathTemplate::parse("/v1/users/me").unwrap();
+        assert_eq!(template.matches("/v1/users/me"), Some(Vec::new()));
+        assert_eq!(template.matches("/v1/users/me:changePassword"), None);
+        assert_eq!(template.matches("/v1/users"), None);
+
+        let template = PathTemplate::parse("/v1/users:signUp").unwrap();
+        assert_eq!(template.matches("/v1/users:signUp"), Some(Vec::new()));
+        assert_eq!(template.matches("/v1/users"), None);
+        assert_eq!(template.matches("/v1/users:signIn"), None);
+
+        let template = PathTemplate::parse("/v1/{name=users/*}").unwrap();
+        assert_eq!(
+            template.matches("/v1/users/a%20b"),
+            Some(vec![("name".into(), "users/a b".into())])
+        );
+        assert_eq!(template.matches("/v1/users/"), None);
+        assert_eq!(template.matches("/v1/users/1/posts"), None);
+        assert_eq!(template.matches("/v1/posts/1"), None);
+
+        let template = PathTemplate::parse("/v1/{parent}/{name=files/**}").unwrap();
+        assert_eq!(template.matches("/v1/users/a/b"), None);
+        assert_eq!(
+            template.matches("/v1/tester/files/a/b"),
+            Some(vec![
+                ("parent".into(), "tester".into()),
+                ("name".into(), "files/a/b".into()),
+            ])
+        );
+
+        for template in [
+            "v1/users",
+            "/v1//users",
+            "/v1/users/",
+            "/v1/{=*}",
+            "/{name=**}/a",
+        ] {
+            assert!(PathTemplate::parse(template).is_err(), "{}", template);
+        }
+    }
+}
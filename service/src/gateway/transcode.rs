@@ -0,0 +1,303 @@
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderValue, StatusCode};
+use prost::Message;
+use prost_reflect::{DynamicMessage, Kind, MessageDescriptor, Value};
+use serde_json::{Map, Value as JsonValue};
+use tonic::{Code, Status};
+
+use crate::{error::decode_proto_status, tracing::request_id::REQUEST_ID_HEADER};
+
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Builds the request message from the body, query parameters and path variables.
+///
+/// Query parameters are ignored if the whole message is mapped to the body.
+/// Path variables take precedence over the body and query parameters.
+pub fn make_request_message(
+    input: &MessageDescriptor,
+    body_field: Option<&str>,
+    variables: Vec<(String, String)>,
+    query: Option<&str>,
+    body: &[u8],
+) -> Result<DynamicMessage, Status> {
+    let mut message = match body_field {
+        Some("*") => parse_json(input.clone(), body)?,
+        Some(field_name) => {
+            let mut message = DynamicMessage::new(input.clone());
+            let field = input
+                .get_field_by_name(field_name)
+                .ok_or_else(|| Status::internal(Code::Internal.description()))?;
+            let Kind::Message(field_message) = field.kind() else {
+                return Err(Status::internal(Code::Internal.description()));
+            };
+            message.set_field(&field, Value::Message(parse_json(field_message, body)?));
+            message
+        }
+        None => DynamicMessage::new(input.clone()),
+    };
+
+    let mut fields = Map::new();
+    if body_field != Some("*") {
+        for (field_path, value) in form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+            insert_field(input, &mut fields, &field_path, value.into_owned())?;
+        }
+    }
+    for (field_path, value) in variables {
+        insert_field(input, &mut fields, &field_path, value)?;
+    }
+    if !fields.is_empty() {
+        let overrides = DynamicMessage::deserialize(input.clone(), JsonValue::Object(fields))
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        message
+            .merge(overrides.encode_to_vec().as_slice())
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+    }
+
+    Ok(message)
+}
+
+fn parse_json(desc: MessageDescriptor, body: &[u8]) -> Result<DynamicMessage, Status> {
+    if body.is_empty() {
+        return Ok(DynamicMessage::new(desc));
+    }
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let message = DynamicMessage::deserialize(desc, &mut deserializer)
+        .and_then(|message| deserializer.end().map(|_| message))
+        .map_err(|err| Status::invalid_argument(format!("invalid JSON body: {}", err)))?;
+    Ok(message)
+}
+
+/// Inserts a value at a dotted field path, in the JSON mapping of the field.
+fn insert_field(
+    desc: &MessageDescriptor,
+    fields: &mut Map<String, JsonValue>,
+    field_path: &str,
+    value: String,
+) -> Result<(), Status> {
+    let unknown_field = || Status::invalid_argument(format!("unknown field `{}`", field_path));
+
+    let (name, rest) = match field_path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (field_path, None),
+    };
+    let field = desc
+        .get_field_by_name(name)
+        .or_else(|| desc.get_field_by_json_name(name))
+        .ok_or_else(unknown_field)?;
+
+    match (rest, field.kind()) {
+        (Some(rest), Kind::Message(field_message)) if !field.is_list() && !field.is_map() => {
+            let JsonValue::Object(nested) = fields
+                .entry(field.name())
+                .or_insert_with(|| JsonValue::Object(Map::new()))
+            else {
+                return Err(unknown_field());
+            };
+            insert_field(&field_message, nested, rest, value)
+        }
+        (Some(_), _) => Err(unknown_field()),
+        (None, kind) => {
+            // Other scalars, enums and well-known types accept strings in JSON
+            let value = match kind {
+                Kind::Bool => JsonValue::Bool(value.parse().map_err(|_| {
+                    Status::invalid_argument(format!("invalid boolean `{}`", field_path))
+                })?),
+                _ => JsonValue::String(value),
+            };
+            if field.is_list() {
+                match fields
+                    .entry(field.name())
+                    .or_insert_with(|| JsonValue::Array(Vec::new()))
+                {
+                    JsonValue::Array(values) => values.push(value),
+                    _ => return Err(unknown_field()),
+                }
+            } else {
+                fields.insert(field.name().into(), value);
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn make_response_body(output: &MessageDescriptor, body: &[u8]) -> Result<Vec<u8>, Status> {
+    let message = DynamicMessage::decode(output.clone(), body)
+        .map_err(|_| Status::internal(Code::Internal.description()))?;
+    serde_json::to_vec(&message).map_err(|_| Status::internal(Code::Internal.description()))
+}
+
+pub fn make_json_response(status_code: StatusCode, body: Vec<u8>) -> Response {
+    (
+        status_code,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(JSON_CONTENT_TYPE),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+/// Returns the status as a JSON `google.rpc.Status`, with its request ID.
+pub fn make_error_response(status: &Status) -> Response {
+    let body = serde_json::to_vec(&decode_proto_status(status)).unwrap_or_default();
+    let mut response = make_json_response(http_status_code(status.code()), body);
+    if let Some(request_id) = status
+        .metadata()
+        .clone()
+        .into_headers()
+        .get(REQUEST_ID_HEADER)
+    {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, request_id.clone());
+    }
+    response
+}
+
+/// Maps gRPC codes to HTTP statuses as documented in `google/rpc/code.proto`.
+pub fn http_status_code(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_reflect::DescriptorPool;
+
+    use grpc_sky_api::proto::{
+        GetUserRequest, ListPostsRequest, SignUpRequest, UpdateProfileRequest, FILE_DESCRIPTOR_SET,
+    };
+
+    use super::*;
+
+    fn transcode<T: Message + Default>(
+        input: &str,
+        body_field: Option<&str>,
+        variables: &[(&str, &str)],
+        query: Option<&str>,
+        body: &str,
+    ) -> Result<T, Status> {
+        let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET).unwrap();
+        let message = make_request_message(
+            &pool.get_message_by_name(input).unwrap(),
+            body_field,
+            variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            query,
+            body.as_bytes(),
+        )?;
+        Ok(T::decode(message.encode_to_vec().as_slice()).unwrap())
+    }
+
+    #[test]
+    fn query_parameters() {
+        let request: ListPostsRequest = transcode(
+            "sky.v1.ListPostsRequest",
+            None,
+            &[],
+            Some("pageSize=3&order_by=id%20asc"),
+            "",
+        )
+        .unwrap();
+        assert_eq!(request.page_size, Some(3));
+        assert_eq!(request.order_by.as_deref(), Some("id asc"));
+
+        let err = transcode::<ListPostsRequest>(
+            "sky.v1.ListPostsRequest",
+            None,
+            &[],
+            Some("unknown=1"),
+            "",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn path_variables() {
+        let request: GetUserRequest = transcode(
+            "sky.v1.GetUserRequest",
+            None,
+            &[("name", "users/1")],
+            Some("name=users/2"),
+            "",
+        )
+        .unwrap();
+        assert_eq!(request.name, "users/1");
+    }
+
+    #[test]
+    fn body() {
+        // Query parameters are ignored if the whole message is the body
+        let request: SignUpRequest = transcode(
+            "sky.v1.SignUpRequest",
+            Some("*"),
+            &[],
+            Some("name=bob"),
+            r#"{"name":"alice","password":"secret"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.name, "alice");
+        assert_eq!(request.password, "secret");
+
+        let request: UpdateProfileRequest = transcode(
+            "sky.v1.UpdateProfileRequest",
+            Some("user"),
+            &[("user.id", "2")],
+            None,
+            r#"{"id":"1","displayName":"Alice"}"#,
+        )
+        .unwrap();
+        let user = request.user.unwrap();
+        assert_eq!(user.id, "2");
+        assert_eq!(user.display_name, "Alice");
+
+        let err = transcode::<SignUpRequest>("sky.v1.SignUpRequest", Some("*"), &[], None, "{")
+            .unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(http_status_code(Code::Ok), StatusCode::OK);
+        assert_eq!(
+            http_status_code(Code::InvalidArgument),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            http_status_code(Code::Unauthenticated),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            http_status_code(Code::PermissionDenied),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(http_status_code(Code::Aborted), StatusCode::CONFLICT);
+        assert_eq!(
+            http_status_code(Code::ResourceExhausted),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(http_status_code(Code::Cancelled).as_u16(), 499);
+        assert_eq!(
+            http_status_code(Code::DataLoss),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::sync::watch;
+use tonic_health::{server::HealthReporter, ServingStatus};
+use tracing::{info, warn};
+
+use crate::{error::AppResult, metrics::SERVICE_SERVING, signal::wait_for_shutdown};
+
+#[tonic::async_trait]
+pub trait HealthCheck {
+    /// Fails if the backend is unreachable.
+    async fn health_check(&self) -> AppResult<()>;
+}
+
+pub type HealthCheckArc = Arc<dyn HealthCheck + Send + Sync>;
+
+/// Periodically checks backends and reports the services that depend on them
+/// to the health service.
+///
+/// Each backend is checked once per interval, however many services share it,
+/// e.g. through one of the repositories of a shared database.
+pub struct HealthMonitor {
+    reporter: HealthReporter,
+    interval: Duration,
+    backends: BTreeMap<&'static str, HealthCheckArc>,
+    /// Backend names of each service.
+    services: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+impl HealthMonitor {
+    pub fn new(reporter: HealthReporter, interval: Duration) -> Self {
+        Self {
+            reporter,
+            interval,
+            backends: BTreeMap::new(),
+            services: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_backend<C>(&mut self, backend_name: &'static str, check: C)
+    where
+        C: HealthCheck + Send + Sync + 'static,
+    {
+        self.backends.insert(backend_name, Arc::new(check));
+    }
+
+    /// Services are serving while all of their backends are reachable.
+    pub fn add_service(&mut self, service_name: &'static str, backend_names: &[&'static str]) {
+        self.services
+            .entry(service_name)
+            .or_default()
+            .extend_from_slice(backend_names);
+    }
+
+    /// Runs until shutdown is requested, after which every service is reported as not serving.
+    ///
+    /// Servers should only stop once this returns, so that clients can stop sending requests.
+    pub async fn run(mut self, shutdown_receiver: watch::Receiver<bool>) {
+        let shutdown = wait_for_shutdown(shutdown_receiver);
+        tokio::pin!(shutdown);
+
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = interval.tick() => self.check_all().await,
+            }
+        }
+
+        info!("Reporting services as not serving");
+        let service_names: Vec<_> = self.services.keys().copied().collect();
+        for service_name in service_names {
+            self.reporter
+                .set_service_status(service_name, ServingStatus::NotServing)
+                .await;
+        }
+        self.reporter
+            .set_service_status("", ServingStatus::NotServing)
+            .await;
+    }
+
+    async fn check_all(&mut self) {
+        let mut backend_serving = BTreeMap::new();
+        for (backend_name, check) in &self.backends {
+            // A hanging backend is as bad as an unreachable one
+            let result = tokio::time::timeout(self.interval, check.health_check()).await;
+            let serving = match result {
+                Ok(Ok(())) => true,
+                Ok(Err(err)) => {
+                    warn!("health check of {} failed: {}", backend_name, err);
+                    false
+                }
+                Err(_) => {
+                    warn!("health check of {} timed out", backend_name);
+                    false
+                }
+            };
+            backend_serving.insert(*backend_name, serving);
+        }
+
+        let mut all_serving = true;
+        for (service_name, backend_names) in &self.services {
+            // Unknown backends are never reachable
+            let serving = backend_names
+                .iter()
+                .all(|backend_name| backend_serving.get(backend_name) == Some(&true));
+            all_serving &= serving;
+            ::metrics::gauge!(SERVICE_SERVING, "service" => *service_name).set(if serving {
+                1.0
+            } else {
+                0.0
+            });
+            self.reporter
+                .set_service_status(*service_name, make_serving_status(serving))
+                .await;
+        }
+        self.reporter
+            .set_service_status("", make_serving_status(all_serving))
+            .await;
+    }
+}
+
+fn make_serving_status(serving: bool) -> ServingStatus {
+    if serving {
+        ServingStatus::Serving
+    } else {
+        ServingStatus::NotServing
+    }
+}
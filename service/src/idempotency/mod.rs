@@ -0,0 +1,397 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_proto::google::rpc::Status as ProtoStatus;
+use bomboni_request::error::RequestError;
+use prost::Message;
+use sha2::{Digest, Sha256};
+use std::{future::Future, sync::Arc, time::Duration};
+use tonic::{metadata::MetadataMap, Code, Status};
+use tracing::warn;
+
+use grpc_sky_api::{error::IdempotencyError, proto::idempotency_error::IdempotencyErrorReason};
+
+use crate::{
+    config::IdempotencyConfig,
+    date_time::add_seconds,
+    error::{decode_proto_status, from_proto_status, make_status, AppResult},
+    idempotency::repository::{IdempotencyKeyInsertRecord, IdempotencyRepositoryArc},
+};
+
+pub mod repository;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// How often a batch of expired keys is deleted.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Replays responses of mutating calls that are retried with the same idempotency key.
+pub struct IdempotencyManager {
+    idempotency_repository: IdempotencyRepositoryArc,
+    config: IdempotencyConfig,
+}
+
+impl IdempotencyManager {
+    pub fn new(
+        idempotency_repository: IdempotencyRepositoryArc,
+        config: IdempotencyConfig,
+    ) -> Self {
+        Self {
+            idempotency_repository,
+            config,
+        }
+    }
+
+    /// Runs the call, or returns the stored response of a previous call with the same key.
+    ///
+    /// Keys are scoped, e.g. to a method and user, and must be reused with the same request.
+    /// Failures are replayed like responses, except for retryable ones, which release the key.
+    pub async fn execute<Req, Res, F, Fut>(
+        &self,
+        scope: &str,
+        key: Option<&str>,
+        request: &Req,
+        call: F,
+    ) -> AppResult<Res>
+    where
+        Req: Message,
+        Res: Message + Default,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AppResult<Res>>,
+    {
+        let Some(key) = key else {
+            return call().await;
+        };
+        // Requests may contain secrets, so the key salts the hash
+        let request_hash = hex::encode(
+            Sha256::new()
+                .chain_update(key.as_bytes())
+                .chain_update(request.encode_to_vec())
+                .finalize(),
+        );
+
+        let Some(attempt_id) = self.acquire(scope, key, &request_hash).await? else {
+            let record = self
+                .idempotency_repository
+                .select(scope, key)
+                .await?
+                // The key was released in between because the call failed
+                .ok_or_else(|| make_in_progress_status(key))?;
+            if record.request_hash != request_hash {
+                return Err(IdempotencyError::new(IdempotencyErrorReason::KeyReused)
+                    .with_message(format!(
+                        "idempotency key `{}` was used with a different request",
+                        key
+                    ))
+                    .into());
+            }
+            if let Some(response) = record.response {
+                return Ok(Res::decode(response.as_slice())?);
+            }
+            if let Some(error) = record.error {
+                return Err(from_proto_status(ProtoStatus::decode(error.as_slice())?).into());
+            }
+            return Err(make_in_progress_status(key).into());
+        };
+
+        match call().await {
+            Ok(response) => {
+                self.idempotency_repository
+                    .update_response(scope, key, attempt_id, &response.encode_to_vec())
+                    .await?;
+                Ok(response)
+            }
+            Err(err) => {
+                let status = Status::from(err);
+                let result = if is_retryable(status.code()) {
+                    self.idempotency_repository
+                        .delete(scope, key, attempt_id)
+                        .await
+                } else {
+                    self.idempotency_repository
+                        .update_error(
+                            scope,
+                            key,
+                            attempt_id,
+                            &decode_proto_status(&status).encode_to_vec(),
+                        )
+                        .await
+                };
+                // Otherwise the key is released once its lease expires
+                if let Err(err) = result {
+                    warn!("failed to record idempotency key failure: {}", err);
+                }
+                Err(status.into())
+            }
+        }
+    }
+
+    /// Deletes a batch of expired keys periodically, until the runtime shuts down.
+    pub fn spawn_cleanup(self: &Arc<Self>) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(err) = manager
+                    .idempotency_repository
+                    .delete_expired(UtcDateTime::now())
+                    .await
+                {
+                    warn!("failed to delete expired idempotency keys: {}", err);
+                }
+            }
+        });
+    }
+
+    /// Returns the ID of the attempt that holds the key,
+    /// or `None` if the key is taken by a call that hasn't expired.
+    async fn acquire(&self, scope: &str, key: &str, request_hash: &str) -> AppResult<Option<Id>> {
+        let create_time = UtcDateTime::now();
+        let record = IdempotencyKeyInsertRecord {
+            scope,
+            key,
+            request_hash,
+            attempt_id: Id::generate(),
+            create_time,
+            expire_time: add_seconds(create_time, self.config.ttl_seconds),
+            locked_until: add_seconds(create_time, self.config.lease_seconds),
+        };
+        // Expired keys are reused, and calls that crashed are retried once their lease expires
+        let acquired = self.idempotency_repository.insert(&record).await?
+            || self.idempotency_repository.take_over(&record).await?;
+        Ok(acquired.then_some(record.attempt_id))
+    }
+}
+
+/// Reads the idempotency key from request metadata, if any.
+pub fn get_idempotency_key(metadata: &MetadataMap) -> AppResult<Option<String>> {
+    let Some(value) = metadata.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key)
+            if !key.is_empty()
+                && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH
+                && key.chars().all(|c| c.is_ascii_graphic()) =>
+        {
+            Ok(Some(key.into()))
+        }
+        _ => Err(IdempotencyError::new(IdempotencyErrorReason::InvalidKey).into()),
+    }
+}
+
+/// Concurrent calls with the same key are aborted, so that clients retry them later.
+fn make_in_progress_status(key: &str) -> tonic::Status {
+    make_status(
+        Code::Aborted,
+        RequestError::generic(
+            IdempotencyError::new(IdempotencyErrorReason::RequestInProgress).with_message(format!(
+                "a call with idempotency key `{}` is in progress",
+                key
+            )),
+        ),
+    )
+}
+
+/// Failures that may succeed on retry aren't replayed.
+fn is_retryable(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Unknown
+            | Code::Internal
+            | Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::Aborted
+            | Code::ResourceExhausted
+            | Code::Cancelled
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tonic::metadata::MetadataValue;
+
+    use crate::{error::AppError, idempotency::repository::memory::IdempotencyMemoryRepository};
+
+    use super::*;
+
+    fn make_manager(lease_seconds: u64) -> IdempotencyManager {
+        IdempotencyManager::new(
+            Arc::new(IdempotencyMemoryRepository::default()),
+            IdempotencyConfig {
+                ttl_seconds: 60,
+                lease_seconds,
+            },
+        )
+    }
+
+    fn get_reason(err: AppError) -> Option<IdempotencyErrorReason> {
+        IdempotencyError::from_status(decode_proto_status(&Status::from(err))).map(|err| err.reason)
+    }
+
+    #[tokio::test]
+    async fn replay() {
+        let manager = make_manager(60);
+        let request = "request".to_string();
+        let calls = AtomicUsize::new(0);
+        let call = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok("response".to_string())
+        };
+
+        for _ in 0..2 {
+            assert_eq!(
+                manager
+                    .execute("scope", Some("key"), &request, call)
+                    .await
+                    .unwrap(),
+                "response"
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        manager
+            .execute("other", Some("key"), &request, call)
+            .await
+            .unwrap();
+        manager
+            .execute("scope", None, &request, call)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn key_reused() {
+        let manager = make_manager(60);
+        let call = || async { Ok(String::new()) };
+        manager
+            .execute("scope", Some("key"), &"request".to_string(), call)
+            .await
+            .unwrap();
+
+        let err = manager
+            .execute("scope", Some("key"), &"other".to_string(), call)
+            .await
+            .unwrap_err();
+        assert_eq!(get_reason(err), Some(IdempotencyErrorReason::KeyReused));
+    }
+
+    #[tokio::test]
+    async fn in_progress() {
+        let manager = make_manager(60);
+        let request = "request".to_string();
+        let err = manager
+            .execute("scope", Some("key"), &request, || async {
+                manager
+                    .execute("scope", Some("key"), &request, || async {
+                        Ok(String::new())
+                    })
+                    .await
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(
+            get_reason(err),
+            Some(IdempotencyErrorReason::RequestInProgress)
+        );
+
+        // The aborted call released the key
+        assert_eq!(
+            manager
+                .execute("scope", Some("key"), &request, || async {
+                    Ok("response".to_string())
+                })
+                .await
+                .unwrap(),
+            "response"
+        );
+    }
+
+    #[tokio::test]
+    async fn take_over_expired_lease() {
+        let manager = make_manager(0);
+        let request = "request".to_string();
+        let response = manager
+            .execute("scope", Some("key"), &request, || async {
+                manager
+                    .execute("scope", Some("key"), &request, || async {
+                        Ok("retried".to_string())
+                    })
+                    .await
+                    .unwrap();
+                Ok("original".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(response, "original");
+
+        // The original call doesn't overwrite the response of the retry that took over its key
+        assert_eq!(
+            manager
+                .execute("scope", Some("key"), &request, || async {
+                    Ok("again".to_string())
+                })
+                .await
+                .unwrap(),
+            "retried"
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_failures() {
+        let manager = make_manager(60);
+        let request = "request".to_string();
+        let calls = AtomicUsize::new(0);
+        let call = |status: Status| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move { Err::<String, _>(AppError::from(status)) }
+        };
+
+        for _ in 0..2 {
+            let err = manager
+                .execute("scope", Some("invalid"), &request, || {
+                    call(Status::invalid_argument("invalid"))
+                })
+                .await
+                .unwrap_err();
+            let status = Status::from(err);
+            assert_eq!(status.code(), Code::InvalidArgument);
+            assert_eq!(status.message(), "invalid");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Retryable failures are run again
+        for _ in 0..2 {
+            manager
+                .execute("scope", Some("unavailable"), &request, || {
+                    call(Status::unavailable("unavailable"))
+                })
+                .await
+                .unwrap_err();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn parse_idempotency_key() {
+        let make_metadata = |key: &str| {
+            let mut metadata = MetadataMap::new();
+            metadata.insert(
+                IDEMPOTENCY_KEY_HEADER,
+                MetadataValue::try_from(key).unwrap(),
+            );
+            metadata
+        };
+
+        assert_eq!(
+            get_idempotency_key(&make_metadata("3f1c9a2e-7b4d-4e8f-9a6b-2c5d8e1f0a3b")).unwrap(),
+            Some("3f1c9a2e-7b4d-4e8f-9a6b-2c5d8e1f0a3b".into())
+        );
+        assert_eq!(get_idempotency_key(&MetadataMap::new()).unwrap(), None);
+        assert!(get_idempotency_key(&make_metadata("")).is_err());
+        assert!(get_idempotency_key(&make_metadata("a b")).is_err());
+        assert!(get_idempotency_key(&make_metadata(&"a".repeat(256))).is_err());
+    }
+}
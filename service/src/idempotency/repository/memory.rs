@@ -0,0 +1,135 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{
+    error::AppResult,
+    idempotency::repository::{
+        IdempotencyKeyInsertRecord, IdempotencyKeyRecord, IdempotencyRepository,
+    },
+};
+
+/// Keeps idempotency keys in memory, for tests of the idempotency manager.
+#[derive(Default)]
+pub struct IdempotencyMemoryRepository {
+    /// Records with the attempt that holds them.
+    keys: Mutex<BTreeMap<(String, String), (IdempotencyKeyRecord, Id)>>,
+}
+
+#[tonic::async_trait]
+impl IdempotencyRepository for IdempotencyMemoryRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn select(&self, scope: &str, key: &str) -> AppResult<Option<IdempotencyKeyRecord>> {
+        Ok(self
+            .keys
+            .lock()
+            .unwrap()
+            .get(&make_id(scope, key))
+            .map(|(record, _)| record.clone()))
+    }
+
+    async fn insert(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool> {
+        let mut keys = self.keys.lock().unwrap();
+        let id = make_id(record.scope, record.key);
+        if keys.contains_key(&id) {
+            return Ok(false);
+        }
+        keys.insert(id, (make_record(record), record.attempt_id));
+        Ok(true)
+    }
+
+    async fn take_over(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool> {
+        let mut keys = self.keys.lock().unwrap();
+        let Some((existing, attempt_id)) = keys.get_mut(&make_id(record.scope, record.key)) else {
+            return Ok(false);
+        };
+        let expired = existing.expire_time <= record.create_time;
+        let lease_expired = existing.response.is_none()
+            && existing.error.is_none()
+            && existing.locked_until <= record.create_time
+            && existing.request_hash == record.request_hash;
+        if !expired && !lease_expired {
+            return Ok(false);
+        }
+        *existing = make_record(record);
+        *attempt_id = record.attempt_id;
+        Ok(true)
+    }
+
+    async fn update_response(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        response: &[u8],
+    ) -> AppResult<()> {
+        if let Some((record, _)) = self
+            .keys
+            .lock()
+            .unwrap()
+            .get_mut(&make_id(scope, key))
+            .filter(|(_, holder)| *holder == attempt_id)
+        {
+            record.response = Some(response.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn update_error(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        error: &[u8],
+    ) -> AppResult<()> {
+        if let Some((record, _)) = self
+            .keys
+            .lock()
+            .unwrap()
+            .get_mut(&make_id(scope, key))
+            .filter(|(_, holder)| *holder == attempt_id)
+        {
+            record.error = Some(error.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, scope: &str, key: &str, attempt_id: Id) -> AppResult<()> {
+        let mut keys = self.keys.lock().unwrap();
+        let id = make_id(scope, key);
+        if keys
+            .get(&id)
+            .is_some_and(|(_, holder)| *holder == attempt_id)
+        {
+            keys.remove(&id);
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(&self, time: UtcDateTime) -> AppResult<()> {
+        self.keys
+            .lock()
+            .unwrap()
+            .retain(|_, (record, _)| record.expire_time >= time);
+        Ok(())
+    }
+}
+
+fn make_record(record: &IdempotencyKeyInsertRecord<'_>) -> IdempotencyKeyRecord {
+    IdempotencyKeyRecord {
+        scope: record.scope.into(),
+        key: record.key.into(),
+        request_hash: record.request_hash.into(),
+        response: None,
+        error: None,
+        create_time: record.create_time,
+        expire_time: record.expire_time,
+        locked_until: record.locked_until,
+    }
+}
+
+fn make_id(scope: &str, key: &str) -> (String, String) {
+    (scope.into(), key.into())
+}
@@ -0,0 +1,81 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use std::sync::Arc;
+
+use crate::{error::AppResult, health::HealthCheck};
+
+#[cfg(test)]
+pub mod memory;
+pub mod mysql;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyKeyRecord {
+    pub scope: String,
+    pub key: String,
+    pub request_hash: String,
+    /// Unset while the first call is in progress.
+    pub response: Option<Vec<u8>>,
+    /// Encoded status of a failed call, which is replayed like a response.
+    pub error: Option<Vec<u8>>,
+    pub create_time: UtcDateTime,
+    pub expire_time: UtcDateTime,
+    pub locked_until: UtcDateTime,
+}
+
+pub struct IdempotencyKeyInsertRecord<'a> {
+    pub scope: &'a str,
+    pub key: &'a str,
+    pub request_hash: &'a str,
+    /// Call that holds the key, which changes when a retry takes it over.
+    pub attempt_id: Id,
+    pub create_time: UtcDateTime,
+    pub expire_time: UtcDateTime,
+    pub locked_until: UtcDateTime,
+}
+
+#[tonic::async_trait]
+pub trait IdempotencyRepository {
+    /// Checks that the backend is reachable.
+    async fn health_check(&self) -> AppResult<()>;
+
+    async fn select(&self, scope: &str, key: &str) -> AppResult<Option<IdempotencyKeyRecord>>;
+
+    /// Returns `false` if the key is already taken.
+    async fn insert(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool>;
+
+    /// Replaces a key that expired, or whose call with the same request hash is still unfinished
+    /// past its lease, at the record's create time.
+    /// Returns `false` if the key is still taken.
+    async fn take_over(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool>;
+
+    /// Keys are only updated, and deleted, while the attempt holds them,
+    /// since a retry may have taken them over in the meantime.
+    async fn update_response(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        response: &[u8],
+    ) -> AppResult<()>;
+
+    async fn update_error(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        error: &[u8],
+    ) -> AppResult<()>;
+
+    async fn delete(&self, scope: &str, key: &str, attempt_id: Id) -> AppResult<()>;
+
+    /// Deletes a batch of keys that expired before the given time.
+    async fn delete_expired(&self, time: UtcDateTime) -> AppResult<()>;
+}
+
+pub type IdempotencyRepositoryArc = Arc<dyn IdempotencyRepository + Send + Sync>;
+
+#[tonic::async_trait]
+impl HealthCheck for IdempotencyRepositoryArc {
+    async fn health_check(&self) -> AppResult<()> {
+        IdempotencyRepository::health_check(self.as_ref()).await
+    }
+}
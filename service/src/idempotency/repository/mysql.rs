@@ -0,0 +1,230 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use mysql_async::{prelude::*, Params, Pool};
+
+use crate::{
+    error::AppResult,
+    idempotency::repository::{
+        IdempotencyKeyInsertRecord, IdempotencyKeyRecord, IdempotencyRepository,
+    },
+    metrics::get_mysql_conn,
+};
+
+pub struct IdempotencyMySqlRepository {
+    pool: Pool,
+}
+
+const ER_DUP_ENTRY: u16 = 1062;
+
+/// Expired keys deleted at once, so that cleanup doesn't hold locks for long.
+const DELETE_EXPIRED_BATCH_SIZE: usize = 1000;
+
+const IDEMPOTENCY_KEY_COLUMNS: &str = r#"
+    scope,
+    idempotency_key,
+    request_hash,
+    response,
+    error,
+    create_time,
+    expire_time,
+    locked_until
+"#;
+
+type IdempotencyKeyRow = (
+    String,
+    String,
+    String,
+    Option<Vec<u8>>,
+    Option<Vec<u8>>,
+    UtcDateTime,
+    UtcDateTime,
+    UtcDateTime,
+);
+
+impl IdempotencyMySqlRepository {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl IdempotencyRepository for IdempotencyMySqlRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+        conn.ping().await?;
+        Ok(())
+    }
+
+    async fn select(&self, scope: &str, key: &str) -> AppResult<Option<IdempotencyKeyRecord>> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let records = format!(
+            r#"
+                SELECT {} FROM idempotency_keys
+                WHERE scope = :scope AND idempotency_key = :idempotency_key
+                LIMIT 1
+            "#,
+            IDEMPOTENCY_KEY_COLUMNS
+        )
+        .with(params! {
+            "scope" => scope,
+            "idempotency_key" => key,
+        })
+        .map(&mut *conn, make_idempotency_key_record)
+        .await?;
+
+        Ok(records.into_iter().next())
+    }
+
+    async fn insert(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let result = r#"
+            INSERT INTO idempotency_keys (scope, idempotency_key, request_hash, attempt_id, create_time, expire_time, locked_until)
+            VALUES(:scope, :idempotency_key, :request_hash, :attempt_id, :create_time, :expire_time, :locked_until)
+        "#
+        .with(make_insert_params(record))
+        .ignore(&mut *conn)
+        .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(mysql_async::Error::Server(err)) if err.code == ER_DUP_ENTRY => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn take_over(&self, record: &IdempotencyKeyInsertRecord<'_>) -> AppResult<bool> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        // The row is locked while the condition is checked, so only one retry takes it over
+        r#"
+            UPDATE idempotency_keys
+            SET request_hash = :request_hash, attempt_id = :attempt_id, response = NULL, error = NULL,
+                create_time = :create_time, expire_time = :expire_time, locked_until = :locked_until
+            WHERE scope = :scope AND idempotency_key = :idempotency_key
+                AND (
+                    expire_time <= :create_time
+                    OR (
+                        response IS NULL AND error IS NULL
+                        AND locked_until <= :create_time AND request_hash = :request_hash
+                    )
+                )
+        "#
+        .with(make_insert_params(record))
+        .ignore(&mut *conn)
+        .await?;
+
+        Ok(conn.affected_rows() > 0)
+    }
+
+    async fn update_response(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        response: &[u8],
+    ) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"
+            UPDATE idempotency_keys SET response = :response
+            WHERE scope = :scope AND idempotency_key = :idempotency_key AND attempt_id = :attempt_id
+        "#
+        .with(params! {
+            "scope" => scope,
+            "idempotency_key" => key,
+            "attempt_id" => attempt_id,
+            "response" => response,
+        })
+        .ignore(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_error(
+        &self,
+        scope: &str,
+        key: &str,
+        attempt_id: Id,
+        error: &[u8],
+    ) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"
+            UPDATE idempotency_keys SET error = :error
+            WHERE scope = :scope AND idempotency_key = :idempotency_key AND attempt_id = :attempt_id
+        "#
+        .with(params! {
+            "scope" => scope,
+            "idempotency_key" => key,
+            "attempt_id" => attempt_id,
+            "error" => error,
+        })
+        .ignore(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, scope: &str, key: &str, attempt_id: Id) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"
+            DELETE FROM idempotency_keys
+            WHERE scope = :scope AND idempotency_key = :idempotency_key AND attempt_id = :attempt_id
+        "#
+        .with(params! {
+            "scope" => scope,
+            "idempotency_key" => key,
+            "attempt_id" => attempt_id,
+        })
+        .ignore(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, time: UtcDateTime) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        format!(
+            r#"DELETE FROM idempotency_keys WHERE expire_time < :time LIMIT {}"#,
+            DELETE_EXPIRED_BATCH_SIZE
+        )
+        .with(params! {
+            "time" => time,
+        })
+        .ignore(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn make_insert_params(record: &IdempotencyKeyInsertRecord<'_>) -> Params {
+    params! {
+        "scope" => record.scope,
+        "idempotency_key" => record.key,
+        "request_hash" => record.request_hash,
+        "attempt_id" => record.attempt_id,
+        "create_time" => record.create_time,
+        "expire_time" => record.expire_time,
+        "locked_until" => record.locked_until,
+    }
+}
+
+fn make_idempotency_key_record(
+    (scope, key, request_hash, response, error, create_time, expire_time, locked_until): IdempotencyKeyRow,
+) -> IdempotencyKeyRecord {
+    IdempotencyKeyRecord {
+        scope,
+        key,
+        request_hash,
+        response,
+        error,
+        create_time,
+        expire_time,
+        locked_until,
+    }
+}
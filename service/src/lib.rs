@@ -1,7 +1,20 @@
+pub mod api_key;
+pub mod auth;
+pub mod batch;
 pub mod config;
 pub mod context;
+pub mod cors;
+pub mod date_time;
 pub mod error;
+pub mod gateway;
+pub mod health;
+pub mod idempotency;
+pub mod localization;
+pub mod metrics;
+pub mod peer;
 pub mod post;
+pub mod rate_limit;
 pub mod signal;
+pub mod status;
 pub mod tracing;
 pub mod user;
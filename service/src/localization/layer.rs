@@ -0,0 +1,72 @@
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{ErrorInfo, Status as ProtoStatus},
+};
+use http::HeaderMap;
+use std::sync::Arc;
+
+use crate::{
+    localization::{MessageCatalog, ACCEPT_LANGUAGE_HEADER},
+    status::{RewriteStatus, RewriteStatusLayer},
+};
+
+/// Adds a `google.rpc.LocalizedMessage` to error statuses, in the locale of `accept-language`.
+///
+/// Errors are left as they are without a catalog.
+pub type LocalizationLayer = RewriteStatusLayer<Localization>;
+
+#[derive(Clone)]
+pub struct Localization {
+    catalog: Option<Arc<MessageCatalog>>,
+}
+
+impl Localization {
+    pub fn new(catalog: Option<Arc<MessageCatalog>>) -> Self {
+        Self { catalog }
+    }
+}
+
+impl RewriteStatus for Localization {
+    type State = Option<String>;
+
+    fn on_request<B>(&self, request: &mut http::Request<B>) -> Self::State {
+        request
+            .headers()
+            .get(ACCEPT_LANGUAGE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string)
+    }
+
+    fn rewrite_status(
+        &self,
+        accept_language: &Self::State,
+        _headers: &HeaderMap,
+        status: &mut ProtoStatus,
+    ) -> bool {
+        match self.catalog.as_ref() {
+            Some(catalog) => add_localized_message(status, catalog, accept_language.as_deref()),
+            None => false,
+        }
+    }
+}
+
+/// Returns whether a message of the error infos of the status was found.
+fn add_localized_message(
+    status: &mut ProtoStatus,
+    catalog: &MessageCatalog,
+    accept_language: Option<&str>,
+) -> bool {
+    let error_infos: Vec<ErrorInfo> = status
+        .details
+        .iter()
+        .filter_map(|detail| Any::unpack_into(detail.clone()).ok())
+        .collect();
+    let locale = catalog.negotiate_locale(accept_language);
+    let Some(localized_message) = catalog.localize(locale, &error_infos) else {
+        return false;
+    };
+    status
+        .details
+        .push(Any::pack_from(&localized_message).unwrap());
+    true
+}
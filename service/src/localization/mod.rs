@@ -0,0 +1,308 @@
+use bomboni_proto::google::rpc::{ErrorInfo, LocalizedMessage};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{negotiate_languages, parse_accepted_languages, NegotiationStrategy};
+use std::fs;
+use thiserror::Error;
+use unic_langid::LanguageIdentifier;
+
+use grpc_sky_api::error::{BAD_REQUEST_REASON, COMMON_ERROR_DOMAIN};
+
+use crate::{config::LocalizationConfig, error::AppResult};
+
+pub mod layer;
+
+pub const ACCEPT_LANGUAGE_HEADER: &str = "accept-language";
+/// Catalog file in the directory of every locale.
+const CATALOG_FILE_NAME: &str = "errors.ftl";
+
+#[derive(Debug, Error)]
+pub enum LocalizationError {
+    #[error("invalid locale `{0}`")]
+    InvalidLocale(String),
+    #[error("invalid message catalog of locale `{0}`")]
+    InvalidCatalog(String),
+    #[error("no message catalog for the default locale `{0}`")]
+    MissingDefaultLocale(String),
+}
+
+/// Fluent messages of error reasons, interpolated with `ErrorInfo` metadata.
+pub struct MessageCatalog {
+    locales: Vec<LanguageIdentifier>,
+    bundles: Vec<FluentBundle<FluentResource>>,
+    default_locale: LanguageIdentifier,
+}
+
+impl MessageCatalog {
+    /// Loads `{locale}/errors.ftl` of every locale directory.
+    pub fn load(config: &LocalizationConfig) -> AppResult<Self> {
+        let mut catalogs = Vec::new();
+        for entry in fs::read_dir(&config.locales_path)? {
+            let entry = entry?;
+            let path = entry.path().join(CATALOG_FILE_NAME);
+            if path.is_file() {
+                catalogs.push((
+                    entry.file_name().to_string_lossy().into_owned(),
+                    fs::read_to_string(path)?,
+                ));
+            }
+        }
+        Ok(Self::new(&config.default_locale, catalogs)?)
+    }
+
+    /// Creates a catalog from the Fluent sources of locales.
+    pub fn new(
+        default_locale: &str,
+        catalogs: Vec<(String, String)>,
+    ) -> Result<Self, LocalizationError> {
+        let parse_locale = |locale: &str| {
+            locale
+                .parse::<LanguageIdentifier>()
+                .map_err(|_| LocalizationError::InvalidLocale(locale.into()))
+        };
+        let default_locale = parse_locale(default_locale)?;
+
+        let mut locales = Vec::with_capacity(catalogs.len());
+        let mut bundles = Vec::with_capacity(catalogs.len());
+        for (locale, source) in catalogs {
+            let language = parse_locale(&locale)?;
+            let resource = FluentResource::try_new(source)
+                .map_err(|_| LocalizationError::InvalidCatalog(locale.clone()))?;
+            let mut bundle = FluentBundle::new_concurrent(vec![language.clone()]);
+            // Unicode isolation marks would end up in the messages shown to users
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .map_err(|_| LocalizationError::InvalidCatalog(locale))?;
+            locales.push(language);
+            bundles.push(bundle);
+        }
+
+        if !locales.contains(&default_locale) {
+            return Err(LocalizationError::MissingDefaultLocale(
+                default_locale.to_string(),
+            ));
+        }
+        Ok(Self {
+            locales,
+            bundles,
+            default_locale,
+        })
+    }
+
+    /// Picks the supported locale of an `accept-language` value, in order of preference.
+    pub fn negotiate_locale(&self, accept_language: Option<&str>) -> &LanguageIdentifier {
+        let requested = parse_accepted_languages(accept_language.unwrap_or_default());
+        negotiate_languages(
+            &requested,
+            &self.locales,
+            Some(&self.default_locale),
+            NegotiationStrategy::Lookup,
+        )
+        .first()
+        .copied()
+        .unwrap_or(&self.default_locale)
+    }
+
+    /// Formats the message of the most specific error info that has one.
+    ///
+    /// Domain reasons are preferred over common ones, and both over the reason of bad requests,
+    /// so that the error of the first field violation is shown.
+    pub fn localize(
+        &self,
+        locale: &LanguageIdentifier,
+        error_infos: &[ErrorInfo],
+    ) -> Option<LocalizedMessage> {
+        let mut error_infos: Vec<&ErrorInfo> = error_infos.iter().collect();
+        error_infos.sort_by_key(|error_info| {
+            if error_info.reason == BAD_REQUEST_REASON {
+                2
+            } else if error_info.domain == COMMON_ERROR_DOMAIN {
+                1
+            } else {
+                0
+            }
+        });
+        error_infos
+            .into_iter()
+            .find_map(|error_info| self.format_message(locale, error_info))
+    }
+
+    /// Formats the message of a reason, falling back to the default locale.
+    pub fn format_message(
+        &self,
+        locale: &LanguageIdentifier,
+        error_info: &ErrorInfo,
+    ) -> Option<LocalizedMessage> {
+        let mut args = FluentArgs::new();
+        for (key, value) in &error_info.metadata {
+            args.set(key.as_str(), FluentValue::try_number(value));
+        }
+
+        [locale, &self.default_locale]
+            .into_iter()
+            .find_map(|locale| {
+                let bundle = &self.bundles[self.locales.iter().position(|l| l == locale)?];
+                let pattern = bundle.get_message(&error_info.reason)?.value()?;
+                let mut errors = Vec::new();
+                let message = bundle.format_pattern(pattern, Some(&args), &mut errors);
+                Some(LocalizedMessage {
+                    locale: locale.to_string(),
+                    message: message.into_owned(),
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path};
+
+    use grpc_sky_api::{
+        error::{RATE_LIMIT_EXCEEDED_REASON, SKY_ERROR_DOMAIN},
+        proto::{
+            auth_error::AuthErrorReason, common_error::CommonErrorReason,
+            idempotency_error::IdempotencyErrorReason, post_error::PostErrorReason,
+            social_error::SocialErrorReason, user_error::UserErrorReason,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn catalogs_cover_reasons() {
+        let catalog = MessageCatalog::load(&LocalizationConfig {
+            locales_path: Path::new(env!("CARGO_MANIFEST_DIR")).join("config/locales"),
+            default_locale: "en".into(),
+        })
+        .unwrap();
+
+        let mut reasons = vec![BAD_REQUEST_REASON, RATE_LIMIT_EXCEEDED_REASON];
+        macro_rules! add_reasons {
+            ($($type:ty),* $(,)?) => {
+                $(
+                    // Values of removed reasons are reserved, so there can be gaps
+                    reasons.extend(
+                        (1..64)
+                            .filter_map(|value: i32| <$type>::try_from(value).ok())
+                            .map(|reason| reason.as_str_name()),
+                    );
+                )*
+            };
+        }
+        add_reasons!(
+            CommonErrorReason,
+            UserErrorReason,
+            IdempotencyErrorReason,
+            PostErrorReason,
+            SocialErrorReason,
+            AuthErrorReason,
+        );
+
+        assert!(catalog.locales.len() > 1);
+        for (locale, bundle) in catalog.locales.iter().zip(&catalog.bundles) {
+            for reason in &reasons {
+                assert!(bundle.has_message(reason), "{}: {}", locale, reason);
+            }
+        }
+    }
+
+    #[test]
+    fn localize_errors() {
+        let catalog = MessageCatalog::new(
+            "en",
+            vec![
+                (
+                    "en".into(),
+                    r#"
+COMMON_ERROR_REASON_NOT_FOUND = Not found.
+USER_ERROR_REASON_INVALID_NAME = The name "{ $userName }" is not valid.
+USER_ERROR_REASON_BIO_TOO_LONG = The bio can have at most { $maxLength ->
+    [one] one character
+   *[other] { $maxLength } characters
+}.
+BAD_REQUEST = The request is not valid.
+"#
+                    .into(),
+                ),
+                (
+                    "de".into(),
+                    "USER_ERROR_REASON_INVALID_NAME = Der Name „{ $userName }“ ist ungültig.\n"
+                        .into(),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let de = catalog.negotiate_locale(Some("de-AT,de;q=0.9,en;q=0.8"));
+        assert_eq!(de.to_string(), "de");
+        let en = catalog.negotiate_locale(Some("fr"));
+        assert_eq!(en.to_string(), "en");
+        assert_eq!(catalog.negotiate_locale(None).to_string(), "en");
+
+        let make_error_info = |reason: &str, domain: &str, metadata: &[(&str, &str)]| ErrorInfo {
+            reason: reason.into(),
+            domain: domain.into(),
+            metadata: metadata
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+        };
+        let invalid_name = make_error_info(
+            "USER_ERROR_REASON_INVALID_NAME",
+            &format!("{}/User", SKY_ERROR_DOMAIN),
+            &[("userName", "tester")],
+        );
+        assert_eq!(
+            catalog.format_message(de, &invalid_name),
+            Some(LocalizedMessage {
+                locale: "de".into(),
+                message: "Der Name „tester“ ist ungültig.".into(),
+            })
+        );
+
+        let bio_too_long = make_error_info(
+            "USER_ERROR_REASON_BIO_TOO_LONG",
+            &format!("{}/User", SKY_ERROR_DOMAIN),
+            &[("maxLength", "160")],
+        );
+        // Missing messages are taken from the default locale
+        assert_eq!(
+            catalog.format_message(de, &bio_too_long),
+            Some(LocalizedMessage {
+                locale: "en".into(),
+                message: "The bio can have at most 160 characters.".into(),
+            })
+        );
+
+        let not_found = make_error_info("COMMON_ERROR_REASON_NOT_FOUND", COMMON_ERROR_DOMAIN, &[]);
+        let bad_request = make_error_info(BAD_REQUEST_REASON, SKY_ERROR_DOMAIN, &[]);
+        assert_eq!(
+            catalog
+                .localize(en, &[bad_request.clone(), not_found.clone(), invalid_name])
+                .map(|message| message.message),
+            Some(r#"The name "tester" is not valid."#.into())
+        );
+        assert_eq!(
+            catalog
+                .localize(en, &[bad_request.clone(), not_found])
+                .map(|message| message.message),
+            Some("Not found.".into())
+        );
+        assert_eq!(
+            catalog
+                .localize(en, &[bad_request])
+                .map(|message| message.message),
+            Some("The request is not valid.".into())
+        );
+        assert_eq!(catalog.localize(en, &[]), None);
+
+        assert!(matches!(
+            MessageCatalog::new("fr", vec![("en".into(), String::new())]),
+            Err(LocalizationError::MissingDefaultLocale(_))
+        ));
+        assert!(matches!(
+            MessageCatalog::new("en", vec![("en".into(), "= invalid".into())]),
+            Err(LocalizationError::InvalidCatalog(_))
+        ));
+    }
+}
@@ -1,55 +1,270 @@
-use std::sync::Arc;
-use tonic::transport::Server;
-use tracing::info;
+use std::{fs, sync::Arc, time::Duration};
+use tonic::{
+    service::Routes,
+    transport::{Certificate, Identity, Server, ServerTlsConfig},
+};
+use tonic_web::GrpcWebLayer;
+use tower::{
+    util::{option_layer, BoxCloneService},
+    ServiceBuilder,
+};
+use tracing::{info, warn};
 
 use grpc_sky_api::proto::{
-    post_service_server::PostServiceServer, user_service_server::UserServiceServer,
+    api_key_service_server::{self, ApiKeyServiceServer},
+    post_service_server::{self, PostServiceServer},
+    user_service_server::{self, UserServiceServer},
     FILE_DESCRIPTOR_SET,
 };
 use grpc_sky_service::{
-    config::{AppConfig, DatabaseConfig, MySqlDatabaseConfig},
+    api_key::{
+        adapter::ApiKeyAdapter, create_command::CreateApiKeyCommand,
+        query_manager::ApiKeyQueryManager, repository::mysql::ApiKeyMySqlRepository,
+        revoke_command::RevokeApiKeyCommand,
+    },
+    auth::{
+        authenticator::Authenticator, layer::AuthLayer, oidc::OidcVerifier, token::TokenManager,
+    },
+    config::{AppConfig, DatabaseConfig, MySqlDatabaseConfig, TlsConfig},
+    cors::make_cors_layer,
     error::AppResult,
+    gateway::Gateway,
+    health::HealthMonitor,
+    idempotency::{repository::mysql::IdempotencyMySqlRepository, IdempotencyManager},
+    localization::{
+        layer::{Localization, LocalizationLayer},
+        MessageCatalog,
+    },
+    metrics::{self, layer::MetricsLayer},
     post::{
         adapter::PostAdapter, create_command::CreatePostCommand, query_manager::PostQueryManager,
         repository::mysql::PostMySqlRepository,
     },
-    tracing::tracer::Tracer,
+    rate_limit::{layer::RateLimitLayer, RateLimiter},
+    signal::{spawn_delayed_shutdown, spawn_shutdown_listener, wait_for_shutdown},
+    tracing::{
+        access_log::AccessLogLayer,
+        layer::{TraceContext, TraceContextLayer},
+        request_id::{RequestIdLayer, RequestIds},
+        tracer::Tracer,
+    },
     user::{
-        adapter::UserAdapter, repository::mysql::UserMySqlRepository,
-        sign_up_command::SignUpCommand,
+        adapter::UserAdapter, change_password_command::ChangePasswordCommand,
+        exchange_oidc_token_command::ExchangeOidcTokenCommand, password::PasswordPolicy,
+        query_manager::UserQueryManager, repository::mysql::UserMySqlRepository,
+        sign_in_command::SignInCommand, sign_in_throttle::SignInThrottle,
+        sign_up_command::SignUpCommand, unlock_account_command::UnlockAccountCommand,
+        update_profile_command::UpdateProfileCommand,
     },
 };
 
+/// Name of the database in health check logs.
+const MYSQL_BACKEND: &str = "mysql";
+/// How long closing database connections may take at shutdown.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> AppResult<()> {
     let config = AppConfig::get();
 
-    Tracer::install_stdout()?;
+    Tracer::install(&config.distribution, &config.tracing)?;
 
     info!(
         "Starting {} v{}",
         config.distribution.name, config.distribution.version
     );
     info!("Listening gRPC on {}", config.server.grpc_address);
+    if let Some(metrics_address) = config.server.metrics_address {
+        info!("Serving metrics on {}", metrics_address);
+        metrics::install(metrics_address)?;
+    }
 
-    let (user_repository, post_repository) = match &config.database {
-        DatabaseConfig::MySql(MySqlDatabaseConfig { connection }) => {
-            let pool = mysql_async::Pool::new(connection.as_str());
-            let user_repository = Arc::new(UserMySqlRepository::new(pool.clone()));
-            let post_repository = Arc::new(PostMySqlRepository::new(pool));
-            (user_repository, post_repository)
-        }
-        DatabaseConfig::Memory => todo!(),
-    };
+    let (pool, user_repository, post_repository, api_key_repository, idempotency_repository) =
+        match &config.database {
+            DatabaseConfig::MySql(MySqlDatabaseConfig { connection }) => {
+                let opts =
+                    mysql_async::Opts::from_url(connection).map_err(mysql_async::Error::from)?;
+                metrics::record_mysql_pool_limits(&opts);
+                let pool = mysql_async::Pool::new(opts);
+                let user_repository = Arc::new(UserMySqlRepository::new(pool.clone()));
+                let post_repository = Arc::new(PostMySqlRepository::new(pool.clone()));
+                let api_key_repository = Arc::new(ApiKeyMySqlRepository::new(pool.clone()));
+                let idempotency_repository =
+                    Arc::new(IdempotencyMySqlRepository::new(pool.clone()));
+                (
+                    pool,
+                    user_repository,
+                    post_repository,
+                    api_key_repository,
+                    idempotency_repository,
+                )
+            }
+            DatabaseConfig::Memory => todo!(),
+        };
 
-    let sign_up_command = SignUpCommand::new(user_repository.clone());
-    let user_adapter = UserAdapter::new(user_repository, sign_up_command);
+    let shutdown_receiver = spawn_shutdown_listener();
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let mut health_monitor = HealthMonitor::new(
+        health_reporter,
+        Duration::from_secs(config.server.health_check_interval_seconds),
+    );
+    // Every service stores its data, and idempotency keys, in the same database,
+    // so it is checked through a single repository
+    health_monitor.add_backend(MYSQL_BACKEND, user_repository.clone() as UserRepositoryArc);
+    for service_name in [
+        user_service_server::SERVICE_NAME,
+        post_service_server::SERVICE_NAME,
+        api_key_service_server::SERVICE_NAME,
+    ] {
+        health_monitor.add_service(service_name, &[MYSQL_BACKEND]);
+    }
+    // Services are reported as not serving before servers stop accepting connections
+    let server_shutdown_receiver = spawn_delayed_shutdown(
+        tokio::spawn(health_monitor.run(shutdown_receiver)),
+        Duration::from_secs(config.server.drain_delay_seconds),
+    );
+
+    let password_policy = Arc::new(PasswordPolicy::new(config.password_policy.clone())?);
+    let sign_in_throttle = Arc::new(SignInThrottle::new(config.sign_in_throttle.clone()));
+    let token_manager = Arc::new(TokenManager::new(&config.auth));
+    let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.clone()));
+    let idempotency_manager = Arc::new(IdempotencyManager::new(
+        idempotency_repository,
+        config.idempotency.clone(),
+    ));
+    idempotency_manager.spawn_cleanup();
+    let message_catalog = config
+        .localization
+        .as_ref()
+        .map(MessageCatalog::load)
+        .transpose()?
+        .map(Arc::new);
+    let oidc_verifier = config
+        .oidc
+        .clone()
+        .map(|oidc_config| Arc::new(OidcVerifier::new(oidc_config)));
+
+    let sign_up_command = SignUpCommand::new(user_repository.clone(), password_policy.clone());
+    let sign_in_command = SignInCommand::new(
+        user_repository.clone(),
+        sign_in_throttle.clone(),
+        token_manager.clone(),
+    );
+    let exchange_oidc_token_command = ExchangeOidcTokenCommand::new(
+        user_repository.clone(),
+        oidc_verifier,
+        token_manager.clone(),
+    );
+    let update_profile_command = UpdateProfileCommand::new(user_repository.clone());
+    let change_password_command =
+        ChangePasswordCommand::new(user_repository.clone(), password_policy);
+    let unlock_account_command =
+        UnlockAccountCommand::new(user_repository.clone(), sign_in_throttle);
+    let user_query_manager = UserQueryManager::new(user_repository.clone());
+    let authenticator = Arc::new(Authenticator::new(
+        token_manager,
+        api_key_repository.clone(),
+        user_repository.clone(),
+    ));
+    let user_adapter = UserAdapter::new(
+        user_repository,
+        sign_up_command,
+        sign_in_command,
+        exchange_oidc_token_command,
+        update_profile_command,
+        change_password_command,
+        unlock_account_command,
+        user_query_manager,
+        idempotency_manager.clone(),
+    );
 
     let create_post_command = CreatePostCommand::new(post_repository.clone());
     let post_query_manager = PostQueryManager::new(post_repository);
-    let post_adapter = PostAdapter::new(create_post_command, post_query_manager);
+    let post_adapter =
+        PostAdapter::new(create_post_command, post_query_manager, idempotency_manager);
+
+    let create_api_key_command =
+        CreateApiKeyCommand::new(api_key_repository.clone(), config.api_key.clone());
+    let revoke_api_key_command = RevokeApiKeyCommand::new(api_key_repository.clone());
+    let api_key_query_manager = ApiKeyQueryManager::new(api_key_repository);
+    let api_key_adapter = ApiKeyAdapter::new(
+        create_api_key_command,
+        revoke_api_key_command,
+        api_key_query_manager,
+    );
 
-    Server::builder()
+    let grpc_web_config = config.server.grpc_web.as_ref();
+    // gRPC-Web is served over HTTP/1.1
+    let mut server_builder = Server::builder().accept_http1(grpc_web_config.is_some());
+    let server_tls_config = config
+        .server
+        .tls
+        .as_ref()
+        .map(make_server_tls_config)
+        .transpose()?;
+    if let Some(tls_config) = config.server.tls.as_ref() {
+        info!(
+            "TLS enabled{}",
+            if tls_config.client_ca_path.is_some() {
+                " with client authentication"
+            } else {
+                ""
+            }
+        );
+    }
+    if let Some(server_tls_config) = server_tls_config.clone() {
+        server_builder = server_builder.tls_config(server_tls_config)?;
+    }
+
+    let drain_timeout = Duration::from_secs(config.server.shutdown_timeout_seconds);
+
+    let cors_layer = grpc_web_config.map(make_cors_layer).transpose()?;
+    if grpc_web_config.is_some() {
+        info!("gRPC-Web enabled");
+    }
+
+    // Shared by the gRPC server and the REST gateway
+    let layers = ServiceBuilder::new()
+        .layer(TraceContextLayer::new(TraceContext))
+        .layer(RequestIdLayer::new(RequestIds))
+        .layer(LocalizationLayer::new(Localization::new(message_catalog)))
+        .layer(AccessLogLayer)
+        .layer(MetricsLayer::new(metrics::grpc_methods()?))
+        .layer(RateLimitLayer::by_address(rate_limiter.clone()))
+        .layer(AuthLayer::new(authenticator))
+        .layer(RateLimitLayer::by_user(rate_limiter));
+    let user_service = UserServiceServer::new(user_adapter);
+    let post_service = PostServiceServer::new(post_adapter);
+
+    // The gateway is served with the same TLS config, so never in plaintext next to TLS
+    let http_server = match config.server.http_address {
+        Some(http_address) => {
+            info!("Listening HTTP on {}", http_address);
+            let gateway = Gateway::new(BoxCloneService::new(
+                layers.service(Routes::new(user_service.clone()).add_service(post_service.clone())),
+            ))?;
+            let mut http_server_builder = Server::builder().accept_http1(true);
+            if let Some(server_tls_config) = server_tls_config {
+                http_server_builder = http_server_builder.tls_config(server_tls_config)?;
+            }
+            Some(
+                http_server_builder
+                    .add_routes(Routes::from(gateway.into_router()))
+                    .serve_with_shutdown(
+                        http_address,
+                        wait_for_shutdown(server_shutdown_receiver.clone()),
+                    ),
+            )
+        }
+        None => None,
+    };
+
+    let server = server_builder
+        .layer(option_layer(cors_layer))
+        .layer(option_layer(grpc_web_config.map(|_| GrpcWebLayer::new())))
+        .layer(layers)
+        .add_service(health_service)
         .add_service(
             tonic_reflection::server::Builder::configure()
                 .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
@@ -57,10 +272,69 @@ async fn main() -> AppResult<()> {
                 .build_v1alpha()
                 .unwrap(),
         )
-        .add_service(UserServiceServer::new(user_adapter))
-        .add_service(PostServiceServer::new(post_adapter))
-        .serve(config.server.grpc_address)
-        .await?;
+        .add_service(user_service)
+        .add_service(post_service)
+        .add_service(ApiKeyServiceServer::new(api_key_adapter))
+        // New connections are refused once shutdown is requested,
+        // while in-flight requests can finish
+        .serve_with_shutdown(
+            config.server.grpc_address,
+            wait_for_shutdown(server_shutdown_receiver.clone()),
+        );
 
+    // Both servers stop when either fails
+    let servers = async {
+        tokio::try_join!(server, async {
+            match http_server {
+                Some(http_server) => http_server.await,
+                None => Ok(()),
+            }
+        })
+        .map(|_| ())
+    };
+
+    // Resources are released also when the server fails
+    let server_result = tokio::select! {
+        result = servers => result,
+        _ = async {
+            wait_for_shutdown(server_shutdown_receiver).await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            warn!("In-flight requests did not finish within {:?}", drain_timeout);
+            Ok(())
+        }
+    };
+
+    info!("Closing database connections");
+    let disconnect_result = match tokio::time::timeout(DISCONNECT_TIMEOUT, pool.disconnect()).await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                "Database connections did not close within {:?}",
+                DISCONNECT_TIMEOUT
+            );
+            Ok(())
+        }
+    };
+
+    Tracer::shutdown();
+
+    server_result?;
+    disconnect_result?;
     Ok(())
 }
+
+fn make_server_tls_config(tls_config: &TlsConfig) -> AppResult<ServerTlsConfig> {
+    let identity = Identity::from_pem(
+        fs::read(&tls_config.cert_path)?,
+        fs::read(&tls_config.key_path)?,
+    );
+    let mut server_tls_config = ServerTlsConfig::new().identity(identity);
+    if let Some(client_ca_path) = tls_config.client_ca_path.as_ref() {
+        server_tls_config = server_tls_config
+            .client_ca_root(Certificate::from_pem(fs::read(client_ca_path)?))
+            .client_auth_optional(tls_config.client_auth_optional);
+    }
+    Ok(server_tls_config)
+}
@@ -0,0 +1,206 @@
+use base64::{
+    alphabet,
+    engine::{general_purpose::GeneralPurpose, DecodePaddingMode, GeneralPurposeConfig},
+    Engine,
+};
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{ErrorInfo, Status as ProtoStatus},
+};
+use http::HeaderMap;
+use prost::Message;
+use std::{
+    collections::BTreeSet,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tonic::{body::BoxBody, Code};
+use tower::{Layer, Service};
+
+use crate::metrics::{ERRORS_TOTAL, REQUESTS_TOTAL, REQUEST_DURATION_SECONDS};
+
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+const GRPC_STATUS_DETAILS_HEADER: &str = "grpc-status-details-bin";
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// Binary metadata may or may not be padded.
+const BINARY_METADATA_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Records request count, errors and latency per gRPC method.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    methods: Arc<BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    methods: Arc<BTreeSet<String>>,
+}
+
+impl MetricsLayer {
+    /// Requests of other paths than `methods` are labeled as unknown, to keep the label bounded.
+    pub fn new(methods: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            methods: Arc::new(methods.into_iter().collect()),
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            methods: self.methods.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for MetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready must be the one that is called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let method = match request.uri().path() {
+            path if self.methods.contains(path) => path.to_string(),
+            _ => UNKNOWN_METHOD.to_string(),
+        };
+        let start = Instant::now();
+
+        Box::pin(async move {
+            let response = inner.call(request).await?;
+            record_response(method, response.headers(), start.elapsed());
+            Ok(response)
+        })
+    }
+}
+
+/// Errors of unary calls are sent as trailers-only responses, so a missing status header means
+/// that the call succeeded.
+fn record_response(method: String, headers: &HeaderMap, duration: Duration) {
+    let code = headers
+        .get(GRPC_STATUS_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .map_or(Code::Ok, Code::from_i32);
+
+    ::metrics::counter!(REQUESTS_TOTAL, "method" => method.clone()).increment(1);
+    ::metrics::histogram!(REQUEST_DURATION_SECONDS, "method" => method.clone())
+        .record(duration.as_secs_f64());
+    if code != Code::Ok {
+        ::metrics::counter!(
+            ERRORS_TOTAL,
+            "method" => method,
+            "code" => format!("{:?}", code),
+            "reason" => get_error_reason(headers).unwrap_or_default(),
+        )
+        .increment(1);
+    }
+}
+
+/// Returns the most specific `ErrorInfo` reason of the status details.
+fn get_error_reason(headers: &HeaderMap) -> Option<String> {
+    let details = headers.get(GRPC_STATUS_DETAILS_HEADER)?;
+    let details = BINARY_METADATA_ENGINE.decode(details.as_bytes()).ok()?;
+    let status = ProtoStatus::decode(details.as_slice()).ok()?;
+    status
+        .details
+        .into_iter()
+        .filter_map(|detail| Any::unpack_into::<ErrorInfo>(detail).ok())
+        .last()
+        .map(|error_info| error_info.reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::STANDARD_NO_PAD;
+    use http::HeaderValue;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::convert::Infallible;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn method_labels() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let service = MetricsLayer::new(["/sky.v1.UserService/GetMe".to_string()]).layer(
+            tower::service_fn(|_request: http::Request<BoxBody>| async {
+                Ok::<_, Infallible>(http::Response::new(tonic::body::empty_body()))
+            }),
+        );
+
+        ::metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    for path in [
+                        "/sky.v1.UserService/GetMe",
+                        "/sky.v1.UserService/Missing",
+                        "/wp-login.php",
+                    ] {
+                        let request = http::Request::builder()
+                            .uri(path)
+                            .body(tonic::body::empty_body())
+                            .unwrap();
+                        service.clone().oneshot(request).await.unwrap();
+                    }
+                });
+        });
+
+        let rendered = handle.render();
+        assert!(rendered
+            .contains(r#"grpc_server_requests_total{method="/sky.v1.UserService/GetMe"} 1"#));
+        assert!(rendered.contains(r#"grpc_server_requests_total{method="unknown"} 2"#));
+        assert!(!rendered.contains("Missing"));
+        assert!(!rendered.contains("wp-login"));
+    }
+
+    #[test]
+    fn error_reason_from_details() {
+        let status = ProtoStatus {
+            code: Code::InvalidArgument as i32,
+            message: String::new(),
+            details: ["COMMON", "SPECIFIC"]
+                .into_iter()
+                .map(|reason| {
+                    Any::pack_from(&ErrorInfo {
+                        reason: reason.into(),
+                        ..Default::default()
+                    })
+                    .unwrap()
+                })
+                .collect(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            GRPC_STATUS_DETAILS_HEADER,
+            HeaderValue::from_str(&STANDARD_NO_PAD.encode(status.encode_to_vec())).unwrap(),
+        );
+        assert_eq!(get_error_reason(&headers), Some("SPECIFIC".into()));
+        assert_eq!(get_error_reason(&HeaderMap::new()), None);
+    }
+}
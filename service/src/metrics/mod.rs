@@ -0,0 +1,98 @@
+use grpc_sky_api::proto::FILE_DESCRIPTOR_SET;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use prost_reflect::DescriptorPool;
+use std::{
+    net::SocketAddr,
+    ops::{Deref, DerefMut},
+};
+
+use crate::error::AppResult;
+
+pub mod layer;
+
+pub const REQUESTS_TOTAL: &str = "grpc_server_requests_total";
+pub const ERRORS_TOTAL: &str = "grpc_server_errors_total";
+pub const REQUEST_DURATION_SECONDS: &str = "grpc_server_request_duration_seconds";
+pub const SERVICE_SERVING: &str = "service_serving";
+pub const MYSQL_POOL_MIN_CONNECTIONS_LIMIT: &str = "mysql_pool_min_connections_limit";
+pub const MYSQL_POOL_MAX_CONNECTIONS_LIMIT: &str = "mysql_pool_max_connections_limit";
+pub const MYSQL_POOL_ACTIVE_CONNECTIONS: &str = "mysql_pool_active_connections";
+pub const SIGN_UPS_TOTAL: &str = "sky_sign_ups_total";
+pub const POSTS_CREATED_TOTAL: &str = "sky_posts_created_total";
+
+const REQUEST_DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Methods of the health and reflection services, which are not in the API descriptors.
+const STANDARD_METHODS: &[&str] = &[
+    "/grpc.health.v1.Health/Check",
+    "/grpc.health.v1.Health/Watch",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+/// Installs the global metrics recorder and serves metrics in the Prometheus format.
+pub fn install(metrics_address: SocketAddr) -> AppResult<()> {
+    PrometheusBuilder::new()
+        .with_http_listener(metrics_address)
+        .set_buckets_for_metric(
+            Matcher::Full(REQUEST_DURATION_SECONDS.into()),
+            REQUEST_DURATION_BUCKETS,
+        )?
+        .install()?;
+    Ok(())
+}
+
+/// Returns the paths of the served gRPC methods, which are the only values of the method label.
+pub fn grpc_methods() -> AppResult<Vec<String>> {
+    let pool = DescriptorPool::decode(FILE_DESCRIPTOR_SET)?;
+    let mut methods: Vec<_> = STANDARD_METHODS.iter().map(ToString::to_string).collect();
+    for service in pool.services() {
+        methods.extend(
+            service
+                .methods()
+                .map(|method| format!("/{}/{}", service.full_name(), method.name())),
+        );
+    }
+    Ok(methods)
+}
+
+/// Reports the configured connection limits of a MySQL pool.
+/// Live usage is reported by the connections of [`get_mysql_conn`].
+pub fn record_mysql_pool_limits(opts: &mysql_async::Opts) {
+    let constraints = opts.pool_opts().constraints();
+    ::metrics::gauge!(MYSQL_POOL_MIN_CONNECTIONS_LIMIT).set(constraints.min() as f64);
+    ::metrics::gauge!(MYSQL_POOL_MAX_CONNECTIONS_LIMIT).set(constraints.max() as f64);
+}
+
+/// Takes a connection from a MySQL pool, counted as active until it is dropped.
+///
+/// Idle connections are not reported, since the pool doesn't expose how many it keeps.
+pub async fn get_mysql_conn(pool: &mysql_async::Pool) -> Result<ActiveConn, mysql_async::Error> {
+    let conn = pool.get_conn().await?;
+    ::metrics::gauge!(MYSQL_POOL_ACTIVE_CONNECTIONS).increment(1.0);
+    Ok(ActiveConn(conn))
+}
+
+/// Connection of a MySQL pool that is returned to the pool when dropped.
+pub struct ActiveConn(mysql_async::Conn);
+
+impl Deref for ActiveConn {
+    type Target = mysql_async::Conn;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ActiveConn {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for ActiveConn {
+    fn drop(&mut self) {
+        ::metrics::gauge!(MYSQL_POOL_ACTIVE_CONNECTIONS).decrement(1.0);
+    }
+}
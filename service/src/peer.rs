@@ -0,0 +1,33 @@
+use std::net::SocketAddr;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+
+/// Returns the remote address of the connection a request was received on, with or without TLS.
+pub fn peer_addr<B>(request: &http::Request<B>) -> Option<SocketAddr> {
+    let extensions = request.extensions();
+    extensions
+        .get::<TcpConnectInfo>()
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .map(TlsConnectInfo::get_ref)
+        })
+        .and_then(TcpConnectInfo::remote_addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_peer_addr() {
+        let remote_addr: SocketAddr = "192.0.2.1:50000".parse().unwrap();
+        let mut request = http::Request::new(());
+        assert_eq!(peer_addr(&request), None);
+
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(remote_addr),
+        });
+        assert_eq!(peer_addr(&request), Some(remote_addr));
+    }
+}
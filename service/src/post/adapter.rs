@@ -1,17 +1,18 @@
 use bomboni_request::parse::RequestParse;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tonic::{Request, Response};
 
 use grpc_sky_api::{
-    dto::{ListPostsRequestDto, PostRequestDto},
+    dto::{BatchGetPostsRequestDto, ListPostsRequestDto, PostRequestDto},
     proto::{
-        post_service_server::PostService, ListPostsRequest, ListPostsResponse, PostRequest,
-        PostResponse,
+        post_service_server::PostService, BatchGetPostsRequest, BatchGetPostsResponse,
+        ListPostsRequest, ListPostsResponse, PostRequest, PostResponse,
     },
 };
 
 use crate::{
     context::Context,
+    idempotency::{get_idempotency_key, IdempotencyManager},
     post::{
         create_command::{CreatePostCommand, CreatePostCommandInput},
         query_manager::PostQueryManager,
@@ -21,16 +22,19 @@ use crate::{
 pub struct PostAdapter {
     create_post_command: CreatePostCommand,
     post_query_manager: PostQueryManager,
+    idempotency_manager: Arc<IdempotencyManager>,
 }
 
 impl PostAdapter {
     pub fn new(
         create_post_command: CreatePostCommand,
         post_query_manager: PostQueryManager,
+        idempotency_manager: Arc<IdempotencyManager>,
     ) -> Self {
         Self {
             create_post_command,
             post_query_manager,
+            idempotency_manager,
         }
     }
 }
@@ -45,23 +49,44 @@ impl PostService for PostAdapter {
         request: Request<PostRequest>,
     ) -> Result<Response<PostResponse>, tonic::Status> {
         let context = Context::from_request(&request);
+        let idempotency_key = get_idempotency_key(request.metadata())?;
 
-        let request = PostRequestDto::parse(request.into_inner())?;
+        let request = request.into_inner();
+        let request_dto = PostRequestDto::parse(request.clone())?;
 
-        let output = self
-            .create_post_command
+        // Keys are scoped to the author
+        let idempotency_scope = format!(
+            "post:{}",
+            context
+                .principal()
+                .map(|principal| principal.user_id.to_string())
+                .unwrap_or_default()
+        );
+        let response = self
+            .idempotency_manager
             .execute(
-                &context,
-                CreatePostCommandInput {
-                    content: &request.content,
+                &idempotency_scope,
+                idempotency_key.as_deref(),
+                &request,
+                || async {
+                    let output = self
+                        .create_post_command
+                        .execute(
+                            &context,
+                            CreatePostCommandInput {
+                                content: &request_dto.content,
+                            },
+                        )
+                        .await?;
+                    Ok(PostResponse {
+                        post_id: output.post_id.to_string(),
+                        create_time: Some(output.create_time.into()),
+                    })
                 },
             )
             .await?;
 
-        Ok(Response::new(PostResponse {
-            post_id: output.post_id.to_string(),
-            create_time: Some(output.create_time.into()),
-        }))
+        Ok(Response::new(response))
     }
 
     #[tracing::instrument(skip(self, request), fields(
@@ -86,6 +111,24 @@ impl PostService for PostAdapter {
             total_size: output.total_size,
         }))
     }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn batch_get_posts(
+        &self,
+        request: Request<BatchGetPostsRequest>,
+    ) -> Result<Response<BatchGetPostsResponse>, tonic::Status> {
+        let request = BatchGetPostsRequestDto::parse(request.into_inner())?;
+
+        let results = self
+            .post_query_manager
+            // Posts are public, so none are forbidden
+            .batch_get(&request.names, request.allow_missing, |_| true)
+            .await?;
+
+        Ok(Response::new(BatchGetPostsResponse { results }))
+    }
 }
 
 impl Debug for PostAdapter {
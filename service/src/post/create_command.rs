@@ -1,8 +1,10 @@
 use bomboni_common::{date_time::UtcDateTime, id::Id};
 
 use crate::{
+    auth::permission::Permission,
     context::Context,
     error::AppResult,
+    metrics::POSTS_CREATED_TOTAL,
     post::repository::{PostInsertRecord, PostRepositoryArc},
 };
 
@@ -20,6 +22,8 @@ pub struct CreatePostCommandOutput {
 }
 
 impl CreatePostCommand {
+    pub const PERMISSION: Permission = Permission::PostsWrite;
+
     pub fn new(post_repository: PostRepositoryArc) -> Self {
         Self { post_repository }
     }
@@ -29,7 +33,7 @@ impl CreatePostCommand {
         context: &Context,
         input: CreatePostCommandInput<'_>,
     ) -> AppResult<CreatePostCommandOutput> {
-        let user_id = context.authenticate()?;
+        let user_id = context.authorize(Self::PERMISSION)?;
 
         let post_id = Id::generate();
         let create_time = UtcDateTime::now();
@@ -42,6 +46,7 @@ impl CreatePostCommand {
                 create_time,
             })
             .await?;
+        ::metrics::counter!(POSTS_CREATED_TOTAL).increment(1);
 
         Ok(CreatePostCommandOutput {
             post_id,
@@ -1,3 +1,4 @@
+use bomboni_common::id::Id;
 use bomboni_request::{
     ordering::{OrderingDirection, OrderingTerm},
     query::{
@@ -6,9 +7,18 @@ use bomboni_request::{
     },
     schema::FunctionSchemaMap,
 };
-use grpc_sky_api::{dto::PostDto, proto::Post};
+use grpc_sky_api::{
+    dto::{make_post_name, PostDto},
+    error::PostError,
+    proto::{post_error::PostErrorReason, post_result, BatchGetPostsRequest, Post, PostResult},
+};
+use prost::Name;
 
-use crate::{error::AppResult, post::repository::PostRepositoryArc};
+use crate::{
+    batch::BatchGet,
+    error::AppResult,
+    post::repository::{PostRecord, PostRepositoryArc},
+};
 
 pub struct PostQueryManager {
     post_repository: PostRepositoryArc,
@@ -69,19 +79,176 @@ impl PostQueryManager {
                 .items
                 .into_iter()
                 // TODO: apply views
-                .map(|record| Post {
-                    id: record.id.to_string(),
-                    user_id: record.user_id.to_string(),
-                    content: record.content,
-                    create_time: Some(record.create_time.into()),
-                })
+                .map(make_post)
                 .collect(),
             next_page_token,
             total_size: post_list.total_size,
         })
     }
 
+    /// Returns results in the same order as `ids`, where posts rejected by `can_read` are
+    /// reported as missing.
+    /// Unless `allow_missing` is set, any missing post fails the whole request.
+    pub async fn batch_get(
+        &self,
+        ids: &[Id],
+        allow_missing: bool,
+        can_read: impl Fn(&PostRecord) -> bool,
+    ) -> AppResult<Vec<PostResult>> {
+        let records = self.post_repository.select_many(ids).await?;
+        let results = BatchGet {
+            request_name: BatchGetPostsRequest::NAME,
+            ids,
+            allow_missing,
+        }
+        .collect(
+            records.into_iter().map(|record| (record.id, record)),
+            can_read,
+            make_post_not_found_error,
+        )?;
+
+        Ok(ids
+            .iter()
+            .zip(results)
+            .map(|(id, result)| PostResult {
+                name: make_post_name(*id),
+                result: Some(match result {
+                    Ok(record) => post_result::Result::Post(make_post(record)),
+                    Err(status) => post_result::Result::Error(status),
+                }),
+            })
+            .collect())
+    }
+
     pub fn list_query_builder(&self) -> &Aes256ListQueryBuilder {
         &self.list_query_builder
     }
 }
+
+fn make_post_not_found_error(id: Id) -> PostError {
+    PostError::new(PostErrorReason::PostNotFound).with_post_id(&id.to_string())
+}
+
+fn make_post(record: PostRecord) -> Post {
+    Post {
+        id: record.id.to_string(),
+        user_id: record.user_id.to_string(),
+        content: record.content,
+        create_time: Some(record.create_time.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bomboni_common::date_time::UtcDateTime;
+    use bomboni_request::error::{PathErrorStep, RequestError};
+    use grpc_sky_api::error::decode_request_error;
+    use std::sync::Arc;
+    use tonic::{Code, Status};
+
+    use crate::{
+        error::decode_proto_status,
+        post::repository::{memory::PostMemoryRepository, PostInsertRecord, PostRepository},
+    };
+
+    use super::*;
+
+    async fn insert_post(post_repository: &PostMemoryRepository) -> Id {
+        let id = Id::generate();
+        post_repository
+            .insert(PostInsertRecord {
+                id,
+                user_id: Id::generate(),
+                content: "Hello",
+                create_time: UtcDateTime::now(),
+            })
+            .await
+            .unwrap();
+        id
+    }
+
+    fn result_post_id(result: &PostResult) -> Option<String> {
+        match result.result.as_ref()? {
+            post_result::Result::Post(post) => Some(post.id.clone()),
+            post_result::Result::Error(status) => {
+                assert_eq!(status.code, Code::NotFound as i32);
+                None
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_get_posts() {
+        let post_repository = Arc::new(PostMemoryRepository::default());
+        let first_id = insert_post(&post_repository).await;
+        let second_id = insert_post(&post_repository).await;
+        let missing_id = Id::generate();
+        let query_manager = PostQueryManager::new(post_repository);
+
+        // Results follow the order of the request, including duplicates
+        let ids = [second_id, missing_id, first_id, second_id];
+        let results = query_manager.batch_get(&ids, true, |_| true).await.unwrap();
+        assert_eq!(
+            results.iter().map(result_post_id).collect::<Vec<_>>(),
+            vec![
+                Some(second_id.to_string()),
+                None,
+                Some(first_id.to_string()),
+                Some(second_id.to_string()),
+            ]
+        );
+        assert_eq!(results[1].name, make_post_name(missing_id));
+
+        // Forbidden posts are reported like missing ones
+        let results = query_manager
+            .batch_get(&ids, true, |record| record.id != first_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            results.iter().map(result_post_id).collect::<Vec<_>>(),
+            vec![
+                Some(second_id.to_string()),
+                None,
+                None,
+                Some(second_id.to_string())
+            ]
+        );
+
+        let results = query_manager
+            .batch_get(&[first_id, second_id], false, |_| true)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+
+        // Without `allow_missing`, a violation is reported for each missing or forbidden post
+        let status = Status::from(
+            query_manager
+                .batch_get(&ids, false, |record| record.id != first_id)
+                .await
+                .unwrap_err(),
+        );
+        assert_eq!(status.code(), Code::NotFound);
+        let Some(RequestError::BadRequest { name, violations }) =
+            decode_request_error(decode_proto_status(&status))
+        else {
+            panic!("expected a bad request");
+        };
+        assert_eq!(name, BatchGetPostsRequest::NAME);
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| violation.path.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    PathErrorStep::Field("names".into()),
+                    PathErrorStep::Index(1)
+                ],
+                vec![
+                    PathErrorStep::Field("names".into()),
+                    PathErrorStep::Index(2)
+                ],
+            ]
+        );
+    }
+}
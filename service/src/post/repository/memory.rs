@@ -0,0 +1,65 @@
+use bomboni_common::id::Id;
+use bomboni_request::query::list::ListQuery;
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{
+    error::AppResult,
+    post::repository::{PostInsertRecord, PostRecord, PostRecordList, PostRepository},
+};
+
+/// Keeps posts in memory, for tests of commands and query managers.
+#[derive(Default)]
+pub struct PostMemoryRepository {
+    posts: Mutex<BTreeMap<Id, PostRecord>>,
+}
+
+#[tonic::async_trait]
+impl PostRepository for PostMemoryRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn select(&self, id: Id) -> AppResult<Option<PostRecord>> {
+        Ok(self.posts.lock().unwrap().get(&id).cloned())
+    }
+
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<PostRecord>> {
+        let posts = self.posts.lock().unwrap();
+        Ok(ids.iter().filter_map(|id| posts.get(id).cloned()).collect())
+    }
+
+    /// Lists the first page by descending ID, since filters and page tokens are only applied
+    /// by the MySQL repository.
+    async fn select_list(&self, query: &ListQuery) -> AppResult<PostRecordList> {
+        let posts = self.posts.lock().unwrap();
+        let mut items: Vec<PostRecord> = posts
+            .values()
+            .rev()
+            .take(query.page_size as usize + 1)
+            .cloned()
+            .collect();
+        let next_item = if items.len() > query.page_size as usize {
+            items.pop()
+        } else {
+            None
+        };
+        Ok(PostRecordList {
+            items,
+            next_item,
+            total_size: posts.len() as i64,
+        })
+    }
+
+    async fn insert(&self, record: PostInsertRecord<'_>) -> AppResult<()> {
+        self.posts.lock().unwrap().insert(
+            record.id,
+            PostRecord {
+                id: record.id,
+                user_id: record.user_id,
+                content: record.content.into(),
+                create_time: record.create_time,
+            },
+        );
+        Ok(())
+    }
+}
@@ -2,8 +2,10 @@ use bomboni_common::{date_time::UtcDateTime, id::Id};
 use bomboni_request::{query::list::ListQuery, schema::SchemaMapped, value::Value as FilterValue};
 use std::sync::Arc;
 
-use crate::error::AppResult;
+use crate::{error::AppResult, health::HealthCheck};
 
+#[cfg(test)]
+pub mod memory;
 pub mod mysql;
 
 #[derive(Debug, Clone)]
@@ -30,8 +32,13 @@ pub struct PostRecordList {
 
 #[tonic::async_trait]
 pub trait PostRepository {
+    /// Checks that the backend is reachable.
+    async fn health_check(&self) -> AppResult<()>;
+
     async fn select(&self, id: Id) -> AppResult<Option<PostRecord>>;
 
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<PostRecord>>;
+
     async fn select_list(&self, query: &ListQuery) -> AppResult<PostRecordList>;
 
     async fn insert(&self, record: PostInsertRecord<'_>) -> AppResult<()>;
@@ -39,6 +46,13 @@ pub trait PostRepository {
 
 pub type PostRepositoryArc = Arc<dyn PostRepository + Send + Sync>;
 
+#[tonic::async_trait]
+impl HealthCheck for PostRepositoryArc {
+    async fn health_check(&self) -> AppResult<()> {
+        PostRepository::health_check(self.as_ref()).await
+    }
+}
+
 impl SchemaMapped for PostRecord {
     fn get_field(&self, name: &str) -> FilterValue {
         match name {
@@ -13,6 +13,7 @@ use grpc_sky_api::dto::PostDto;
 
 use crate::{
     error::AppResult,
+    metrics::get_mysql_conn,
     post::repository::{PostInsertRecord, PostRecord, PostRecordList, PostRepository},
 };
 
@@ -45,15 +46,21 @@ impl PostMySqlRepository {
 
 #[tonic::async_trait]
 impl PostRepository for PostMySqlRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+        conn.ping().await?;
+        Ok(())
+    }
+
     async fn select(&self, id: Id) -> AppResult<Option<PostRecord>> {
-        let mut conn = self.pool.get_conn().await?;
+        let mut conn = get_mysql_conn(&self.pool).await?;
 
         let users = r#"SELECT id, user_id, content, create_time FROM posts WHERE id = :id LIMIT 1"#
             .with(params! {
                 "id" => id,
             })
             .map(
-                &mut conn,
+                &mut *conn,
                 |(id, user_id, content, create_time): (Id, Id, String, UtcDateTime)| PostRecord {
                     id,
                     user_id,
@@ -66,6 +73,35 @@ impl PostRepository for PostMySqlRepository {
         Ok(users.into_iter().next())
     }
 
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<PostRecord>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let statement = format!(
+            r#"SELECT id, user_id, content, create_time FROM posts WHERE id IN ({})"#,
+            vec!["?"; ids.len()].join(", "),
+        );
+        let params: Vec<mysql_async::Value> = ids.iter().copied().map(Into::into).collect();
+
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let posts = statement
+            .with(params)
+            .map(
+                &mut *conn,
+                |(id, user_id, content, create_time): (Id, Id, String, UtcDateTime)| PostRecord {
+                    id,
+                    user_id,
+                    content,
+                    create_time,
+                },
+            )
+            .await?;
+
+        Ok(posts)
+    }
+
     async fn select_list(&self, query: &ListQuery) -> AppResult<PostRecordList> {
         let query_statement = self
             .query_sql_builder
@@ -110,7 +146,7 @@ impl PostRepository for PostMySqlRepository {
             },
         );
 
-        let mut conn = self.pool.get_conn().await?;
+        let mut conn = get_mysql_conn(&self.pool).await?;
 
         let mut items = {
             let params: Vec<mysql_async::Value> = query_statement
@@ -122,7 +158,7 @@ impl PostRepository for PostMySqlRepository {
             statement
                 .with(params)
                 .map(
-                    &mut conn,
+                    &mut *conn,
                     |(id, user_id, content, create_time): (Id, Id, String, UtcDateTime)| {
                         PostRecord {
                             id,
@@ -144,7 +180,7 @@ impl PostRepository for PostMySqlRepository {
 
             total_count_statement
                 .with(params)
-                .map(&mut conn, |(total_count,): (i64,)| total_count)
+                .map(&mut *conn, |(total_count,): (i64,)| total_count)
                 .await?
         };
 
@@ -163,7 +199,7 @@ impl PostRepository for PostMySqlRepository {
     }
 
     async fn insert(&self, record: PostInsertRecord<'_>) -> AppResult<()> {
-        let mut conn = self.pool.get_conn().await?;
+        let mut conn = get_mysql_conn(&self.pool).await?;
 
         r#"INSERT INTO posts (id, user_id, content, create_time) VALUES(:id, :user_id, :content, :create_time)"#
             .with(params! {
@@ -172,7 +208,7 @@ impl PostRepository for PostMySqlRepository {
                 "content" => record.content,
                 "create_time" => record.create_time,
             })
-            .ignore(&mut conn)
+            .ignore(&mut *conn)
             .await?;
 
         info!("inserted user: {:?}", record.id);
@@ -0,0 +1,176 @@
+use bomboni_request::error::RequestError;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tonic::{body::BoxBody, Code};
+use tower::{Layer, Service};
+
+use grpc_sky_api::error::RateLimitError;
+
+use crate::{
+    auth::Principal,
+    error::make_status,
+    peer::peer_addr,
+    rate_limit::{RateLimitSubject, RateLimiter},
+};
+
+/// Rejects calls over the per-method limit with `RESOURCE_EXHAUSTED`.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    rate_limiter: Arc<RateLimiter>,
+    subject_kind: SubjectKind,
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    rate_limiter: Arc<RateLimiter>,
+    subject_kind: SubjectKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SubjectKind {
+    Address,
+    User,
+}
+
+impl RateLimitLayer {
+    /// Limits every call by remote address.
+    ///
+    /// It must be placed before the auth layer, so that calls with invalid credentials are
+    /// limited too.
+    pub fn by_address(rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            rate_limiter,
+            subject_kind: SubjectKind::Address,
+        }
+    }
+
+    /// Limits authenticated calls by user ID, so it must be placed after the auth layer.
+    pub fn by_user(rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            rate_limiter,
+            subject_kind: SubjectKind::User,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            rate_limiter: self.rate_limiter.clone(),
+            subject_kind: self.subject_kind,
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready must be the one that is called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let method = request.uri().path();
+        let subject = match self.subject_kind {
+            SubjectKind::Address => {
+                peer_addr(&request).map(|address| RateLimitSubject::Address(address.ip()))
+            }
+            SubjectKind::User => request
+                .extensions()
+                .get::<Principal>()
+                .map(|principal| RateLimitSubject::User(principal.user_id)),
+        };
+        if let Some(subject) = subject {
+            if let Err(retry_delay) = self.rate_limiter.acquire(method, subject) {
+                let per_minute = self
+                    .rate_limiter
+                    .rule(method)
+                    .map_or(0, |rule| rule.per_minute);
+                let status = make_status(
+                    Code::ResourceExhausted,
+                    RequestError::generic(RateLimitError {
+                        subject: subject.to_string(),
+                        description: format!(
+                            "Rate limit of {} calls per minute exceeded for {}",
+                            per_minute, method
+                        ),
+                        retry_delay,
+                    }),
+                );
+                return Box::pin(async move { Ok(status.into_http()) });
+            }
+        }
+
+        Box::pin(async move { inner.call(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr};
+    use tonic::transport::server::TcpConnectInfo;
+    use tower::{service_fn, ServiceExt};
+
+    use crate::config::{RateLimitConfig, RateLimitRule};
+
+    use super::*;
+
+    const METHOD: &str = "/sky.v1.UserService/SignIn";
+
+    /// Calls the layer from `remote_addr` and returns the error code, if any.
+    async fn call(layer: &RateLimitLayer, remote_addr: &str) -> Option<Code> {
+        let service = layer.layer(service_fn(|_request: http::Request<()>| async {
+            Ok::<_, Infallible>(http::Response::new(tonic::body::empty_body()))
+        }));
+        let mut request = http::Request::builder().uri(METHOD).body(()).unwrap();
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(remote_addr.parse::<SocketAddr>().unwrap()),
+        });
+        let response = service.oneshot(request).await.unwrap();
+        tonic::Status::from_header_map(response.headers()).map(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn limit_subjects() {
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig {
+            rules: vec![RateLimitRule {
+                method: METHOD.into(),
+                burst: 1,
+                per_minute: 1,
+            }],
+        }));
+        let by_address = RateLimitLayer::by_address(rate_limiter.clone());
+        let by_user = RateLimitLayer::by_user(rate_limiter);
+
+        assert_eq!(call(&by_address, "192.0.2.1:50000").await, None);
+        assert_eq!(
+            call(&by_address, "192.0.2.1:50001").await,
+            Some(Code::ResourceExhausted)
+        );
+        assert_eq!(call(&by_address, "192.0.2.2:50000").await, None);
+
+        // Calls without a principal are left to the address limit
+        assert_eq!(call(&by_user, "192.0.2.1:50000").await, None);
+        assert_eq!(call(&by_user, "192.0.2.1:50000").await, None);
+    }
+}
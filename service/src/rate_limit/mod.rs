@@ -0,0 +1,161 @@
+use bomboni_common::id::Id;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::config::{RateLimitConfig, RateLimitRule};
+
+pub mod layer;
+
+/// Buckets that were not used for this long are full again and can be dropped.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket rate limiter with a bucket per method and subject.
+///
+/// State is kept in memory, so every service instance limits independently.
+pub struct RateLimiter {
+    rules: HashMap<String, RateLimitRule>,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    buckets: HashMap<(String, RateLimitSubject), TokenBucket>,
+    last_cleanup: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitSubject {
+    User(Id),
+    Address(IpAddr),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    update_time: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            rules: config
+                .rules
+                .into_iter()
+                .map(|rule| (rule.method.clone(), rule))
+                .collect(),
+            state: Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                last_cleanup: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rule(&self, method: &str) -> Option<&RateLimitRule> {
+        self.rules.get(method)
+    }
+
+    /// Takes a token for the call, or returns how long to wait until one is available.
+    pub fn acquire(&self, method: &str, subject: RateLimitSubject) -> Result<(), Duration> {
+        self.acquire_at(method, subject, Instant::now())
+    }
+
+    fn acquire_at(
+        &self,
+        method: &str,
+        subject: RateLimitSubject,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let Some(rule) = self.rules.get(method) else {
+            return Ok(());
+        };
+        let capacity = f64::from(rule.burst);
+        let tokens_per_second = f64::from(rule.per_minute) / 60.0;
+
+        let mut state = self.state.lock().unwrap();
+        if now.duration_since(state.last_cleanup) >= CLEANUP_INTERVAL {
+            let rules = &self.rules;
+            state.buckets.retain(|(method, _), bucket| {
+                rules.get(method).is_some_and(|rule| {
+                    let refill = f64::from(rule.per_minute) / 60.0
+                        * now.duration_since(bucket.update_time).as_secs_f64();
+                    bucket.tokens + refill < f64::from(rule.burst)
+                })
+            });
+            state.last_cleanup = now;
+        }
+
+        let bucket = state
+            .buckets
+            .entry((method.into(), subject))
+            .or_insert(TokenBucket {
+                tokens: capacity,
+                update_time: now,
+            });
+        let elapsed = now.duration_since(bucket.update_time).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * tokens_per_second).min(capacity);
+        bucket.update_time = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / tokens_per_second,
+        ))
+    }
+}
+
+impl Display for RateLimitSubject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(user_id) => write!(f, "user:{}", user_id),
+            Self::Address(address) => write!(f, "address:{}", address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            rules: vec![RateLimitRule {
+                method: "/sky.v1.PostService/Post".into(),
+                burst: 2,
+                per_minute: 6,
+            }],
+        });
+        let method = "/sky.v1.PostService/Post";
+        let subject = RateLimitSubject::Address("127.0.0.1".parse().unwrap());
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at(method, subject, now).is_ok());
+        assert!(limiter.acquire_at(method, subject, now).is_ok());
+        assert_eq!(
+            limiter.acquire_at(method, subject, now),
+            Err(Duration::from_secs(10))
+        );
+        assert!(limiter
+            .acquire_at(
+                method,
+                RateLimitSubject::Address("127.0.0.2".parse().unwrap()),
+                now
+            )
+            .is_ok());
+        assert!(limiter
+            .acquire_at(method, subject, now + Duration::from_secs(10))
+            .is_ok());
+
+        for _ in 0..10 {
+            assert!(limiter
+                .acquire_at("/sky.v1.PostService/ListPosts", subject, now)
+                .is_ok());
+        }
+    }
+}
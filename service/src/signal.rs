@@ -1,4 +1,6 @@
-use std::future::Future;
+use std::{future::Future, time::Duration};
+use tokio::sync::watch;
+use tracing::info;
 
 pub fn make_shutdown_signal() -> impl Future<Output = ()> {
     use tokio::signal;
@@ -17,3 +19,35 @@ pub fn make_shutdown_signal() -> impl Future<Output = ()> {
     };
     shutdown
 }
+
+/// Broadcasts a shutdown request once a termination signal is received.
+pub fn spawn_shutdown_listener() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        make_shutdown_signal().await;
+        info!("Shutdown requested");
+        let _ = sender.send(true);
+    });
+    receiver
+}
+
+/// Broadcasts a shutdown request `delay` after `ready` resolves, e.g. after services are reported
+/// as not serving.
+pub fn spawn_delayed_shutdown<F>(ready: F, delay: Duration) -> watch::Receiver<bool>
+where
+    F: Future + Send + 'static,
+{
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        ready.await;
+        info!("Stopping servers in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        let _ = sender.send(true);
+    });
+    receiver
+}
+
+/// Resolves once shutdown has been requested.
+pub async fn wait_for_shutdown(mut receiver: watch::Receiver<bool>) {
+    let _ = receiver.wait_for(|shutdown| *shutdown).await;
+}
@@ -0,0 +1,198 @@
+use bomboni_proto::google::rpc::Status as ProtoStatus;
+use http::HeaderMap;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tonic::{body::BoxBody, Code, Status};
+use tower::{Layer, Service};
+use tracing::{Instrument, Span};
+
+use crate::error::{decode_proto_status, from_proto_status};
+
+/// Hooks of a [`RewriteStatusLayer`] around every call.
+pub trait RewriteStatus {
+    /// State of a request that its response is rewritten with.
+    type State: Send + 'static;
+
+    /// Called before the inner service is called with the request.
+    fn on_request<B>(&self, request: &mut http::Request<B>) -> Self::State;
+
+    /// Span that the call is instrumented with.
+    fn span(&self, _state: &Self::State) -> Span {
+        Span::current()
+    }
+
+    /// Called with the headers of every response, before the status is rewritten.
+    fn on_response(&self, _state: &Self::State, _headers: &mut HeaderMap) {}
+
+    /// Rewrites the status of an error response and returns whether it was changed.
+    fn rewrite_status(
+        &self,
+        state: &Self::State,
+        headers: &HeaderMap,
+        status: &mut ProtoStatus,
+    ) -> bool;
+}
+
+/// Rewrites the error statuses of responses, e.g. to add details.
+#[derive(Debug, Clone, Default)]
+pub struct RewriteStatusLayer<R> {
+    rewrite: R,
+}
+
+#[derive(Debug, Clone)]
+pub struct RewriteStatusService<S, R> {
+    inner: S,
+    rewrite: R,
+}
+
+impl<R> RewriteStatusLayer<R> {
+    pub fn new(rewrite: R) -> Self {
+        Self { rewrite }
+    }
+}
+
+impl<S, R: Clone> Layer<S> for RewriteStatusLayer<R> {
+    type Service = RewriteStatusService<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RewriteStatusService {
+            inner,
+            rewrite: self.rewrite.clone(),
+        }
+    }
+}
+
+impl<S, R, B> Service<http::Request<B>> for RewriteStatusService<S, R>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    R: RewriteStatus + Clone + Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready must be the one that is called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let rewrite = self.rewrite.clone();
+        let state = rewrite.on_request(&mut request);
+        let span = rewrite.span(&state);
+
+        Box::pin(
+            async move {
+                let mut response = inner.call(request).await?;
+                let headers = response.headers_mut();
+                rewrite.on_response(&state, headers);
+                rewrite_error_status(headers, |headers, status| {
+                    rewrite.rewrite_status(&state, headers, status)
+                });
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Rewrites the status of an error response with `f`, if it returns that it was changed.
+///
+/// Errors of unary calls are sent as trailers-only responses, so their status is in the headers.
+pub fn rewrite_error_status<F>(headers: &mut HeaderMap, f: F)
+where
+    F: FnOnce(&HeaderMap, &mut ProtoStatus) -> bool,
+{
+    let Some(status) = Status::from_header_map(headers) else {
+        return;
+    };
+    if status.code() == Code::Ok {
+        return;
+    }
+    let mut proto_status = decode_proto_status(&status);
+    if f(headers, &mut proto_status) {
+        // Keep the original status if the new one can't be encoded
+        let _ = from_proto_status(proto_status).add_header(headers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    const SUFFIX_HEADER: &str = "x-suffix";
+
+    /// Appends the `x-suffix` request header to error messages.
+    #[derive(Clone)]
+    struct AppendSuffix;
+
+    impl RewriteStatus for AppendSuffix {
+        type State = String;
+
+        fn on_request<B>(&self, request: &mut http::Request<B>) -> Self::State {
+            request
+                .headers()
+                .get(SUFFIX_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        }
+
+        fn rewrite_status(
+            &self,
+            state: &Self::State,
+            _headers: &HeaderMap,
+            status: &mut ProtoStatus,
+        ) -> bool {
+            status.message.push_str(state);
+            !state.is_empty()
+        }
+    }
+
+    async fn call(status: Option<Status>, suffix: &str) -> Option<Status> {
+        let service = RewriteStatusLayer::new(AppendSuffix).layer(service_fn(
+            move |_request: http::Request<()>| {
+                let status = status.clone();
+                async move {
+                    Ok::<_, Infallible>(match status {
+                        Some(status) => status.into_http(),
+                        None => http::Response::new(tonic::body::empty_body()),
+                    })
+                }
+            },
+        ));
+        let request = http::Request::builder()
+            .header(SUFFIX_HEADER, suffix)
+            .body(())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        Status::from_header_map(response.headers())
+    }
+
+    #[tokio::test]
+    async fn rewrite_error_statuses() {
+        let status = call(Some(Status::not_found("missing")), " post")
+            .await
+            .unwrap();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "missing post");
+
+        // Unchanged statuses are not encoded again
+        let status = call(Some(Status::not_found("missing")), "").await.unwrap();
+        assert_eq!(status.message(), "missing");
+        assert!(status.details().is_empty());
+
+        assert!(call(None, " post").await.is_none());
+    }
+}
@@ -0,0 +1,154 @@
+use bomboni_common::id::Id;
+use http::HeaderMap;
+use http_body::{Body, Frame, SizeHint};
+use std::{
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tonic::{body::BoxBody, Code, Status};
+use tower::{Layer, Service};
+use tracing::info;
+
+use crate::{auth::Principal, peer::peer_addr, tracing::request_id::RequestId};
+
+/// Target of access log events, which are written as JSON regardless of the log format.
+pub const ACCESS_LOG_TARGET: &str = "access_log";
+
+/// Logs one line per RPC once its response has been sent.
+#[derive(Debug, Clone, Default)]
+pub struct AccessLogLayer;
+
+#[derive(Debug, Clone)]
+pub struct AccessLogService<S> {
+    inner: S,
+}
+
+/// Counts the response size and captures the status, which may be sent in trailers.
+struct AccessLogBody {
+    inner: BoxBody,
+    entry: AccessLogEntry,
+}
+
+struct AccessLogEntry {
+    method: String,
+    peer: Option<SocketAddr>,
+    request_id: Option<String>,
+    user_id: Option<Id>,
+    code: Option<Code>,
+    start_time: Instant,
+    response_size: usize,
+}
+
+impl<S> Layer<S> for AccessLogLayer {
+    type Service = AccessLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLogService { inner }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for AccessLogService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // The service that was polled ready must be the one that is called
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let method = request.uri().path().to_string();
+        let peer = peer_addr(&request);
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .map(|request_id| request_id.0.clone());
+        let start_time = Instant::now();
+
+        Box::pin(async move {
+            let response = inner.call(request).await?;
+            let user_id = response
+                .extensions()
+                .get::<Principal>()
+                .map(|principal| principal.user_id);
+            let code = get_code(response.headers());
+            Ok(response.map(|body| {
+                tonic::body::boxed(AccessLogBody {
+                    inner: body,
+                    entry: AccessLogEntry {
+                        method,
+                        peer,
+                        request_id,
+                        user_id,
+                        code,
+                        start_time,
+                        response_size: 0,
+                    },
+                })
+            }))
+        })
+    }
+}
+
+impl Body for AccessLogBody {
+    type Data = <BoxBody as Body>::Data;
+    type Error = <BoxBody as Body>::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        let frame = Pin::new(&mut this.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &frame {
+            if let Some(data) = frame.data_ref() {
+                this.entry.response_size += data.len();
+            } else if let Some(trailers) = frame.trailers_ref() {
+                this.entry.code = this.entry.code.or(get_code(trailers));
+            }
+        }
+        frame
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for AccessLogBody {
+    fn drop(&mut self) {
+        let entry = &self.entry;
+        // Without a status the client went away before the response was sent
+        let code = entry.code.unwrap_or(Code::Cancelled);
+        info!(
+            target: ACCESS_LOG_TARGET,
+            method = entry.method.as_str(),
+            peer = entry.peer.map(|peer| peer.to_string()),
+            request_id = entry.request_id.as_deref(),
+            user_id = entry.user_id.map(|user_id| user_id.to_string()),
+            code = ?code,
+            latency_ms = entry.start_time.elapsed().as_secs_f64() * 1000.0,
+            response_size = entry.response_size,
+        );
+    }
+}
+
+fn get_code(headers: &HeaderMap) -> Option<Code> {
+    Status::from_header_map(headers).map(|status| status.code())
+}
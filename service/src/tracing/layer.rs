@@ -0,0 +1,118 @@
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{RequestInfo, Status as ProtoStatus},
+};
+use http::HeaderMap;
+use opentelemetry::{global, propagation::Extractor, trace::TraceContextExt, trace::TraceId};
+use tracing::{field::Empty, info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::{
+    status::{RewriteStatus, RewriteStatusLayer},
+    tracing::request_id::REQUEST_ID_HEADER,
+};
+
+/// Starts a server span for every request, parented to the caller's `traceparent`.
+///
+/// The trace ID is added to the details of error statuses, so that clients can refer to it.
+pub type TraceContextLayer = RewriteStatusLayer<TraceContext>;
+
+#[derive(Debug, Clone, Default)]
+pub struct TraceContext;
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl RewriteStatus for TraceContext {
+    type State = (Span, TraceId);
+
+    fn on_request<B>(&self, request: &mut http::Request<B>) -> Self::State {
+        let parent_context = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let span = info_span!(
+            "grpc_request",
+            otel.name = request.uri().path(),
+            otel.kind = "server",
+            rpc.system = "grpc",
+            request_id = Empty,
+        );
+        span.set_parent(parent_context);
+        let trace_id = span.context().span().span_context().trace_id();
+        (span, trace_id)
+    }
+
+    fn span(&self, (span, _): &Self::State) -> Span {
+        span.clone()
+    }
+
+    fn rewrite_status(
+        &self,
+        (_, trace_id): &Self::State,
+        headers: &HeaderMap,
+        status: &mut ProtoStatus,
+    ) -> bool {
+        if *trace_id == TraceId::INVALID {
+            return false;
+        }
+        let request_id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        add_trace_id(status, request_id, *trace_id);
+        true
+    }
+}
+
+fn add_trace_id(status: &mut ProtoStatus, request_id: &str, trace_id: TraceId) {
+    status.details.push(
+        Any::pack_from(&RequestInfo {
+            request_id: request_id.into(),
+            serving_data: trace_id.to_string(),
+        })
+        .unwrap(),
+    );
+}
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::{Code, Status};
+
+    use crate::{error::decode_proto_status, status::rewrite_error_status};
+
+    use super::*;
+
+    #[test]
+    fn add_trace_id_to_error_details() {
+        let trace_id = TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+
+        let mut headers = HeaderMap::new();
+        Status::not_found("missing")
+            .add_header(&mut headers)
+            .unwrap();
+        rewrite_error_status(&mut headers, |_, status| {
+            add_trace_id(status, "abc-123", trace_id);
+            true
+        });
+
+        let status = Status::from_header_map(&headers).unwrap();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "missing");
+        let request_info: RequestInfo =
+            Any::unpack_into(decode_proto_status(&status).details.remove(0)).unwrap();
+        assert_eq!(request_info.request_id, "abc-123");
+        assert_eq!(
+            request_info.serving_data,
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+    }
+}
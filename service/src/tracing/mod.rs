@@ -1 +1,4 @@
+pub mod access_log;
+pub mod layer;
+pub mod request_id;
 pub mod tracer;
@@ -0,0 +1,142 @@
+use bomboni_proto::google::{
+    protobuf::Any,
+    rpc::{ErrorInfo, Status as ProtoStatus},
+};
+use http::{HeaderMap, HeaderValue};
+use rand::RngCore;
+use tracing::Span;
+
+use crate::status::{RewriteStatus, RewriteStatusLayer};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Key of [`SkyErrorMetadata::request_id`](grpc_sky_api::error::SkyErrorMetadata::request_id).
+const REQUEST_ID_METADATA_KEY: &str = "requestId";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// ID of a request, either passed by the caller or generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Assigns a [`RequestId`] to every request.
+///
+/// The ID is echoed in the `x-request-id` response header, recorded on the request span and
+/// added to the `ErrorInfo` metadata of error statuses.
+pub type RequestIdLayer = RewriteStatusLayer<RequestIds>;
+
+#[derive(Debug, Clone, Default)]
+pub struct RequestIds;
+
+impl RewriteStatus for RequestIds {
+    type State = String;
+
+    fn on_request<B>(&self, request: &mut http::Request<B>) -> Self::State {
+        let request_id = get_request_id(request.headers()).unwrap_or_else(generate_request_id);
+        request
+            .extensions_mut()
+            .insert(RequestId(request_id.clone()));
+        Span::current().record("request_id", request_id.as_str());
+        request_id
+    }
+
+    fn on_response(&self, request_id: &Self::State, headers: &mut HeaderMap) {
+        // Request IDs are validated or generated, so they are valid header values
+        headers.insert(
+            REQUEST_ID_HEADER,
+            HeaderValue::from_str(request_id).unwrap(),
+        );
+    }
+
+    fn rewrite_status(
+        &self,
+        request_id: &Self::State,
+        _headers: &HeaderMap,
+        status: &mut ProtoStatus,
+    ) -> bool {
+        add_request_id(status, request_id)
+    }
+}
+
+/// Caller provided IDs are only accepted if they are short and printable.
+fn get_request_id(headers: &HeaderMap) -> Option<String> {
+    let request_id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?;
+    if request_id.is_empty()
+        || request_id.len() > MAX_REQUEST_ID_LENGTH
+        || !request_id.chars().all(|c| c.is_ascii_graphic())
+    {
+        return None;
+    }
+    Some(request_id.into())
+}
+
+fn generate_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Returns whether the ID was added to any `ErrorInfo` detail.
+fn add_request_id(status: &mut ProtoStatus, request_id: &str) -> bool {
+    let mut changed = false;
+    for detail in status.details.iter_mut() {
+        let Ok(mut error_info) = Any::unpack_into::<ErrorInfo>(detail.clone()) else {
+            continue;
+        };
+        let previous_request_id = error_info
+            .metadata
+            .insert(REQUEST_ID_METADATA_KEY.into(), request_id.into());
+        if previous_request_id.as_deref() != Some(request_id) {
+            *detail = Any::pack_from(&error_info).unwrap();
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_request_id() {
+        let make_headers = |request_id: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                REQUEST_ID_HEADER,
+                HeaderValue::from_str(request_id).unwrap(),
+            );
+            headers
+        };
+
+        assert_eq!(
+            get_request_id(&make_headers("abc-123")),
+            Some("abc-123".into())
+        );
+        assert_eq!(get_request_id(&make_headers("")), None);
+        assert_eq!(get_request_id(&make_headers("a b")), None);
+        assert_eq!(get_request_id(&make_headers(&"a".repeat(129))), None);
+        assert_eq!(get_request_id(&HeaderMap::new()), None);
+        assert_eq!(generate_request_id().len(), 32);
+    }
+
+    #[test]
+    fn add_request_ids() {
+        let mut status = ProtoStatus {
+            code: tonic::Code::NotFound as i32,
+            message: "missing".into(),
+            details: Vec::new(),
+        };
+        assert!(!add_request_id(&mut status, "abc-123"));
+
+        status
+            .details
+            .push(Any::pack_from(&ErrorInfo::default()).unwrap());
+        assert!(add_request_id(&mut status, "abc-123"));
+        let error_info = Any::unpack_into::<ErrorInfo>(status.details[0].clone()).unwrap();
+        assert_eq!(
+            error_info.metadata.get(REQUEST_ID_METADATA_KEY),
+            Some(&"abc-123".to_string())
+        );
+        assert!(!add_request_id(&mut status, "abc-123"));
+    }
+}
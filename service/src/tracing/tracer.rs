@@ -1,30 +1,108 @@
-use opentelemetry::global;
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{Protocol, WithExportConfig};
 use opentelemetry_sdk::{
-    propagation::TraceContextPropagator, runtime::Tokio, trace::TracerProvider,
+    propagation::TraceContextPropagator, runtime::Tokio, trace::TracerProvider, Resource,
 };
-use std::error::Error;
+use std::{error::Error, time::Duration};
+use tracing::Level;
 use tracing_subscriber::fmt::format::{Format, JsonFields};
-use tracing_subscriber::{prelude::*, EnvFilter};
+use tracing_subscriber::{filter::Targets, prelude::*, EnvFilter};
+
+use crate::{
+    config::{
+        DistributionConfig, LogFormat, OtlpExporterConfig, TraceExporterConfig, TracingConfig,
+    },
+    tracing::access_log::ACCESS_LOG_TARGET,
+};
 
 pub struct Tracer;
 
 impl Tracer {
-    pub fn install_stdout() -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub fn install(
+        distribution: &DistributionConfig,
+        config: &TracingConfig,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         global::set_text_map_propagator(TraceContextPropagator::new());
 
-        use opentelemetry_stdout::SpanExporter;
-        let provider = TracerProvider::builder()
-            .with_batch_exporter(SpanExporter::default(), Tokio)
-            .build();
-        global::set_tracer_provider(provider);
+        let provider_builder = TracerProvider::builder().with_resource(Resource::new([
+            KeyValue::new("service.name", distribution.name.clone()),
+            KeyValue::new("service.version", distribution.version.clone()),
+        ]));
+        let provider = match &config.exporter {
+            TraceExporterConfig::None => None,
+            TraceExporterConfig::Stdout => Some(
+                provider_builder
+                    .with_batch_exporter(opentelemetry_stdout::SpanExporter::default(), Tokio),
+            ),
+            TraceExporterConfig::OtlpGrpc(OtlpExporterConfig {
+                endpoint,
+                timeout_seconds,
+            }) => Some(
+                provider_builder.with_batch_exporter(
+                    opentelemetry_otlp::SpanExporter::builder()
+                        .with_tonic()
+                        .with_endpoint(endpoint)
+                        .with_timeout(Duration::from_secs(*timeout_seconds))
+                        .build()?,
+                    Tokio,
+                ),
+            ),
+            TraceExporterConfig::OtlpHttp(OtlpExporterConfig {
+                endpoint,
+                timeout_seconds,
+            }) => Some(
+                provider_builder.with_batch_exporter(
+                    opentelemetry_otlp::SpanExporter::builder()
+                        .with_http()
+                        .with_protocol(Protocol::HttpBinary)
+                        .with_endpoint(endpoint)
+                        .with_timeout(Duration::from_secs(*timeout_seconds))
+                        .build()?,
+                    Tokio,
+                ),
+            ),
+        }
+        .map(|provider_builder| provider_builder.build());
+
+        // Spans are only bridged to OpenTelemetry if they are exported somewhere
+        let otel_layer = provider.map(|provider| {
+            let tracer = provider.tracer(distribution.name.clone());
+            global::set_tracer_provider(provider);
+            tracing_opentelemetry::layer().with_tracer(tracer)
+        });
 
-        let layer = tracing_subscriber::fmt::layer()
-            .event_format(Format::default().pretty())
-            .fmt_fields(JsonFields::new())
-            .with_filter(EnvFilter::from_default_env());
+        let fmt_layer = match config.log_format {
+            LogFormat::Pretty => tracing_subscriber::fmt::layer()
+                .event_format(Format::default().pretty())
+                .fmt_fields(JsonFields::new())
+                .boxed(),
+            LogFormat::Compact => tracing_subscriber::fmt::layer().compact().boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+        }
+        // Access log events are only written by the access log layer
+        .with_filter(
+            EnvFilter::from_default_env()
+                .add_directive(format!("{}=off", ACCESS_LOG_TARGET).parse()?),
+        );
+        let access_log_layer = config.access_log.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(false)
+                .with_span_list(false)
+                .with_filter(Targets::new().with_target(ACCESS_LOG_TARGET, Level::INFO))
+        });
 
-        tracing_subscriber::registry().with(layer).init();
+        tracing_subscriber::registry()
+            .with(fmt_layer)
+            .with(access_log_layer)
+            .with(otel_layer)
+            .init();
 
         Ok(())
     }
+
+    /// Flushes pending spans.
+    pub fn shutdown() {
+        global::shutdown_tracer_provider();
+    }
 }
@@ -3,32 +3,73 @@ use bomboni_request::{
     parse::RequestParse,
 };
 use prost::Name;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tonic::{Request, Response};
 
 use grpc_sky_api::{
-    dto::SignUpRequestDto,
-    proto::{user_service_server::UserService, GetMeRequest, SignUpRequest, SignUpResponse, User},
+    dto::{
+        BatchGetUsersRequestDto, ChangePasswordRequestDto, ExchangeOidcTokenRequestDto,
+        GetUserByNameRequestDto, GetUserRequestDto, ListUsersRequestDto, SignInRequestDto,
+        SignUpRequestDto, UnlockAccountRequestDto, UpdateProfileRequestDto,
+    },
+    proto::{
+        user_service_server::UserService, BatchGetUsersRequest, BatchGetUsersResponse,
+        ChangePasswordRequest, ExchangeOidcTokenRequest, ExchangeOidcTokenResponse, GetMeRequest,
+        GetUserByNameRequest, GetUserRequest, ListUsersRequest, ListUsersResponse, SignInRequest,
+        SignInResponse, SignUpRequest, SignUpResponse, UnlockAccountRequest, UpdateProfileRequest,
+        User,
+    },
 };
 
 use crate::{
     context::Context,
+    idempotency::{get_idempotency_key, IdempotencyManager},
     user::{
+        change_password_command::{ChangePasswordCommand, ChangePasswordCommandInput},
+        exchange_oidc_token_command::{ExchangeOidcTokenCommand, ExchangeOidcTokenCommandInput},
+        query_manager::{make_user, UserQueryManager},
         repository::UserRepositoryArc,
+        sign_in_command::{SignInCommand, SignInCommandInput},
         sign_up_command::{SignUpCommand, SignUpCommandInput},
+        unlock_account_command::{UnlockAccountCommand, UnlockAccountCommandInput},
+        update_profile_command::{UpdateProfileCommand, UpdateProfileCommandInput},
     },
 };
 
 pub struct UserAdapter {
     user_repository: UserRepositoryArc,
     sign_up_command: SignUpCommand,
+    sign_in_command: SignInCommand,
+    exchange_oidc_token_command: ExchangeOidcTokenCommand,
+    update_profile_command: UpdateProfileCommand,
+    change_password_command: ChangePasswordCommand,
+    unlock_account_command: UnlockAccountCommand,
+    user_query_manager: UserQueryManager,
+    idempotency_manager: Arc<IdempotencyManager>,
 }
 
 impl UserAdapter {
-    pub fn new(user_repository: UserRepositoryArc, sign_up_command: SignUpCommand) -> Self {
+    pub fn new(
+        user_repository: UserRepositoryArc,
+        sign_up_command: SignUpCommand,
+        sign_in_command: SignInCommand,
+        exchange_oidc_token_command: ExchangeOidcTokenCommand,
+        update_profile_command: UpdateProfileCommand,
+        change_password_command: ChangePasswordCommand,
+        unlock_account_command: UnlockAccountCommand,
+        user_query_manager: UserQueryManager,
+        idempotency_manager: Arc<IdempotencyManager>,
+    ) -> Self {
         Self {
             user_repository,
             sign_up_command,
+            sign_in_command,
+            exchange_oidc_token_command,
+            update_profile_command,
+            change_password_command,
+            unlock_account_command,
+            user_query_manager,
+            idempotency_manager,
         }
     }
 }
@@ -42,18 +83,94 @@ impl UserService for UserAdapter {
         &self,
         request: Request<SignUpRequest>,
     ) -> Result<Response<SignUpResponse>, tonic::Status> {
-        let request = SignUpRequestDto::parse(request.into_inner())?;
+        let idempotency_key = get_idempotency_key(request.metadata())?;
+        // Callers aren't signed in, so keys are scoped to their address
+        let idempotency_scope = format!(
+            "sign_up:{}",
+            request
+                .remote_addr()
+                .map(|address| address.ip().to_string())
+                .unwrap_or_default()
+        );
+
+        let request = request.into_inner();
+        let request_dto = SignUpRequestDto::parse(request.clone())?;
+
+        let response = self
+            .idempotency_manager
+            .execute(
+                &idempotency_scope,
+                idempotency_key.as_deref(),
+                &request,
+                || async {
+                    let output = self
+                        .sign_up_command
+                        .execute(SignUpCommandInput {
+                            name: &request_dto.name,
+                            password: &request_dto.password,
+                        })
+                        .await?;
+                    Ok(SignUpResponse {
+                        user_id: output.user_id.to_string(),
+                    })
+                },
+            )
+            .await?;
+
+        Ok(Response::new(response))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn sign_in(
+        &self,
+        request: Request<SignInRequest>,
+    ) -> Result<Response<SignInResponse>, tonic::Status> {
+        let remote_address = request.remote_addr().map(|addr| addr.ip());
+
+        let request = SignInRequestDto::parse(request.into_inner())?;
 
         let output = self
-            .sign_up_command
-            .execute(SignUpCommandInput {
+            .sign_in_command
+            .execute(SignInCommandInput {
                 name: &request.name,
                 password: &request.password,
+                remote_address,
             })
             .await?;
 
-        Ok(Response::new(SignUpResponse {
+        Ok(Response::new(SignInResponse {
+            user_id: output.user_id.to_string(),
+            access_token: output.access_token,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn exchange_oidc_token(
+        &self,
+        request: Request<ExchangeOidcTokenRequest>,
+    ) -> Result<Response<ExchangeOidcTokenResponse>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = ExchangeOidcTokenRequestDto::parse(request.into_inner())?;
+
+        let output = self
+            .exchange_oidc_token_command
+            .execute(
+                &context,
+                ExchangeOidcTokenCommandInput {
+                    id_token: &request.id_token,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(ExchangeOidcTokenResponse {
             user_id: output.user_id.to_string(),
+            access_token: output.access_token,
+            created: output.created,
         }))
     }
 
@@ -73,11 +190,162 @@ impl UserService for UserAdapter {
                 .into());
         };
 
-        Ok(Response::new(User {
-            id: user.id.to_string(),
-            name: user.name,
+        Ok(Response::new(make_user(user)))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn get_user(
+        &self,
+        request: Request<GetUserRequest>,
+    ) -> Result<Response<User>, tonic::Status> {
+        let request = GetUserRequestDto::parse(request.into_inner())?;
+
+        let Some(user) = self.user_query_manager.get(request.name).await? else {
+            return Err(CommonError::ResourceNotFound
+                .wrap_request(GetUserRequest::NAME)
+                .into());
+        };
+
+        Ok(Response::new(user))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn get_user_by_name(
+        &self,
+        request: Request<GetUserByNameRequest>,
+    ) -> Result<Response<User>, tonic::Status> {
+        let request = GetUserByNameRequestDto::parse(request.into_inner())?;
+
+        let Some(user) = self.user_query_manager.get_by_name(&request.name).await? else {
+            return Err(CommonError::ResourceNotFound
+                .wrap_request(GetUserByNameRequest::NAME)
+                .into());
+        };
+
+        Ok(Response::new(user))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn list_users(
+        &self,
+        request: Request<ListUsersRequest>,
+    ) -> Result<Response<ListUsersResponse>, tonic::Status> {
+        let request = ListUsersRequestDto::parse_list_query(
+            request.into_inner(),
+            self.user_query_manager.list_query_builder(),
+        )?;
+
+        let output = self
+            .user_query_manager
+            .query_list(request.query, request.name_prefix.as_deref())
+            .await?;
+
+        Ok(Response::new(ListUsersResponse {
+            users: output.items,
+            next_page_token: output.next_page_token,
+            total_size: output.total_size,
         }))
     }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn batch_get_users(
+        &self,
+        request: Request<BatchGetUsersRequest>,
+    ) -> Result<Response<BatchGetUsersResponse>, tonic::Status> {
+        let request = BatchGetUsersRequestDto::parse(request.into_inner())?;
+
+        let results = self
+            .user_query_manager
+            // Profiles are public, so none are forbidden
+            .batch_get(&request.names, request.allow_missing, |_| true)
+            .await?;
+
+        Ok(Response::new(BatchGetUsersResponse { results }))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<User>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = UpdateProfileRequestDto::parse(request.into_inner())?;
+
+        let output = self
+            .update_profile_command
+            .execute(
+                &context,
+                UpdateProfileCommandInput {
+                    user_id: request.user_id,
+                    display_name: request.display_name.as_deref(),
+                    bio: request.bio.as_deref(),
+                    avatar_url: request.avatar_url.as_deref(),
+                    location: request.location.as_deref(),
+                    website: request.website.as_deref(),
+                },
+            )
+            .await?;
+
+        Ok(Response::new(make_user(output.user)))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<()>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = ChangePasswordRequestDto::parse(request.into_inner())?;
+
+        self.change_password_command
+            .execute(
+                &context,
+                ChangePasswordCommandInput {
+                    current_password: request.current_password.as_deref(),
+                    new_password: &request.new_password,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(()))
+    }
+
+    #[tracing::instrument(skip(self, request), fields(
+        remote_addr = request.remote_addr().map(|addr| addr.to_string()),
+    ), err(Debug))]
+    async fn unlock_account(
+        &self,
+        request: Request<UnlockAccountRequest>,
+    ) -> Result<Response<()>, tonic::Status> {
+        let context = Context::from_request(&request);
+
+        let request = UnlockAccountRequestDto::parse(request.into_inner())?;
+
+        self.unlock_account_command
+            .execute(
+                &context,
+                UnlockAccountCommandInput {
+                    name: &request.name,
+                },
+            )
+            .await?;
+
+        Ok(Response::new(()))
+    }
 }
 
 impl Debug for UserAdapter {
@@ -0,0 +1,185 @@
+use bomboni_common::date_time::UtcDateTime;
+use bomboni_request::error::CommonError;
+use prost::Name;
+use std::sync::Arc;
+
+use grpc_sky_api::{
+    error::UserError,
+    proto::{user_error::UserErrorReason, ChangePasswordRequest},
+};
+
+use crate::{
+    context::Context,
+    error::AppResult,
+    user::{
+        password::{hash_password, verify_password, PasswordPolicy},
+        repository::UserRepositoryArc,
+    },
+};
+
+pub struct ChangePasswordCommand {
+    user_repository: UserRepositoryArc,
+    password_policy: Arc<PasswordPolicy>,
+}
+
+pub struct ChangePasswordCommandInput<'a> {
+    /// Not needed to set the first password of users without one.
+    pub current_password: Option<&'a str>,
+    pub new_password: &'a str,
+}
+
+impl ChangePasswordCommand {
+    pub fn new(user_repository: UserRepositoryArc, password_policy: Arc<PasswordPolicy>) -> Self {
+        Self {
+            user_repository,
+            password_policy,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: ChangePasswordCommandInput<'_>,
+    ) -> AppResult<()> {
+        let user_id = context.authenticate()?;
+
+        let Some(user) = self.user_repository.select(user_id).await? else {
+            return Err(CommonError::Unauthorized.into());
+        };
+
+        // Users who signed up with an identity provider set their first password
+        if let Some(password_hash) = user.password_hash.as_deref() {
+            if !input
+                .current_password
+                .is_some_and(|current_password| verify_password(current_password, password_hash))
+            {
+                return Err(UserError::new(UserErrorReason::IncorrectCredentials).into());
+            }
+        }
+
+        self.password_policy.validate(
+            input.new_password,
+            &user.name,
+            ChangePasswordRequest::NAME,
+            "new_password",
+        )?;
+
+        let password_hash = hash_password(input.new_password)?;
+        self.user_repository
+            .update_password_hash(user_id, &password_hash, UtcDateTime::now())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bomboni_common::id::Id;
+
+    use grpc_sky_api::user_name::canonicalize_user_name;
+
+    use crate::{
+        auth::permission::Permission,
+        config::PasswordPolicyConfig,
+        user::repository::{memory::UserMemoryRepository, UserInsertRecord, UserRepository},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn change_password() {
+        let user_repository = Arc::new(UserMemoryRepository::default());
+        let command = ChangePasswordCommand::new(
+            user_repository.clone(),
+            Arc::new(
+                PasswordPolicy::new(PasswordPolicyConfig {
+                    min_length: 8,
+                    require_lowercase: false,
+                    require_uppercase: false,
+                    require_digit: false,
+                    require_symbol: false,
+                    reject_similar_to_name: false,
+                    breached_passwords_path: None,
+                })
+                .unwrap(),
+            ),
+        );
+        let user_id = Id::generate();
+        user_repository
+            .insert(UserInsertRecord {
+                id: user_id,
+                name: "tester",
+                canonical_name: &canonicalize_user_name("tester"),
+                password_hash: None,
+                create_time: UtcDateTime::now(),
+            })
+            .await
+            .unwrap();
+        let context = Context::from_user(user_id);
+
+        // API keys can't set passwords, not even the first one
+        assert!(command
+            .execute(
+                &Context::from_api_key(user_id, vec![Permission::ApiKeysManage]),
+                ChangePasswordCommandInput {
+                    current_password: None,
+                    new_password: "first password",
+                },
+            )
+            .await
+            .is_err());
+        assert_eq!(
+            user_repository
+                .select(user_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .password_hash,
+            None
+        );
+
+        // Without a password, the first one is set without the current one
+        command
+            .execute(
+                &context,
+                ChangePasswordCommandInput {
+                    current_password: None,
+                    new_password: "first password",
+                },
+            )
+            .await
+            .unwrap();
+        let password_hash = user_repository
+            .select(user_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .password_hash
+            .unwrap();
+        assert!(verify_password("first password", &password_hash));
+
+        for current_password in [None, Some("wrong password")] {
+            assert!(command
+                .execute(
+                    &context,
+                    ChangePasswordCommandInput {
+                        current_password,
+                        new_password: "second password",
+                    },
+                )
+                .await
+                .is_err());
+        }
+        command
+            .execute(
+                &context,
+                ChangePasswordCommandInput {
+                    current_password: Some("first password"),
+                    new_password: "second password",
+                },
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::error::{CommonError, RequestError};
+use rand::Rng;
+use std::sync::Arc;
+use tonic::{Code, Status};
+
+use grpc_sky_api::{
+    dto::is_valid_user_name, error::AuthError, proto::auth_error::AuthErrorReason,
+    user_name::canonicalize_user_name,
+};
+
+use crate::{
+    auth::{
+        oidc::{OidcIdentity, OidcVerifier},
+        token::TokenManager,
+    },
+    context::Context,
+    error::{make_status, AppResult},
+    metrics::SIGN_UPS_TOTAL,
+    user::repository::{
+        UserIdentityInsertRecord, UserInsertRecord, UserRecord, UserRepositoryArc,
+        UserWithIdentityInsertResult,
+    },
+};
+
+const FALLBACK_USER_NAME: &str = "user";
+const MAX_USER_NAME_BASE_LENGTH: usize = 12;
+const MAX_USER_NAME_ATTEMPTS: usize = 5;
+
+pub struct ExchangeOidcTokenCommand {
+    user_repository: UserRepositoryArc,
+    oidc_verifier: Option<Arc<OidcVerifier>>,
+    token_manager: Arc<TokenManager>,
+}
+
+pub struct ExchangeOidcTokenCommandInput<'a> {
+    pub id_token: &'a str,
+}
+
+pub struct ExchangeOidcTokenCommandOutput {
+    pub user_id: Id,
+    pub access_token: String,
+    pub created: bool,
+}
+
+impl ExchangeOidcTokenCommand {
+    pub fn new(
+        user_repository: UserRepositoryArc,
+        oidc_verifier: Option<Arc<OidcVerifier>>,
+        token_manager: Arc<TokenManager>,
+    ) -> Self {
+        Self {
+            user_repository,
+            oidc_verifier,
+            token_manager,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: ExchangeOidcTokenCommandInput<'_>,
+    ) -> AppResult<ExchangeOidcTokenCommandOutput> {
+        let Some(oidc_verifier) = self.oidc_verifier.as_ref() else {
+            return Err(Status::unimplemented("OIDC login is not configured").into());
+        };
+        let Some(identity) = oidc_verifier.verify(input.id_token).await? else {
+            return Err(make_status(
+                Code::Unauthenticated,
+                RequestError::generic(AuthError::new(AuthErrorReason::InvalidIdToken)),
+            )
+            .into());
+        };
+
+        let (user, created) = self.resolve_user(context, &identity).await?;
+
+        Ok(ExchangeOidcTokenCommandOutput {
+            user_id: user.id,
+            access_token: self.token_manager.issue(user.id, user.role)?,
+            created,
+        })
+    }
+
+    /// Returns the user linked to the identity, linking or creating one if there is none.
+    ///
+    /// Also returns whether the user was created.
+    async fn resolve_user(
+        &self,
+        context: &Context,
+        identity: &OidcIdentity,
+    ) -> AppResult<(UserRecord, bool)> {
+        if let Some(user) = self
+            .user_repository
+            .select_by_identity(&identity.issuer, &identity.subject)
+            .await?
+        {
+            return Ok((user, false));
+        }
+
+        if context.principal().is_none() {
+            return match self.create_user(identity).await? {
+                Some(user) => Ok((user, true)),
+                // A concurrent request created the user first
+                None => Ok((self.select_linked_user(identity).await?, false)),
+            };
+        }
+        // Only sessions link identities, as a linked identity signs in to the whole account
+        let user_id = context.authenticate()?;
+
+        let user = self
+            .user_repository
+            .select(user_id)
+            .await?
+            .ok_or(CommonError::ResourceNotFound)?;
+        // Identities can only be linked once, also with concurrent requests
+        if !self
+            .user_repository
+            .insert_identity(UserIdentityInsertRecord {
+                user_id: user.id,
+                issuer: &identity.issuer,
+                subject: &identity.subject,
+                create_time: UtcDateTime::now(),
+            })
+            .await?
+            && self.select_linked_user(identity).await?.id != user.id
+        {
+            return Err(CommonError::AlreadyExists.into());
+        }
+        Ok((user, false))
+    }
+
+    async fn select_linked_user(&self, identity: &OidcIdentity) -> AppResult<UserRecord> {
+        let user = self
+            .user_repository
+            .select_by_identity(&identity.issuer, &identity.subject)
+            .await?
+            .ok_or(CommonError::ResourceNotFound)?;
+        Ok(user)
+    }
+
+    /// Creates a user linked to the identity, named after the preferred user name if available.
+    ///
+    /// The user has no password until they set one. Returns `None` if the identity was linked
+    /// to another user in the meantime.
+    async fn create_user(&self, identity: &OidcIdentity) -> AppResult<Option<UserRecord>> {
+        let base_name = make_base_user_name(identity.preferred_username.as_deref());
+        let user_id = Id::generate();
+
+        for attempt in 0..MAX_USER_NAME_ATTEMPTS {
+            let name = if attempt == 0 && base_name != FALLBACK_USER_NAME {
+                base_name.clone()
+            } else {
+                format!("{}{}", base_name, rand::thread_rng().gen_range(1000..10000))
+            };
+            let canonical_name = canonicalize_user_name(&name);
+            let create_time = UtcDateTime::now();
+            let result = self
+                .user_repository
+                .insert_with_identity(
+                    UserInsertRecord {
+                        id: user_id,
+                        name: &name,
+                        canonical_name: &canonical_name,
+                        password_hash: None,
+                        create_time,
+                    },
+                    UserIdentityInsertRecord {
+                        user_id,
+                        issuer: &identity.issuer,
+                        subject: &identity.subject,
+                        create_time,
+                    },
+                )
+                .await?;
+            match result {
+                UserWithIdentityInsertResult::Inserted => {
+                    ::metrics::counter!(SIGN_UPS_TOTAL, "method" => "oidc").increment(1);
+                    let user = self
+                        .user_repository
+                        .select(user_id)
+                        .await?
+                        .ok_or(CommonError::ResourceNotFound)?;
+                    return Ok(Some(user));
+                }
+                UserWithIdentityInsertResult::IdentityTaken => return Ok(None),
+                UserWithIdentityInsertResult::NameTaken => {}
+            }
+        }
+
+        Err(CommonError::AlreadyExists.into())
+    }
+}
+
+/// Derives a valid user name from the provider's preferred user name, leaving room for a suffix.
+fn make_base_user_name(preferred_username: Option<&str>) -> String {
+    let Some(preferred_username) = preferred_username else {
+        return FALLBACK_USER_NAME.into();
+    };
+    let local_part = preferred_username
+        .split_once('@')
+        .map_or(preferred_username, |(local_part, _)| local_part);
+    let name: String = local_part
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .skip_while(|c| !c.is_alphabetic())
+        .take(MAX_USER_NAME_BASE_LENGTH)
+        .collect();
+    if is_valid_user_name(&name) {
+        name
+    } else {
+        FALLBACK_USER_NAME.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        auth::{permission::Permission, token::make_test_auth_config},
+        user::repository::{memory::UserMemoryRepository, UserRepository},
+    };
+
+    use super::*;
+
+    fn make_identity(subject: &str) -> OidcIdentity {
+        OidcIdentity {
+            issuer: "https://accounts.example.com".into(),
+            subject: subject.into(),
+            preferred_username: Some("tester@example.com".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_users() {
+        let user_repository = Arc::new(UserMemoryRepository::default());
+        let command = ExchangeOidcTokenCommand::new(
+            user_repository.clone(),
+            None,
+            Arc::new(TokenManager::new(&make_test_auth_config())),
+        );
+
+        let (user, created) = command
+            .resolve_user(&Context::anonymous(), &make_identity("1"))
+            .await
+            .unwrap();
+        assert!(created);
+        assert_eq!(user.name, "tester");
+        assert_eq!(user.password_hash, None);
+        let (linked_user, created) = command
+            .resolve_user(&Context::anonymous(), &make_identity("1"))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(linked_user.id, user.id);
+
+        // Signed in users link identities, which can't be linked to anyone else
+        let (other_user, _) = command
+            .resolve_user(&Context::anonymous(), &make_identity("2"))
+            .await
+            .unwrap();
+        assert_ne!(other_user.name, user.name);
+        let (linked_user, created) = command
+            .resolve_user(&Context::from_user(user.id), &make_identity("3"))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(linked_user.id, user.id);
+        assert_eq!(
+            user_repository
+                .select_by_identity("https://accounts.example.com", "3")
+                .await
+                .unwrap()
+                .map(|user| user.id),
+            Some(user.id)
+        );
+        assert!(command
+            .resolve_user(&Context::from_user(other_user.id), &make_identity("3"))
+            .await
+            .is_err());
+
+        // API keys can't link identities
+        assert!(command
+            .resolve_user(
+                &Context::from_api_key(other_user.id, vec![Permission::ApiKeysManage]),
+                &make_identity("4"),
+            )
+            .await
+            .is_err());
+        assert!(user_repository
+            .select_by_identity("https://accounts.example.com", "4")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn base_user_names() {
+        assert_eq!(make_base_user_name(Some("tester")), "tester");
+        assert_eq!(
+            make_base_user_name(Some("jane.doe@example.com")),
+            "jane_doe"
+        );
+        assert_eq!(make_base_user_name(Some("1st.tester")), "st_tester");
+        assert_eq!(
+            make_base_user_name(Some("a_very_long_user_name")),
+            "a_very_long_"
+        );
+        assert_eq!(make_base_user_name(Some("ab")), FALLBACK_USER_NAME);
+        assert_eq!(make_base_user_name(None), FALLBACK_USER_NAME);
+    }
+}
@@ -1,3 +1,11 @@
 pub mod adapter;
+pub mod change_password_command;
+pub mod exchange_oidc_token_command;
+pub mod password;
+pub mod query_manager;
 pub mod repository;
+pub mod sign_in_command;
+pub mod sign_in_throttle;
 pub mod sign_up_command;
+pub mod unlock_account_command;
+pub mod update_profile_command;
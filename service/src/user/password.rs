@@ -0,0 +1,239 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use bomboni_request::error::{PathError, PathErrorStep, RequestError};
+use std::{collections::BTreeSet, fs, io, sync::LazyLock};
+
+use grpc_sky_api::{
+    error::UserError, proto::user_error::UserErrorReason, user_name::canonicalize_user_name,
+};
+
+use crate::{config::PasswordPolicyConfig, error::AppResult};
+
+pub struct PasswordPolicy {
+    config: PasswordPolicyConfig,
+    breached_passwords: BTreeSet<String>,
+}
+
+impl PasswordPolicy {
+    pub fn new(config: PasswordPolicyConfig) -> AppResult<Self> {
+        let breached_passwords = if let Some(path) = config.breached_passwords_path.as_ref() {
+            fs::read_to_string(path)
+                .map_err(|err| {
+                    io::Error::new(
+                        err.kind(),
+                        format!(
+                            "failed to read breached passwords from `{}`: {}",
+                            path.display(),
+                            err
+                        ),
+                    )
+                })?
+                .lines()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|line| !line.is_empty())
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+        Ok(Self {
+            config,
+            breached_passwords,
+        })
+    }
+
+    /// Returns an error for every rule the password violates.
+    pub fn check(&self, password: &str, user_name: &str) -> Vec<UserError> {
+        let mut errors = Vec::new();
+
+        if password.chars().count() < self.config.min_length {
+            errors.push(
+                UserError::new(UserErrorReason::PasswordTooShort)
+                    .with_min_length(self.config.min_length),
+            );
+        }
+
+        for (required, predicate, reason) in [
+            (
+                self.config.require_lowercase,
+                char::is_lowercase as fn(char) -> bool,
+                UserErrorReason::PasswordMissingLowercase,
+            ),
+            (
+                self.config.require_uppercase,
+                char::is_uppercase,
+                UserErrorReason::PasswordMissingUppercase,
+            ),
+            (
+                self.config.require_digit,
+                |c: char| c.is_numeric(),
+                UserErrorReason::PasswordMissingDigit,
+            ),
+            (
+                self.config.require_symbol,
+                |c: char| !c.is_alphanumeric() && !c.is_whitespace(),
+                UserErrorReason::PasswordMissingSymbol,
+            ),
+        ] {
+            if required && !password.chars().any(predicate) {
+                errors.push(UserError::new(reason));
+            }
+        }
+
+        if self.breached_passwords.contains(&password.to_lowercase()) {
+            errors.push(UserError::new(UserErrorReason::PasswordBreached));
+        }
+
+        if self.config.reject_similar_to_name && is_similar_to_name(password, user_name) {
+            errors.push(
+                UserError::new(UserErrorReason::PasswordSimilarToName).with_user_name(user_name),
+            );
+        }
+
+        errors
+    }
+
+    /// Checks the password and reports violations on the given request field.
+    pub fn validate(
+        &self,
+        password: &str,
+        user_name: &str,
+        request_name: &str,
+        field_name: &str,
+    ) -> AppResult<()> {
+        let errors = self.check(password, user_name);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(RequestError::BadRequest {
+            name: request_name.into(),
+            violations: errors
+                .into_iter()
+                .map(|error| PathError {
+                    path: vec![PathErrorStep::Field(field_name.into())],
+                    error: Box::new(error),
+                })
+                .collect(),
+        }
+        .into())
+    }
+}
+
+/// A password is similar to a name if either contains the other, compared in canonical form.
+fn is_similar_to_name(password: &str, user_name: &str) -> bool {
+    let password = canonicalize_user_name(password);
+    let user_name = canonicalize_user_name(user_name);
+    if user_name.is_empty() {
+        return false;
+    }
+    let reversed_user_name: String = user_name.chars().rev().collect();
+    password.contains(&user_name)
+        || password.contains(&reversed_user_name)
+        || user_name.contains(&password)
+}
+
+pub fn hash_password(password: &str) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|_| UserError::new(UserErrorReason::InvalidPassword))?
+        .to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let Ok(password_hash) = PasswordHash::new(password_hash) else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &password_hash)
+        .is_ok()
+}
+
+/// Hashed with the same parameters as real passwords, so that verifying against it takes as long.
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("dummy password").unwrap());
+
+/// Verifies a password against a hash that may be missing, e.g. for unknown users.
+///
+/// Without a hash, the password is verified against a dummy hash and rejected,
+/// so that missing users and passwords can't be told apart by timing.
+pub fn verify_password_or_dummy(password: &str, password_hash: Option<&str>) -> bool {
+    match password_hash {
+        Some(password_hash) => verify_password(password, password_hash),
+        None => {
+            verify_password(password, &DUMMY_PASSWORD_HASH);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_config() -> PasswordPolicyConfig {
+        PasswordPolicyConfig {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: false,
+            require_digit: true,
+            require_symbol: false,
+            reject_similar_to_name: true,
+            breached_passwords_path: None,
+        }
+    }
+
+    #[test]
+    fn check_policy() {
+        let policy = PasswordPolicy {
+            config: make_config(),
+            breached_passwords: ["password1".to_string()].into_iter().collect(),
+        };
+
+        assert!(policy.check("abc123456", "tester").is_empty());
+
+        let reasons = |password: &str| -> Vec<_> {
+            policy
+                .check(password, "tester")
+                .into_iter()
+                .map(|error| error.reason)
+                .collect()
+        };
+        assert_eq!(
+            reasons("ABC"),
+            vec![
+                UserErrorReason::PasswordTooShort,
+                UserErrorReason::PasswordMissingLowercase,
+                UserErrorReason::PasswordMissingDigit,
+            ]
+        );
+        assert_eq!(reasons("Password1"), vec![UserErrorReason::PasswordBreached]);
+        assert_eq!(
+            reasons("my_tester_1"),
+            vec![UserErrorReason::PasswordSimilarToName]
+        );
+    }
+
+    #[test]
+    fn missing_breached_passwords() {
+        let err = PasswordPolicy::new(PasswordPolicyConfig {
+            breached_passwords_path: Some("missing/breached_passwords.txt".into()),
+            ..make_config()
+        })
+        .err()
+        .unwrap();
+        assert!(err
+            .to_string()
+            .contains("failed to read breached passwords from `missing/breached_passwords.txt`"));
+    }
+
+    #[test]
+    fn verify_missing_password_hash() {
+        let password_hash = hash_password("abc123456").unwrap();
+        assert!(verify_password_or_dummy("abc123456", Some(&password_hash)));
+        assert!(!verify_password_or_dummy("other", Some(&password_hash)));
+        assert!(!verify_password_or_dummy("abc123456", None));
+        assert!(!verify_password_or_dummy("dummy password", None));
+    }
+}
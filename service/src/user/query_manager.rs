@@ -0,0 +1,273 @@
+use bomboni_common::id::Id;
+use bomboni_request::{
+    error::CommonError,
+    ordering::{OrderingDirection, OrderingTerm},
+    query::{
+        list::{Aes256ListQueryBuilder, ListQuery, ListQueryConfig},
+        page_token::aes256::Aes256PageTokenBuilder,
+    },
+    schema::FunctionSchemaMap,
+};
+use grpc_sky_api::{
+    dto::{make_user_name, UserDto},
+    proto::{user_result, BatchGetUsersRequest, User, UserResult},
+    user_name::canonicalize_user_name,
+};
+use prost::Name;
+
+use crate::{
+    batch::BatchGet,
+    error::AppResult,
+    user::repository::{UserRecord, UserRepositoryArc},
+};
+
+pub struct UserQueryManager {
+    user_repository: UserRepositoryArc,
+    list_query_builder: Aes256ListQueryBuilder,
+}
+
+pub struct UserListResult {
+    pub items: Vec<User>,
+    pub next_page_token: Option<String>,
+    pub total_size: i64,
+}
+
+impl UserQueryManager {
+    pub fn new(user_repository: UserRepositoryArc) -> Self {
+        Self {
+            user_repository,
+            list_query_builder: Aes256ListQueryBuilder::new(
+                UserDto::get_schema(),
+                FunctionSchemaMap::new(),
+                ListQueryConfig {
+                    max_page_size: Some(20),
+                    default_page_size: 10,
+                    primary_ordering_term: Some(OrderingTerm {
+                        name: "id".into(),
+                        direction: OrderingDirection::Descending,
+                    }),
+                    max_filter_length: Some(100),
+                    max_ordering_length: Some(100),
+                },
+                Aes256PageTokenBuilder::new(true),
+            ),
+        }
+    }
+
+    pub async fn get(&self, id: Id) -> AppResult<Option<User>> {
+        Ok(self.user_repository.select(id).await?.map(make_user))
+    }
+
+    pub async fn get_by_name(&self, name: &str) -> AppResult<Option<User>> {
+        Ok(self
+            .user_repository
+            .select_by_canonical_name(&canonicalize_user_name(name))
+            .await?
+            .map(make_user))
+    }
+
+    pub async fn query_list(
+        &self,
+        query: ListQuery,
+        name_prefix: Option<&str>,
+    ) -> AppResult<UserListResult> {
+        let canonical_name_prefix = name_prefix.map(canonicalize_user_name);
+        let user_list = self
+            .user_repository
+            .select_list(&query, canonical_name_prefix.as_deref())
+            .await?;
+
+        let next_page_token = if let Some(next_item) = &user_list.next_item {
+            Some(
+                self.list_query_builder
+                    .build_next_page_token(&query, next_item)
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+
+        Ok(UserListResult {
+            items: user_list.items.into_iter().map(make_user).collect(),
+            next_page_token,
+            total_size: user_list.total_size,
+        })
+    }
+
+    /// Returns results in the same order as `ids`, where users rejected by `can_read` are
+    /// reported as missing.
+    /// Unless `allow_missing` is set, any missing user fails the whole request.
+    pub async fn batch_get(
+        &self,
+        ids: &[Id],
+        allow_missing: bool,
+        can_read: impl Fn(&UserRecord) -> bool,
+    ) -> AppResult<Vec<UserResult>> {
+        let records = self.user_repository.select_many(ids).await?;
+        let results = BatchGet {
+            request_name: BatchGetUsersRequest::NAME,
+            ids,
+            allow_missing,
+        }
+        .collect(
+            records.into_iter().map(|record| (record.id, record)),
+            can_read,
+            |_| CommonError::ResourceNotFound,
+        )?;
+
+        Ok(ids
+            .iter()
+            .zip(results)
+            .map(|(id, result)| UserResult {
+                name: make_user_name(*id),
+                result: Some(match result {
+                    Ok(record) => user_result::Result::User(make_user(record)),
+                    Err(status) => user_result::Result::Error(status),
+                }),
+            })
+            .collect())
+    }
+
+    pub fn list_query_builder(&self) -> &Aes256ListQueryBuilder {
+        &self.list_query_builder
+    }
+}
+
+pub fn make_user(record: UserRecord) -> User {
+    User {
+        id: record.id.to_string(),
+        name: record.name,
+        display_name: record.display_name.unwrap_or_default(),
+        bio: record.bio.unwrap_or_default(),
+        avatar_url: record.avatar_url.unwrap_or_default(),
+        location: record.location.unwrap_or_default(),
+        website: record.website.unwrap_or_default(),
+        create_time: Some(record.create_time.into()),
+        update_time: Some(record.update_time.into()),
+        role: record.role as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bomboni_common::date_time::UtcDateTime;
+    use std::sync::Arc;
+    use tonic::{Code, Status};
+
+    use grpc_sky_api::{dto::ListUsersRequestDto, proto::ListUsersRequest};
+
+    use crate::user::repository::{memory::UserMemoryRepository, UserInsertRecord, UserRepository};
+
+    use super::*;
+
+    /// Query manager of a memory repository with users of the names, and their IDs.
+    async fn make_query_manager(names: &[&str]) -> (UserQueryManager, Vec<Id>) {
+        let user_repository = Arc::new(UserMemoryRepository::default());
+        let mut ids = Vec::new();
+        for name in names {
+            let id = Id::generate();
+            user_repository
+                .insert(UserInsertRecord {
+                    id,
+                    name,
+                    canonical_name: &canonicalize_user_name(name),
+                    password_hash: None,
+                    create_time: UtcDateTime::now(),
+                })
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        (UserQueryManager::new(user_repository), ids)
+    }
+
+    #[tokio::test]
+    async fn get_users() {
+        let (query_manager, ids) = make_query_manager(&["Tester", "other"]).await;
+
+        let user = query_manager.get(ids[0]).await.unwrap().unwrap();
+        assert_eq!(user.name, "Tester");
+        assert_eq!(query_manager.get(Id::generate()).await.unwrap(), None);
+
+        // Names are looked up by their canonical form
+        let user = query_manager.get_by_name("TESTER").await.unwrap().unwrap();
+        assert_eq!(user.id, ids[0].to_string());
+        assert_eq!(query_manager.get_by_name("missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn list_users_by_name_prefix() {
+        let (query_manager, _) =
+            make_query_manager(&["tester", "Testing", "TEST_two", "other"]).await;
+
+        let request = ListUsersRequestDto::parse_list_query(
+            ListUsersRequest {
+                page_size: Some(2),
+                name_prefix: Some("Test".into()),
+                ..Default::default()
+            },
+            query_manager.list_query_builder(),
+        )
+        .unwrap();
+        let result = query_manager
+            .query_list(request.query, request.name_prefix.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(result.items.len(), 2);
+        assert!(result
+            .items
+            .iter()
+            .all(|user| user.name.to_lowercase().starts_with("test")));
+        assert!(result.next_page_token.is_some());
+        assert_eq!(result.total_size, 3);
+
+        let request = ListUsersRequestDto::parse_list_query(
+            ListUsersRequest::default(),
+            query_manager.list_query_builder(),
+        )
+        .unwrap();
+        let result = query_manager
+            .query_list(request.query, request.name_prefix.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(result.items.len(), 4);
+        assert_eq!(result.next_page_token, None);
+    }
+
+    #[tokio::test]
+    async fn batch_get_users() {
+        let (query_manager, ids) = make_query_manager(&["tester", "other"]).await;
+
+        let names = |results: Vec<UserResult>| -> Vec<Option<String>> {
+            results
+                .into_iter()
+                .map(|result| match result.result.unwrap() {
+                    user_result::Result::User(user) => Some(user.name),
+                    user_result::Result::Error(_) => None,
+                })
+                .collect()
+        };
+
+        let request_ids = [ids[1], Id::generate(), ids[0]];
+        let results = query_manager
+            .batch_get(&request_ids, true, |_| true)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(results),
+            vec![Some("other".into()), None, Some("tester".into())]
+        );
+
+        let results = query_manager
+            .batch_get(&request_ids, true, |record| record.name != "other")
+            .await
+            .unwrap();
+        assert_eq!(names(results), vec![None, None, Some("tester".into())]);
+
+        let err = query_manager
+            .batch_get(&[ids[0], ids[1]], false, |record| record.name != "other")
+            .await
+            .unwrap_err();
+        assert_eq!(Status::from(err).code(), Code::NotFound);
+    }
+}
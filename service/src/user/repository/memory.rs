@@ -0,0 +1,191 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::query::list::ListQuery;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use grpc_sky_api::proto::UserRole;
+
+use crate::{
+    error::AppResult,
+    user::repository::{
+        UserIdentityInsertRecord, UserInsertRecord, UserProfileUpdateRecord, UserRecord,
+        UserRecordList, UserRepository, UserWithIdentityInsertResult,
+    },
+};
+
+/// Keeps users in memory, for tests of commands and query managers.
+#[derive(Default)]
+pub struct UserMemoryRepository {
+    users: Mutex<BTreeMap<Id, UserRecord>>,
+    canonical_names: Mutex<HashMap<String, Id>>,
+    identities: Mutex<HashMap<(String, String), Id>>,
+}
+
+#[tonic::async_trait]
+impl UserRepository for UserMemoryRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn select(&self, id: Id) -> AppResult<Option<UserRecord>> {
+        Ok(self.users.lock().unwrap().get(&id).cloned())
+    }
+
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<UserRecord>> {
+        let users = self.users.lock().unwrap();
+        Ok(ids.iter().filter_map(|id| users.get(id).cloned()).collect())
+    }
+
+    async fn select_by_canonical_name(
+        &self,
+        canonical_name: &str,
+    ) -> AppResult<Option<UserRecord>> {
+        let id = self
+            .canonical_names
+            .lock()
+            .unwrap()
+            .get(canonical_name)
+            .copied();
+        Ok(id.and_then(|id| self.users.lock().unwrap().get(&id).cloned()))
+    }
+
+    async fn select_by_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> AppResult<Option<UserRecord>> {
+        let id = self
+            .identities
+            .lock()
+            .unwrap()
+            .get(&(issuer.to_string(), subject.to_string()))
+            .copied();
+        Ok(id.and_then(|id| self.users.lock().unwrap().get(&id).cloned()))
+    }
+
+    /// Lists the first page by descending ID, since filters and page tokens are only applied
+    /// by the MySQL repository.
+    async fn select_list(
+        &self,
+        query: &ListQuery,
+        canonical_name_prefix: Option<&str>,
+    ) -> AppResult<UserRecordList> {
+        let mut ids: Vec<Id> = self
+            .canonical_names
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(canonical_name, _)| {
+                canonical_name.starts_with(canonical_name_prefix.unwrap_or_default())
+            })
+            .map(|(_, id)| *id)
+            .collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+
+        let users = self.users.lock().unwrap();
+        let mut items: Vec<UserRecord> = ids
+            .iter()
+            .take(query.page_size as usize + 1)
+            .filter_map(|id| users.get(id).cloned())
+            .collect();
+        let next_item = if items.len() > query.page_size as usize {
+            items.pop()
+        } else {
+            None
+        };
+        Ok(UserRecordList {
+            items,
+            next_item,
+            total_size: ids.len() as i64,
+        })
+    }
+
+    async fn insert(&self, record: UserInsertRecord<'_>) -> AppResult<bool> {
+        let mut canonical_names = self.canonical_names.lock().unwrap();
+        if canonical_names.contains_key(record.canonical_name) {
+            return Ok(false);
+        }
+        canonical_names.insert(record.canonical_name.into(), record.id);
+        self.users.lock().unwrap().insert(
+            record.id,
+            UserRecord {
+                id: record.id,
+                name: record.name.into(),
+                password_hash: record.password_hash,
+                display_name: None,
+                bio: None,
+                avatar_url: None,
+                location: None,
+                website: None,
+                create_time: record.create_time,
+                update_time: record.create_time,
+                role: UserRole::User,
+            },
+        );
+        Ok(true)
+    }
+
+    async fn insert_identity(&self, record: UserIdentityInsertRecord<'_>) -> AppResult<bool> {
+        let mut identities = self.identities.lock().unwrap();
+        let key = (record.issuer.to_string(), record.subject.to_string());
+        if identities.contains_key(&key) {
+            return Ok(false);
+        }
+        identities.insert(key, record.user_id);
+        Ok(true)
+    }
+
+    async fn insert_with_identity(
+        &self,
+        record: UserInsertRecord<'_>,
+        identity_record: UserIdentityInsertRecord<'_>,
+    ) -> AppResult<UserWithIdentityInsertResult> {
+        let key = (
+            identity_record.issuer.to_string(),
+            identity_record.subject.to_string(),
+        );
+        if self.identities.lock().unwrap().contains_key(&key) {
+            return Ok(UserWithIdentityInsertResult::IdentityTaken);
+        }
+        if !self.insert(record).await? {
+            return Ok(UserWithIdentityInsertResult::NameTaken);
+        }
+        self.insert_identity(identity_record).await?;
+        Ok(UserWithIdentityInsertResult::Inserted)
+    }
+
+    async fn update_profile(&self, record: UserProfileUpdateRecord<'_>) -> AppResult<()> {
+        let mut users = self.users.lock().unwrap();
+        let Some(user) = users.get_mut(&record.id) else {
+            return Ok(());
+        };
+        for (field, value) in [
+            (&mut user.display_name, record.display_name),
+            (&mut user.bio, record.bio),
+            (&mut user.avatar_url, record.avatar_url),
+            (&mut user.location, record.location),
+            (&mut user.website, record.website),
+        ] {
+            if let Some(value) = value {
+                *field = Some(value.to_string()).filter(|value| !value.is_empty());
+            }
+        }
+        user.update_time = record.update_time;
+        Ok(())
+    }
+
+    async fn update_password_hash(
+        &self,
+        id: Id,
+        password_hash: &str,
+        update_time: UtcDateTime,
+    ) -> AppResult<()> {
+        if let Some(user) = self.users.lock().unwrap().get_mut(&id) {
+            user.password_hash = Some(password_hash.into());
+            user.update_time = update_time;
+        }
+        Ok(())
+    }
+}
@@ -1,29 +1,139 @@
-use bomboni_common::id::Id;
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::{query::list::ListQuery, schema::SchemaMapped, value::Value as FilterValue};
 use std::sync::Arc;
 
-use crate::error::AppResult;
+use grpc_sky_api::proto::UserRole;
 
+use crate::{error::AppResult, health::HealthCheck};
+
+#[cfg(test)]
+pub mod memory;
 pub mod mysql;
 
+#[derive(Debug, Clone)]
 pub struct UserRecord {
     pub id: Id,
     pub name: String,
-    pub password_hash: String,
+    /// Unset for users who signed up with an identity provider and haven't set a password.
+    pub password_hash: Option<String>,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub create_time: UtcDateTime,
+    pub update_time: UtcDateTime,
+    pub role: UserRole,
 }
 
 pub struct UserInsertRecord<'a> {
     pub id: Id,
     pub name: &'a str,
-    pub password_hash: String,
+    pub canonical_name: &'a str,
+    pub password_hash: Option<String>,
+    pub create_time: UtcDateTime,
+}
+
+pub struct UserIdentityInsertRecord<'a> {
+    pub user_id: Id,
+    pub issuer: &'a str,
+    pub subject: &'a str,
+    pub create_time: UtcDateTime,
+}
+
+/// Outcome of inserting a user together with an identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserWithIdentityInsertResult {
+    Inserted,
+    /// A user with the same canonical name already exists.
+    NameTaken,
+    /// The identity is already linked to a user.
+    IdentityTaken,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserRecordList {
+    pub items: Vec<UserRecord>,
+    pub next_item: Option<UserRecord>,
+    pub total_size: i64,
+}
+
+/// Fields set to `Some` are updated, and empty values are stored as `NULL`.
+pub struct UserProfileUpdateRecord<'a> {
+    pub id: Id,
+    pub display_name: Option<&'a str>,
+    pub bio: Option<&'a str>,
+    pub avatar_url: Option<&'a str>,
+    pub location: Option<&'a str>,
+    pub website: Option<&'a str>,
+    pub update_time: UtcDateTime,
 }
 
 #[tonic::async_trait]
 pub trait UserRepository {
+    /// Checks that the backend is reachable.
+    async fn health_check(&self) -> AppResult<()>;
+
     async fn select(&self, id: Id) -> AppResult<Option<UserRecord>>;
 
-    async fn select_by_name(&self, name: &str) -> AppResult<Option<UserRecord>>;
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<UserRecord>>;
+
+    async fn select_by_canonical_name(&self, canonical_name: &str)
+        -> AppResult<Option<UserRecord>>;
 
-    async fn insert(&self, record: UserInsertRecord<'_>) -> AppResult<()>;
+    /// Selects the user linked to an external identity.
+    async fn select_by_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> AppResult<Option<UserRecord>>;
+
+    /// Lists users, optionally restricted to canonical names starting with `canonical_name_prefix`.
+    async fn select_list(
+        &self,
+        query: &ListQuery,
+        canonical_name_prefix: Option<&str>,
+    ) -> AppResult<UserRecordList>;
+
+    /// Returns `false` if a user with the same canonical name already exists.
+    async fn insert(&self, record: UserInsertRecord<'_>) -> AppResult<bool>;
+
+    /// Returns `false` if the identity is already linked to a user.
+    async fn insert_identity(&self, record: UserIdentityInsertRecord<'_>) -> AppResult<bool>;
+
+    /// Inserts a user and links the identity to them, or neither.
+    async fn insert_with_identity(
+        &self,
+        record: UserInsertRecord<'_>,
+        identity_record: UserIdentityInsertRecord<'_>,
+    ) -> AppResult<UserWithIdentityInsertResult>;
+
+    async fn update_profile(&self, record: UserProfileUpdateRecord<'_>) -> AppResult<()>;
+
+    async fn update_password_hash(
+        &self,
+        id: Id,
+        password_hash: &str,
+        update_time: UtcDateTime,
+    ) -> AppResult<()>;
 }
 
 pub type UserRepositoryArc = Arc<dyn UserRepository + Send + Sync>;
+
+#[tonic::async_trait]
+impl HealthCheck for UserRepositoryArc {
+    async fn health_check(&self) -> AppResult<()> {
+        UserRepository::health_check(self.as_ref()).await
+    }
+}
+
+impl SchemaMapped for UserRecord {
+    fn get_field(&self, name: &str) -> FilterValue {
+        match name {
+            "id" => self.id.to_string().into(),
+            "name" => self.name.clone().into(),
+            "createTime" => self.create_time.into(),
+            _ => unimplemented!("SchemaMapped for UserRecord::{}", name),
+        }
+    }
+}
@@ -1,79 +1,501 @@
-use bomboni_common::id::Id;
-use mysql_async::{prelude::*, Pool};
-
+use bomboni_common::btree_map_into;
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::{
+    error::RequestError,
+    query::{error::QueryError, list::ListQuery},
+    sql::{QuerySqlBuilder, SqlArgumentStyle, SqlDialect, SqlRenameMap},
+};
+use mysql_async::{prelude::*, Params, Pool, TxOpts};
+use std::collections::BTreeMap;
 use tracing::info;
 
+use grpc_sky_api::{dto::UserDto, proto::UserRole};
+
 use crate::{
     error::AppResult,
-    user::repository::{UserInsertRecord, UserRecord, UserRepository},
+    metrics::get_mysql_conn,
+    user::repository::{
+        UserIdentityInsertRecord, UserInsertRecord, UserProfileUpdateRecord, UserRecord,
+        UserRecordList, UserRepository, UserWithIdentityInsertResult,
+    },
 };
 
 pub struct UserMySqlRepository {
     pool: Pool,
+    query_sql_builder: QuerySqlBuilder,
 }
 
+const USER_COLUMNS: &str = r#"
+    id,
+    name,
+    password_hash,
+    display_name,
+    bio,
+    avatar_url,
+    location,
+    website,
+    create_time,
+    update_time,
+    role
+"#;
+
+type UserRow = (
+    Id,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    UtcDateTime,
+    UtcDateTime,
+    String,
+);
+
+const ER_DUP_ENTRY: u16 = 1062;
+
+const INSERT_USER_QUERY: &str = r#"
+    INSERT INTO users (id, name, canonical_name, password_hash, create_time, update_time)
+    VALUES(:id, :name, :canonical_name, :password_hash, :create_time, :create_time)
+"#;
+
+const INSERT_IDENTITY_QUERY: &str = r#"
+    INSERT INTO user_identities (issuer, subject, user_id, create_time)
+    VALUES(:issuer, :subject, :user_id, :create_time)
+"#;
+
 impl UserMySqlRepository {
     pub fn new(pool: Pool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            query_sql_builder: {
+                let mut query_sql_builder =
+                    QuerySqlBuilder::new(SqlDialect::MySql, UserDto::get_schema());
+                query_sql_builder
+                    .set_argument_style(SqlArgumentStyle::Positional { symbol: "?".into() });
+                query_sql_builder.set_rename_map(SqlRenameMap {
+                    members: btree_map_into! {
+                        "createTime" => "create_time",
+                    },
+                    functions: BTreeMap::new(),
+                });
+                query_sql_builder
+            },
+        }
     }
 }
 
 #[tonic::async_trait]
 impl UserRepository for UserMySqlRepository {
+    async fn health_check(&self) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+        conn.ping().await?;
+        Ok(())
+    }
+
     async fn select(&self, id: Id) -> AppResult<Option<UserRecord>> {
-        let mut conn = self.pool.get_conn().await?;
+        let mut conn = get_mysql_conn(&self.pool).await?;
 
-        let users = r#"SELECT id, name, password_hash FROM users WHERE id = :id LIMIT 1"#
+        let users = format!(r#"SELECT {} FROM users WHERE id = :id LIMIT 1"#, USER_COLUMNS)
             .with(params! {
                 "id" => id.to_string(),
             })
-            .map(
-                &mut conn,
-                |(id, name, password_hash): (Id, String, String)| UserRecord {
-                    id,
-                    name,
-                    password_hash,
-                },
-            )
+            .map(&mut *conn, make_user_record)
             .await?;
 
         Ok(users.into_iter().next())
     }
 
-    async fn select_by_name(&self, name: &str) -> AppResult<Option<UserRecord>> {
-        let mut conn = self.pool.get_conn().await?;
+    async fn select_many(&self, ids: &[Id]) -> AppResult<Vec<UserRecord>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        let users = r#"SELECT id, name, password_hash FROM users WHERE name = :name LIMIT 1"#
-            .with(params! {
-                "name" => name,
-            })
-            .map(
-                &mut conn,
-                |(id, name, password_hash): (Id, String, String)| UserRecord {
-                    id,
-                    name,
-                    password_hash,
-                },
-            )
+        let statement = format!(
+            r#"SELECT {} FROM users WHERE id IN ({})"#,
+            USER_COLUMNS,
+            vec!["?"; ids.len()].join(", "),
+        );
+        let params: Vec<mysql_async::Value> = ids.iter().copied().map(Into::into).collect();
+
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let users = statement
+            .with(params)
+            .map(&mut *conn, make_user_record)
             .await?;
 
+        Ok(users)
+    }
+
+    async fn select_by_canonical_name(
+        &self,
+        canonical_name: &str,
+    ) -> AppResult<Option<UserRecord>> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let users = format!(
+            r#"SELECT {} FROM users WHERE canonical_name = :canonical_name LIMIT 1"#,
+            USER_COLUMNS
+        )
+        .with(params! {
+            "canonical_name" => canonical_name,
+        })
+        .map(&mut *conn, make_user_record)
+        .await?;
+
         Ok(users.into_iter().next())
     }
 
-    async fn insert(&self, record: UserInsertRecord<'_>) -> AppResult<()> {
-        let mut conn = self.pool.get_conn().await?;
+    async fn select_by_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> AppResult<Option<UserRecord>> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let users = format!(
+            r#"
+                SELECT {} FROM users
+                WHERE id = (
+                    SELECT user_id FROM user_identities WHERE issuer = :issuer AND subject = :subject
+                )
+                LIMIT 1
+            "#,
+            USER_COLUMNS
+        )
+        .with(params! {
+            "issuer" => issuer,
+            "subject" => subject,
+        })
+        .map(&mut *conn, make_user_record)
+        .await?;
+
+        Ok(users.into_iter().next())
+    }
+
+    async fn select_list(
+        &self,
+        query: &ListQuery,
+        canonical_name_prefix: Option<&str>,
+    ) -> AppResult<UserRecordList> {
+        let query_statement = self
+            .query_sql_builder
+            .build_list(query)
+            .map_err(|err: QueryError| RequestError::generic(err))?;
+
+        // Prefix condition goes last so that its argument follows the query arguments
+        let name_prefix_condition = canonical_name_prefix.map(|canonical_name_prefix| {
+            (
+                "canonical_name LIKE ?",
+                format!("{}%", escape_like(canonical_name_prefix)),
+            )
+        });
+        let make_where_clause = |where_clause: Option<&String>| {
+            let conditions: Vec<_> = where_clause
+                .map(|where_clause| format!("({})", where_clause))
+                .into_iter()
+                .chain(
+                    name_prefix_condition
+                        .as_ref()
+                        .map(|(condition, _)| condition.to_string()),
+                )
+                .collect();
+            if conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            }
+        };
+
+        let statement = format!(
+            r#"
+                SELECT {}
+                FROM users
+                {}
+                ORDER BY {}
+                {}
+            "#,
+            USER_COLUMNS,
+            make_where_clause(query_statement.paged_where_clause.as_ref()),
+            if let Some(order_by_clause) = query_statement.order_by_clause.as_ref() {
+                order_by_clause
+            } else {
+                "id"
+            },
+            &query_statement.paged_limit_clause,
+        );
+
+        let total_count_statement = format!(
+            r#"
+                SELECT COUNT(id)
+                FROM users
+                {}
+            "#,
+            make_where_clause(query_statement.where_clause.as_ref()),
+        );
+
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let mut items = {
+            let params: Vec<mysql_async::Value> = query_statement
+                .paged_arguments
+                .into_iter()
+                .map(Into::into)
+                .chain(
+                    name_prefix_condition
+                        .as_ref()
+                        .map(|(_, pattern)| pattern.as_str().into()),
+                )
+                .collect();
+
+            statement
+                .with(params)
+                .map(&mut *conn, make_user_record)
+                .await?
+        };
+
+        let total_count_result = {
+            let params: Vec<mysql_async::Value> = query_statement
+                .arguments
+                .into_iter()
+                .map(Into::into)
+                .chain(
+                    name_prefix_condition
+                        .as_ref()
+                        .map(|(_, pattern)| pattern.as_str().into()),
+                )
+                .collect();
+
+            total_count_statement
+                .with(params)
+                .map(&mut *conn, |(total_count,): (i64,)| total_count)
+                .await?
+        };
+
+        let total_size = total_count_result.first().copied().unwrap_or_default();
+        let next_item = if items.len() > query.page_size as usize {
+            items.pop()
+        } else {
+            None
+        };
+
+        Ok(UserRecordList {
+            items,
+            next_item,
+            total_size,
+        })
+    }
+
+    async fn insert(&self, record: UserInsertRecord<'_>) -> AppResult<bool> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        let result = INSERT_USER_QUERY
+            .with(make_user_params(&record))
+            .ignore(&mut *conn)
+            .await;
+        if !check_duplicate_entry(result)? {
+            return Ok(false);
+        }
+
+        info!("inserted user: {:?}", record.id);
+
+        Ok(true)
+    }
+
+    async fn insert_identity(&self, record: UserIdentityInsertRecord<'_>) -> AppResult<bool> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
 
-        r#"INSERT INTO users (id, name, password_hash) VALUES(:id, :name, :password_hash)"#
+        let result = INSERT_IDENTITY_QUERY
+            .with(make_identity_params(&record))
+            .ignore(&mut *conn)
+            .await;
+        if !check_duplicate_entry(result)? {
+            return Ok(false);
+        }
+
+        info!("linked user identity: {:?}", record.user_id);
+
+        Ok(true)
+    }
+
+    async fn insert_with_identity(
+        &self,
+        record: UserInsertRecord<'_>,
+        identity_record: UserIdentityInsertRecord<'_>,
+    ) -> AppResult<UserWithIdentityInsertResult> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+        let mut tx = conn.start_transaction(TxOpts::default()).await?;
+
+        let result = INSERT_USER_QUERY
+            .with(make_user_params(&record))
+            .ignore(&mut tx)
+            .await;
+        if !check_duplicate_entry(result)? {
+            tx.rollback().await?;
+            return Ok(UserWithIdentityInsertResult::NameTaken);
+        }
+
+        let result = INSERT_IDENTITY_QUERY
+            .with(make_identity_params(&identity_record))
+            .ignore(&mut tx)
+            .await;
+        if !check_duplicate_entry(result)? {
+            tx.rollback().await?;
+            return Ok(UserWithIdentityInsertResult::IdentityTaken);
+        }
+
+        tx.commit().await?;
+
+        info!("inserted user with identity: {:?}", record.id);
+
+        Ok(UserWithIdentityInsertResult::Inserted)
+    }
+
+    async fn update_profile(&self, record: UserProfileUpdateRecord<'_>) -> AppResult<()> {
+        let mut assignments = vec!["update_time = ?"];
+        let mut params: Vec<mysql_async::Value> = vec![record.update_time.into()];
+
+        for (assignment, value) in [
+            ("display_name = ?", record.display_name),
+            ("bio = ?", record.bio),
+            ("avatar_url = ?", record.avatar_url),
+            ("location = ?", record.location),
+            ("website = ?", record.website),
+        ] {
+            if let Some(value) = value {
+                assignments.push(assignment);
+                params.push(if value.is_empty() {
+                    mysql_async::Value::NULL
+                } else {
+                    value.into()
+                });
+            }
+        }
+        params.push(record.id.into());
+
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        format!(r#"UPDATE users SET {} WHERE id = ?"#, assignments.join(", "))
+            .with(params)
+            .ignore(&mut *conn)
+            .await?;
+
+        info!("updated user profile: {:?}", record.id);
+
+        Ok(())
+    }
+
+    async fn update_password_hash(
+        &self,
+        id: Id,
+        password_hash: &str,
+        update_time: UtcDateTime,
+    ) -> AppResult<()> {
+        let mut conn = get_mysql_conn(&self.pool).await?;
+
+        r#"UPDATE users SET password_hash = :password_hash, update_time = :update_time WHERE id = :id"#
             .with(params! {
-                "id" => record.id,
-                "name" => record.name,
-                "password_hash" => record.password_hash,
+                "id" => id,
+                "password_hash" => password_hash,
+                "update_time" => update_time,
             })
-            .ignore(&mut conn)
+            .ignore(&mut *conn)
             .await?;
 
-        info!("inserted user: {:?}", record.id);
+        info!("updated user password: {:?}", id);
 
         Ok(())
     }
 }
+
+fn make_user_params(record: &UserInsertRecord<'_>) -> Params {
+    params! {
+        "id" => record.id,
+        "name" => record.name,
+        "canonical_name" => record.canonical_name,
+        "password_hash" => record.password_hash.as_deref(),
+        "create_time" => record.create_time,
+    }
+}
+
+fn make_identity_params(record: &UserIdentityInsertRecord<'_>) -> Params {
+    params! {
+        "issuer" => record.issuer,
+        "subject" => record.subject,
+        "user_id" => record.user_id,
+        "create_time" => record.create_time,
+    }
+}
+
+/// Returns `false` if the statement failed on a unique key.
+fn check_duplicate_entry(result: Result<(), mysql_async::Error>) -> AppResult<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(mysql_async::Error::Server(err)) if err.code == ER_DUP_ENTRY => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn make_user_record(
+    (
+        id,
+        name,
+        password_hash,
+        display_name,
+        bio,
+        avatar_url,
+        location,
+        website,
+        create_time,
+        update_time,
+        role,
+    ): UserRow,
+) -> UserRecord {
+    UserRecord {
+        id,
+        name,
+        password_hash,
+        display_name,
+        bio,
+        avatar_url,
+        location,
+        website,
+        create_time,
+        update_time,
+        role: parse_role(&role),
+    }
+}
+
+/// Unknown roles fall back to the least privileged one.
+fn parse_role(value: &str) -> UserRole {
+    match value {
+        "moderator" => UserRole::Moderator,
+        "admin" => UserRole::Admin,
+        _ => UserRole::User,
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_like_patterns() {
+        assert_eq!(escape_like("tester"), "tester");
+        assert_eq!(escape_like("a%b_c\\d"), "a\\%b\\_c\\\\d");
+        assert_eq!(format!("{}%", escape_like("%_")), "\\%\\_%");
+    }
+}
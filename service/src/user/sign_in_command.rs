@@ -0,0 +1,107 @@
+use bomboni_common::id::Id;
+use bomboni_proto::google::{
+    protobuf::{Any, Duration},
+    rpc::RetryInfo,
+};
+use bomboni_request::error::RequestError;
+use std::{net::IpAddr, sync::Arc};
+use tonic::Code;
+
+use grpc_sky_api::{
+    error::UserError, proto::user_error::UserErrorReason, user_name::canonicalize_user_name,
+};
+
+use crate::{
+    auth::token::TokenManager,
+    error::{from_proto_status, make_proto_status, AppResult},
+    user::{
+        password::verify_password_or_dummy,
+        repository::UserRepositoryArc,
+        sign_in_throttle::{SignInLockout, SignInThrottle},
+    },
+};
+
+pub struct SignInCommand {
+    user_repository: UserRepositoryArc,
+    sign_in_throttle: Arc<SignInThrottle>,
+    token_manager: Arc<TokenManager>,
+}
+
+pub struct SignInCommandInput<'a> {
+    pub name: &'a str,
+    pub password: &'a str,
+    pub remote_address: Option<IpAddr>,
+}
+
+pub struct SignInCommandOutput {
+    pub user_id: Id,
+    pub access_token: String,
+}
+
+impl SignInCommand {
+    pub fn new(
+        user_repository: UserRepositoryArc,
+        sign_in_throttle: Arc<SignInThrottle>,
+        token_manager: Arc<TokenManager>,
+    ) -> Self {
+        Self {
+            user_repository,
+            sign_in_throttle,
+            token_manager,
+        }
+    }
+
+    pub async fn execute(&self, input: SignInCommandInput<'_>) -> AppResult<SignInCommandOutput> {
+        let canonical_name = canonicalize_user_name(input.name);
+
+        if let Err(lockout) = self
+            .sign_in_throttle
+            .check(&canonical_name, input.remote_address)
+        {
+            return Err(make_lockout_status(input.name, lockout).into());
+        }
+
+        let user = self
+            .user_repository
+            .select_by_canonical_name(&canonical_name)
+            .await?;
+        // Takes as long for unknown names and users without passwords as for incorrect passwords
+        let password_hash = user.as_ref().and_then(|user| user.password_hash.as_deref());
+        let verified = verify_password_or_dummy(input.password, password_hash);
+        let Some(user) = user.filter(|_| verified) else {
+            // Unknown names count as failures too, so that they can't be told apart
+            self.sign_in_throttle
+                .record_failure(&canonical_name, input.remote_address);
+            return Err(UserError::new(UserErrorReason::IncorrectCredentials).into());
+        };
+
+        self.sign_in_throttle.record_success(&canonical_name);
+
+        Ok(SignInCommandOutput {
+            user_id: user.id,
+            access_token: self.token_manager.issue(user.id, user.role)?,
+        })
+    }
+}
+
+fn make_lockout_status(name: &str, lockout: SignInLockout) -> tonic::Status {
+    let (reason, retry_after) = match lockout {
+        SignInLockout::Account { retry_after } => (UserErrorReason::AccountLocked, retry_after),
+        SignInLockout::Address { retry_after } => (UserErrorReason::TooManyAttempts, retry_after),
+    };
+    let mut status = make_proto_status(
+        Code::ResourceExhausted,
+        RequestError::generic(UserError::new(reason).with_user_name(name)),
+    );
+    status.details.push(
+        Any::pack_from(&RetryInfo {
+            retry_delay: Some(Duration {
+                // Round up, so that clients don't retry too early
+                seconds: retry_after.as_secs_f64().ceil() as i64,
+                nanos: 0,
+            }),
+        })
+        .unwrap(),
+    );
+    from_proto_status(status)
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::config::SignInThrottleConfig;
+
+/// Tracks failed sign-in attempts per user name and per remote address.
+///
+/// State is kept in memory, so every service instance throttles independently.
+pub struct SignInThrottle {
+    config: SignInThrottleConfig,
+    attempts: Mutex<HashMap<ThrottleKey, FailedAttempts>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ThrottleKey {
+    Name(String),
+    Address(IpAddr),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FailedAttempts {
+    count: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignInLockout {
+    /// Too many failed attempts for the user name.
+    Account { retry_after: Duration },
+    /// Too many failed attempts from the remote address.
+    Address { retry_after: Duration },
+}
+
+impl SignInThrottle {
+    pub fn new(config: SignInThrottleConfig) -> Self {
+        Self {
+            config,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks whether a sign-in attempt is currently allowed.
+    pub fn check(
+        &self,
+        canonical_name: &str,
+        address: Option<IpAddr>,
+    ) -> Result<(), SignInLockout> {
+        self.check_at(canonical_name, address, Instant::now())
+    }
+
+    pub fn record_failure(&self, canonical_name: &str, address: Option<IpAddr>) {
+        self.record_failure_at(canonical_name, address, Instant::now());
+    }
+
+    /// Forgets failed attempts for the user name after a successful sign-in.
+    pub fn record_success(&self, canonical_name: &str) {
+        self.unlock(canonical_name);
+    }
+
+    pub fn unlock(&self, canonical_name: &str) {
+        self.attempts
+            .lock()
+            .unwrap()
+            .remove(&ThrottleKey::Name(canonical_name.into()));
+    }
+
+    fn check_at(
+        &self,
+        canonical_name: &str,
+        address: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), SignInLockout> {
+        let attempts = self.attempts.lock().unwrap();
+        let retry_after = |key: &ThrottleKey| {
+            attempts
+                .get(key)
+                .and_then(|attempts| attempts.locked_until)
+                .filter(|locked_until| *locked_until > now)
+                .map(|locked_until| locked_until - now)
+        };
+
+        if let Some(retry_after) = retry_after(&ThrottleKey::Name(canonical_name.into())) {
+            return Err(SignInLockout::Account { retry_after });
+        }
+        if let Some(retry_after) =
+            address.and_then(|address| retry_after(&ThrottleKey::Address(address)))
+        {
+            return Err(SignInLockout::Address { retry_after });
+        }
+        Ok(())
+    }
+
+    fn record_failure_at(&self, canonical_name: &str, address: Option<IpAddr>, now: Instant) {
+        let mut attempts = self.attempts.lock().unwrap();
+        let reset_after = Duration::from_secs(self.config.reset_after_seconds);
+
+        // Drop stale entries so that the map doesn't grow unbounded
+        attempts.retain(|_, attempts| {
+            now.duration_since(attempts.last_failure) < reset_after
+                || attempts
+                    .locked_until
+                    .is_some_and(|locked_until| locked_until > now)
+        });
+
+        let keys = std::iter::once((
+            ThrottleKey::Name(canonical_name.into()),
+            self.config.max_failed_attempts,
+        ))
+        .chain(address.map(|address| {
+            (
+                ThrottleKey::Address(address),
+                self.config.max_failed_attempts_per_address,
+            )
+        }));
+
+        for (key, max_failed_attempts) in keys {
+            let entry = attempts.entry(key).or_insert(FailedAttempts {
+                count: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            entry.count += 1;
+            entry.last_failure = now;
+            if entry.count >= max_failed_attempts {
+                entry.locked_until =
+                    Some(now + self.lockout_duration(entry.count - max_failed_attempts));
+            }
+        }
+    }
+
+    /// Exponential backoff, capped at the maximum lockout.
+    fn lockout_duration(&self, excess_attempts: u32) -> Duration {
+        let seconds = self
+            .config
+            .base_lockout_seconds
+            .saturating_mul(1u64.checked_shl(excess_attempts).unwrap_or(u64::MAX))
+            .min(self.config.max_lockout_seconds);
+        Duration::from_secs(seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_with_backoff() {
+        let throttle = SignInThrottle::new(SignInThrottleConfig {
+            max_failed_attempts: 3,
+            max_failed_attempts_per_address: 10,
+            base_lockout_seconds: 30,
+            max_lockout_seconds: 100,
+            reset_after_seconds: 900,
+        });
+        let address: IpAddr = "127.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            throttle.record_failure_at("tester", Some(address), now);
+        }
+        assert!(throttle.check_at("tester", Some(address), now).is_ok());
+
+        throttle.record_failure_at("tester", Some(address), now);
+        assert_eq!(
+            throttle.check_at("tester", Some(address), now),
+            Err(SignInLockout::Account {
+                retry_after: Duration::from_secs(30)
+            })
+        );
+        assert!(throttle
+            .check_at("tester", Some(address), now + Duration::from_secs(30))
+            .is_ok());
+        assert!(throttle.check_at("other", Some(address), now).is_ok());
+
+        throttle.record_failure_at("tester", Some(address), now);
+        assert_eq!(
+            throttle.check_at("tester", None, now),
+            Err(SignInLockout::Account {
+                retry_after: Duration::from_secs(60)
+            })
+        );
+        throttle.record_failure_at("tester", Some(address), now);
+        assert_eq!(
+            throttle.check_at("tester", None, now),
+            Err(SignInLockout::Account {
+                retry_after: Duration::from_secs(100)
+            })
+        );
+
+        throttle.unlock("tester");
+        assert!(throttle.check_at("tester", None, now).is_ok());
+    }
+}
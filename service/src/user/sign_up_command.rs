@@ -1,19 +1,25 @@
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2,
-};
-use bomboni_common::id::Id;
-use bomboni_request::error::CommonError;
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use prost::Name;
+use std::sync::Arc;
 
-use grpc_sky_api::{error::UserError, proto::user_error::UserErrorReason};
+use grpc_sky_api::{
+    error::UserError,
+    proto::{common_error::CommonErrorReason, user_error::UserErrorReason, SignUpRequest},
+    user_name::canonicalize_user_name,
+};
 
 use crate::{
     error::AppResult,
-    user::repository::{UserInsertRecord, UserRepositoryArc},
+    metrics::SIGN_UPS_TOTAL,
+    user::{
+        password::{hash_password, PasswordPolicy},
+        repository::{UserInsertRecord, UserRepositoryArc},
+    },
 };
 
 pub struct SignUpCommand {
     user_repository: UserRepositoryArc,
+    password_policy: Arc<PasswordPolicy>,
 }
 
 pub struct SignUpCommandInput<'a> {
@@ -26,41 +32,53 @@ pub struct SignUpCommandOutput {
 }
 
 impl SignUpCommand {
-    pub fn new(user_repository: UserRepositoryArc) -> Self {
-        Self { user_repository }
+    pub fn new(user_repository: UserRepositoryArc, password_policy: Arc<PasswordPolicy>) -> Self {
+        Self {
+            user_repository,
+            password_policy,
+        }
     }
 
     pub async fn execute(&self, input: SignUpCommandInput<'_>) -> AppResult<SignUpCommandOutput> {
-        // TODO: transaction
+        let canonical_name = canonicalize_user_name(input.name);
         if self
             .user_repository
-            .select_by_name(input.name)
+            .select_by_canonical_name(&canonical_name)
             .await?
             .is_some()
         {
-            return Err(CommonError::AlreadyExists.into());
+            return Err(make_name_taken_error(input.name, &canonical_name).into());
         }
 
-        if input.password.len() < 6 {
-            return Err(UserError::new(UserErrorReason::InvalidPassword).into());
-        }
+        self.password_policy
+            .validate(input.password, input.name, SignUpRequest::NAME, "password")?;
 
         let user_id = Id::generate();
+        let password_hash = hash_password(input.password)?;
 
-        let salt = SaltString::generate(&mut OsRng);
-        let password_hash = Argon2::default()
-            .hash_password(input.password.as_bytes(), &salt)
-            .map_err(|_| UserError::new(UserErrorReason::InvalidPassword))?
-            .to_string();
-
-        self.user_repository
+        // Unique canonical names also catch concurrent sign-ups
+        let inserted = self
+            .user_repository
             .insert(UserInsertRecord {
                 id: user_id,
-                name: input.name.into(),
-                password_hash,
+                name: input.name,
+                canonical_name: &canonical_name,
+                password_hash: Some(password_hash),
+                create_time: UtcDateTime::now(),
             })
             .await?;
+        if !inserted {
+            return Err(make_name_taken_error(input.name, &canonical_name).into());
+        }
+        ::metrics::counter!(SIGN_UPS_TOTAL, "method" => "password").increment(1);
 
         Ok(SignUpCommandOutput { user_id })
     }
 }
+
+fn make_name_taken_error(name: &str, canonical_name: &str) -> UserError {
+    UserError::new(CommonErrorReason::AlreadyExists)
+        .with_reason(UserErrorReason::InvalidName)
+        .with_user_name(name)
+        .with_canonical_name(canonical_name)
+}
@@ -0,0 +1,53 @@
+use bomboni_request::error::CommonError;
+use std::sync::Arc;
+
+use grpc_sky_api::user_name::canonicalize_user_name;
+
+use crate::{
+    auth::permission::Permission,
+    context::Context,
+    error::AppResult,
+    user::{repository::UserRepositoryArc, sign_in_throttle::SignInThrottle},
+};
+
+pub struct UnlockAccountCommand {
+    user_repository: UserRepositoryArc,
+    sign_in_throttle: Arc<SignInThrottle>,
+}
+
+pub struct UnlockAccountCommandInput<'a> {
+    pub name: &'a str,
+}
+
+impl UnlockAccountCommand {
+    pub const PERMISSION: Permission = Permission::UsersUnlock;
+
+    pub fn new(user_repository: UserRepositoryArc, sign_in_throttle: Arc<SignInThrottle>) -> Self {
+        Self {
+            user_repository,
+            sign_in_throttle,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: UnlockAccountCommandInput<'_>,
+    ) -> AppResult<()> {
+        context.authorize(Self::PERMISSION)?;
+
+        let canonical_name = canonicalize_user_name(input.name);
+        if self
+            .user_repository
+            .select_by_canonical_name(&canonical_name)
+            .await?
+            .is_none()
+        {
+            return Err(CommonError::ResourceNotFound.into());
+        }
+
+        self.sign_in_throttle.unlock(&canonical_name);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use bomboni_common::{date_time::UtcDateTime, id::Id};
+use bomboni_request::error::CommonError;
+
+use crate::{
+    context::{permission_denied, Context},
+    error::AppResult,
+    user::repository::{UserProfileUpdateRecord, UserRecord, UserRepositoryArc},
+};
+
+pub struct UpdateProfileCommand {
+    user_repository: UserRepositoryArc,
+}
+
+pub struct UpdateProfileCommandInput<'a> {
+    pub user_id: Option<Id>,
+    pub display_name: Option<&'a str>,
+    pub bio: Option<&'a str>,
+    pub avatar_url: Option<&'a str>,
+    pub location: Option<&'a str>,
+    pub website: Option<&'a str>,
+}
+
+pub struct UpdateProfileCommandOutput {
+    pub user: UserRecord,
+}
+
+impl UpdateProfileCommand {
+    pub fn new(user_repository: UserRepositoryArc) -> Self {
+        Self { user_repository }
+    }
+
+    pub async fn execute(
+        &self,
+        context: &Context,
+        input: UpdateProfileCommandInput<'_>,
+    ) -> AppResult<UpdateProfileCommandOutput> {
+        let user_id = context.authenticate()?;
+        // Users can only update their own profile
+        if input.user_id.is_some_and(|id| id != user_id) {
+            return Err(permission_denied(None));
+        }
+
+        self.user_repository
+            .update_profile(UserProfileUpdateRecord {
+                id: user_id,
+                display_name: input.display_name,
+                bio: input.bio,
+                avatar_url: input.avatar_url,
+                location: input.location,
+                website: input.website,
+                update_time: UtcDateTime::now(),
+            })
+            .await?;
+
+        let Some(user) = self.user_repository.select(user_id).await? else {
+            return Err(CommonError::ResourceNotFound.into());
+        };
+
+        Ok(UpdateProfileCommandOutput { user })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use grpc_sky_api::user_name::canonicalize_user_name;
+    use std::sync::Arc;
+    use tonic::{Code, Status};
+
+    use crate::user::repository::{memory::UserMemoryRepository, UserInsertRecord, UserRepository};
+
+    use super::*;
+
+    async fn insert_user(user_repository: &UserMemoryRepository, name: &str) -> Id {
+        let id = Id::generate();
+        user_repository
+            .insert(UserInsertRecord {
+                id,
+                name,
+                canonical_name: &canonicalize_user_name(name),
+                password_hash: None,
+                create_time: UtcDateTime::now(),
+            })
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn update_own_profile_only() {
+        let user_repository = Arc::new(UserMemoryRepository::default());
+        let user_id = insert_user(&user_repository, "tester").await;
+        let other_id = insert_user(&user_repository, "other").await;
+        let command = UpdateProfileCommand::new(user_repository.clone());
+        let context = Context::from_user(user_id);
+
+        let output = command
+            .execute(
+                &context,
+                UpdateProfileCommandInput {
+                    user_id: Some(user_id),
+                    display_name: Some("Tester"),
+                    bio: None,
+                    avatar_url: None,
+                    location: None,
+                    website: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.user.display_name.as_deref(), Some("Tester"));
+
+        let err = command
+            .execute(
+                &context,
+                UpdateProfileCommandInput {
+                    user_id: Some(other_id),
+                    display_name: Some("Other"),
+                    bio: None,
+                    avatar_url: None,
+                    location: None,
+                    website: None,
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(Status::from(err).code(), Code::PermissionDenied);
+        let other = user_repository.select(other_id).await.unwrap().unwrap();
+        assert_eq!(other.display_name, None);
+
+        let err = command
+            .execute(
+                &Context::anonymous(),
+                UpdateProfileCommandInput {
+                    user_id: Some(user_id),
+                    display_name: Some("Anonymous"),
+                    bio: None,
+                    avatar_url: None,
+                    location: None,
+                    website: None,
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(Status::from(err).code(), Code::Unauthenticated);
+    }
+}